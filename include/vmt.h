@@ -0,0 +1,50 @@
+/* C interface of the vmt crate, built with the ffi feature. */
+#ifndef VMT_H
+#define VMT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define VMT_OK 0
+#define VMT_NULL_POINTER (-1)
+#define VMT_UNKNOWN_ALGORITHM (-2)
+#define VMT_BUFFER_TOO_SMALL (-3)
+#define VMT_NOT_FOUND (-4)
+#define VMT_PANIC (-5)
+
+#define VMT_SHA1 1
+#define VMT_SHA256 2
+#define VMT_SHA384 3
+#define VMT_SHA512 4
+
+typedef struct VmtTree VmtTree;
+
+/* Builds a tree over count values, value i is lens[i] bytes at values[i].
+ * The caller owns the result and releases it with vmt_tree_free.
+ * Returns NULL for an unknown algo_id or a null pointer. */
+VmtTree *vmt_tree_new(const uint8_t *const *values, const size_t *lens, size_t count, int algo_id);
+
+/* Copies the root to out and its length to written.
+ * When out_len is too small only written is set and VMT_BUFFER_TOO_SMALL is returned. */
+int vmt_tree_root(const VmtTree *tree, uint8_t *out, size_t out_len, size_t *written);
+
+/* Copies the proof of the first occurrence of the value to out, as vmt_tree_root does.
+ * Returns VMT_NOT_FOUND when the value is not in the tree. */
+int vmt_tree_build_proof(const VmtTree *tree, const uint8_t *value, size_t value_len,
+                         uint8_t *out, size_t out_len, size_t *written);
+
+/* Returns 1 when the proof leads to the root, 0 when it does not, a negative code on error. */
+int vmt_verify_proof(const uint8_t *root, size_t root_len, const uint8_t *proof, size_t proof_len, int algo_id);
+
+/* Releases the tree, NULL is ignored. */
+void vmt_tree_free(VmtTree *tree);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
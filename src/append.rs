@@ -0,0 +1,135 @@
+use std::convert::AsRef;
+
+use error::Error;
+use hasher::MerkleHasher;
+use leaf_map::LeafMap;
+use {calculate_vec_len, level_layout, MerkleTree};
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn push<T: AsRef<[u8]>>(&mut self, value: &T) {
+        let hash = self.scheme.hash_leaf(value.as_ref(), &self.hasher);
+        let count = self.items_count;
+        self.resize_leafs(count, &hash);
+    }
+
+    // Upper levels are rebuilt once for all the values
+    pub fn extend<T: AsRef<[u8]>, I: IntoIterator<Item = T>>(&mut self, values: I) {
+        let mut hashes = vec![];
+        for value in values {
+            hashes.extend_from_slice(&self.scheme.hash_leaf(value.as_ref(), &self.hasher));
+        }
+        if !hashes.is_empty() {
+            let count = self.items_count;
+            self.resize_leafs(count, &hashes);
+        }
+    }
+
+    // Leafs of other go after the leafs of this tree, nodes of this tree left from them are kept.
+    // The result has a map if any of the trees had one.
+    pub fn merge(mut self, mut other: MerkleTree<H>) -> Result<MerkleTree<H>, Error> where H: PartialEq {
+        if self.hasher != other.hasher {
+            return Err(Error::AlgorithmMismatch);
+        }
+        if self.scheme != other.scheme {
+            return Err(Error::SchemeMismatch);
+        }
+        if self.map.is_none() {
+            if let Some(map) = other.map.take() { // Keys are made the same way as in other
+                let len = self.hasher.output_len();
+                self.map = Some(LeafMap::with_keys(&self.array[..self.items_count * len], len, map.into_keys()));
+            }
+        }
+        let count = self.items_count;
+        self.resize_leafs(count, &other.array[..other.items_count * other.hasher.output_len()]);
+        Ok(self)
+    }
+
+    // Removes the last leaf and returns its hash
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        if self.items_count == 0 {
+            return None;
+        }
+        let len = self.hasher.output_len();
+        let last = self.items_count - 1;
+        let hash = self.array[last * len..(last + 1) * len].to_vec();
+        self.truncate(last);
+        Some(hash)
+    }
+
+    // Keeps the first count leafs, does nothing if the tree is not longer than that
+    pub fn truncate(&mut self, count: usize) {
+        if count < self.items_count {
+            self.resize_leafs(count, &[]);
+        }
+    }
+
+    // Keeps the first kept leafs and appends hashes after them. Levels are contiguous, so every
+    // level moves to its new place within the same array; only the nodes on the right edge that
+    // changed are hashed again.
+    fn resize_leafs(&mut self, kept: usize, hashes: &[u8]) {
+        let len = self.hasher.output_len();
+        let old_count = self.items_count;
+        let count = kept + hashes.len() / len;
+        if let Some(ref mut map) = self.map {
+            for (i, leaf) in self.array[kept * len..old_count * len].chunks(len).enumerate() {
+                map.remove(leaf, kept + i); // Earlier occurrences stay
+            }
+        }
+        let old_layout = level_layout(old_count, self.scheme);
+        let layout = level_layout(count, self.scheme);
+
+        // Full subtrees keep their hashes. Levels move right when the tree grows, so the top
+        // one goes first, and left when it shrinks, so the bottom one does.
+        let moves: Vec<(usize, usize, usize)> = old_layout.iter().zip(layout.iter()).enumerate()
+            .map(|(level, (&(old_start, _), &(start, _)))| (old_start * len, start * len, (kept >> level) * len))
+            .collect();
+        let size = calculate_vec_len(count, &self.hasher, self.scheme);
+        if count > old_count {
+            self.array.resize(size);
+            for &(from, to, bytes) in moves.iter().rev() {
+                self.array.copy_within(from..from + bytes, to);
+            }
+        } else {
+            for &(from, to, bytes) in moves.iter() {
+                self.array.copy_within(from..from + bytes, to);
+            }
+            self.array.resize(size);
+        }
+        let array = &mut *self.array;
+        array[kept * len..count * len].copy_from_slice(hashes);
+
+        for level in 0..layout.len() {
+            let (start, level_len) = layout[level];
+            if level > 0 {
+                let (child_start, child_len) = layout[level - 1];
+                for i in (kept >> level)..level_len {
+                    let left = (child_start + 2 * i) * len;
+                    let node = if 2 * i + 1 < child_len {
+                        self.scheme.hash_pair(&array[left..left + len], &array[left + len..left + 2 * len], &self.hasher)
+                    } else {
+                        self.scheme.hash_lone(&array[left..left + len], &self.hasher)
+                    };
+                    array[(start + i) * len..(start + i + 1) * len].copy_from_slice(&node);
+                }
+            }
+            if self.scheme.pads() && level_len & 1 == 1 && !self.scheme.is_root_level(level, level_len) {
+                self.scheme.write_padding(array, (start + level_len - 1) * len, len);
+            }
+        }
+
+        if let Some(ref mut map) = self.map {
+            for (i, hash) in hashes.chunks(len).enumerate() {
+                map.insert(hash, kept + i);
+            }
+        }
+        self.items_count = count;
+        self.height = layout.len();
+    }
+}
+
+// For generic code that appends to collections, levels are rebuilt once the values run out
+impl<T: AsRef<[u8]>, H: MerkleHasher> Extend<T> for MerkleTree<H> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        MerkleTree::extend(self, values)
+    }
+}
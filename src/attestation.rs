@@ -0,0 +1,55 @@
+use ring::signature::{self, Ed25519KeyPair, ED25519};
+use untrusted::Input;
+
+use hasher::NamedHasher;
+use MerkleTree;
+
+const MAGIC: &[u8; 8] = b"VMT-ROOT";
+const VERSION: u8 = 1;
+
+// Root of a tree signed with Ed25519. The signature covers signed_bytes, never the struct itself
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedRoot {
+    pub algorithm: String, // Name of the digest algorithm, as in the stored formats
+    pub leaf_count: usize,
+    pub timestamp: u64, // Given by the signer, its unit is up to the parties
+    pub root: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl SignedRoot {
+    // Layout: magic "VMT-ROOT", version 1, algorithm name length as u8 and name, leaf count and
+    // timestamp as little endian u64, root length as u8 and root. Fixed for version 1.
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 3 + self.algorithm.len() + 16 + self.root.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&[VERSION, self.algorithm.len() as u8]);
+        bytes.extend_from_slice(self.algorithm.as_bytes());
+        bytes.extend_from_slice(&(self.leaf_count as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
+        bytes.push(self.root.len() as u8);
+        bytes.extend_from_slice(&self.root);
+        bytes
+    }
+
+    // Fields that do not fit their length byte are never signed by sign_root
+    pub fn verify(&self, public_key: &[u8]) -> bool {
+        self.algorithm.len() <= u8::MAX as usize && self.root.len() <= u8::MAX as usize
+            && signature::verify(&ED25519, Input::from(public_key), Input::from(&self.signed_bytes()),
+                                 Input::from(&self.signature)).is_ok()
+    }
+}
+
+impl<H: NamedHasher> MerkleTree<H> {
+    pub fn sign_root(&self, key_pair: &Ed25519KeyPair, timestamp: u64) -> SignedRoot {
+        let mut signed = SignedRoot {
+            algorithm: self.hasher.name().to_string(),
+            leaf_count: self.items_count,
+            timestamp,
+            root: self.get_root().to_vec(),
+            signature: vec![],
+        };
+        signed.signature = key_pair.sign(&signed.signed_bytes()).as_ref().to_vec();
+        signed
+    }
+}
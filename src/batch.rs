@@ -0,0 +1,34 @@
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use hasher::MerkleHasher;
+use proof::Proof;
+use MerkleTree;
+
+// Many proofs against one tree, one result per proof in the order given. With the rayon feature
+// the proofs are checked on its thread pool, one after another otherwise.
+impl<H: MerkleHasher + Sync> MerkleTree<H> {
+    // Each proof as validate_bytes takes it
+    pub fn validate_batch<P: AsRef<[u8]> + Sync>(&self, proofs: &[P]) -> Vec<bool> {
+        in_order(proofs, |proof| self.validate_bytes(proof.as_ref()))
+    }
+
+    // Each value with its proof as validate_value takes them
+    pub fn validate_values_batch<T, P>(&self, pairs: &[(T, P)]) -> Vec<bool>
+        where T: AsRef<[u8]> + Sync, P: AsRef<[u8]> + Sync {
+        in_order(pairs, |(value, proof)| match Proof::from_bytes(proof.as_ref(), self.hasher.output_len()) {
+            Ok(proof) => self.validate_value(value, &proof),
+            Err(_) => false,
+        })
+    }
+}
+
+#[cfg(feature = "rayon")]
+fn in_order<T: Sync, F: Fn(&T) -> bool + Send + Sync>(items: &[T], f: F) -> Vec<bool> {
+    items.par_iter().map(f).collect()
+}
+
+#[cfg(not(feature = "rayon"))]
+fn in_order<T, F: Fn(&T) -> bool>(items: &[T], f: F) -> Vec<bool> {
+    items.iter().map(f).collect()
+}
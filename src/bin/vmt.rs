@@ -0,0 +1,140 @@
+// Merkle roots and proofs over file contents, one leaf per file
+extern crate ring;
+extern crate vmt;
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use ring::digest::{Algorithm, SHA256};
+
+use vmt::{hex, MerkleTree, NamedHasher, RingHasher};
+
+const USAGE: &str = "usage: vmt [--algo sha1|sha256|sha384|sha512|sha512_256] root <files...>
+       vmt [--algo sha1|sha256|sha384|sha512|sha512_256] proof <files...> --target <file>
+       vmt [--algo sha1|sha256|sha384|sha512|sha512_256] verify --root <hex> --proof <file-or-hex>";
+
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CliError::Usage(ref message) => write!(f, "{}\n{}", message, USAGE),
+            CliError::Failed(ref message) => write!(f, "{}", message),
+        }
+    }
+}
+
+// Positional arguments and the values of --options, in the order given
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Args, CliError> {
+        let mut result = Args { positional: vec![], options: vec![] };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--algo" | "--target" | "--root" | "--proof" => {
+                    let value = iter.next().ok_or_else(|| CliError::Usage(format!("{} needs a value", arg)))?;
+                    result.options.push((arg[2..].to_string(), value.clone()));
+                }
+                _ if arg.starts_with("--") => return Err(CliError::Usage(format!("unknown option {}", arg))),
+                _ => result.positional.push(arg.clone()),
+            }
+        }
+        Ok(result)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|o| o.0 == name).map(|o| o.1.as_str())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, CliError> {
+        self.option(name).ok_or_else(|| CliError::Usage(format!("--{} is required", name)))
+    }
+
+    fn algorithm(&self) -> Result<&'static Algorithm, CliError> {
+        match self.option("algo") {
+            None => Ok(&SHA256),
+            Some(name) => RingHasher::from_name(name)
+                .map(|hasher| hasher.algorithm())
+                .ok_or_else(|| CliError::Usage(format!("unknown algorithm '{}'", name))),
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(output) => println!("{}", output),
+        Err(e) => {
+            eprintln!("vmt: {}", e);
+            process::exit(match e {
+                CliError::Usage(_) => 2,
+                CliError::Failed(_) => 1,
+            });
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<String, CliError> {
+    let args = Args::parse(args)?;
+    let algo = args.algorithm()?;
+    let (command, files) = match args.positional.split_first() {
+        Some((command, files)) => (command.as_str(), files),
+        None => return Err(CliError::Usage("no command".to_string())),
+    };
+    match command {
+        "root" => Ok(hex::encode(tree(files, algo)?.get_root())),
+        "proof" => {
+            let tree = tree(files, algo)?;
+            let target = args.required("target")?;
+            let proof = tree.build_proof(&read(target)?)
+                .ok_or_else(|| CliError::Failed(format!("{} is not one of the files", target)))?;
+            Ok(proof.to_hex())
+        }
+        "verify" => {
+            if !files.is_empty() {
+                return Err(CliError::Usage("verify takes no files".to_string()));
+            }
+            let root = decode(args.required("root")?, "root")?;
+            let proof = args.required("proof")?;
+            let proof = if Path::new(proof).is_file() {
+                let text = String::from_utf8(read(proof)?).map_err(|_| CliError::Failed(format!("{} is not hex", proof)))?;
+                decode(text.trim(), "proof")?
+            } else {
+                decode(proof, "proof")?
+            };
+            if vmt::verify_proof(&root, &proof, algo) {
+                Ok("valid".to_string())
+            } else {
+                Err(CliError::Failed("proof does not match the root".to_string()))
+            }
+        }
+        _ => Err(CliError::Usage(format!("unknown command '{}'", command))),
+    }
+}
+
+fn tree(files: &[String], algo: &'static Algorithm) -> Result<MerkleTree, CliError> {
+    if files.is_empty() {
+        return Err(CliError::Usage("no files".to_string()));
+    }
+    let values = files.iter().map(|f| read(f)).collect::<Result<Vec<_>, _>>()?;
+    Ok(MerkleTree::new(&values, algo))
+}
+
+fn read(path: &str) -> Result<Vec<u8>, CliError> {
+    fs::read(path).map_err(|e| CliError::Failed(format!("{}: {}", path, e)))
+}
+
+fn decode(text: &str, what: &str) -> Result<Vec<u8>, CliError> {
+    hex::decode(text).map_err(|e| CliError::Failed(format!("{} is not hex: {}", what, e)))
+}
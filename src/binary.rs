@@ -0,0 +1,129 @@
+use std::io::{self, Read, Write};
+
+use error::LoadError;
+use hasher::NamedHasher;
+use nodes::Nodes;
+use scheme::Scheme;
+use {calculate_vec_len, MerkleTree};
+
+const MAGIC: &[u8; 4] = b"VMT\0";
+const VERSION: u8 = 1;
+const MAP_FLAG: u8 = 1;
+
+// Layout: magic, version, algorithm name length and name, scheme flags, options,
+// leaf count, height and node array length as little endian u64, node array
+pub(crate) struct Header {
+    pub(crate) name: String,
+    pub(crate) scheme: Scheme,
+    pub(crate) use_map: bool,
+    pub(crate) items_count: usize,
+    pub(crate) height: usize,
+    pub(crate) array_len: usize,
+}
+
+impl Header {
+    pub(crate) fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let name = self.name.as_bytes();
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, name.len() as u8])?;
+        writer.write_all(name)?;
+        writer.write_all(&[self.scheme.flags(), if self.use_map { MAP_FLAG } else { 0 }])?;
+        for n in &[self.items_count, self.height, self.array_len] {
+            writer.write_all(&(*n as u64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read<R: Read>(reader: &mut R) -> Result<Header, LoadError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(LoadError::BadMagic);
+        }
+        let mut header = [0; 2];
+        reader.read_exact(&mut header)?;
+        if header[0] != VERSION {
+            return Err(LoadError::UnsupportedVersion(header[0]));
+        }
+        let mut name = vec![0; header[1] as usize];
+        reader.read_exact(&mut name)?;
+        let mut options = [0; 2];
+        reader.read_exact(&mut options)?;
+        Ok(Header {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            scheme: Scheme::from_flags(options[0]).ok_or(LoadError::BadScheme(options[0]))?,
+            use_map: options[1] & MAP_FLAG != 0,
+            items_count: read_u64(reader)?,
+            height: read_u64(reader)?,
+            array_len: read_u64(reader)?,
+        })
+    }
+
+    // Bytes taken by write
+    pub(crate) fn len(&self) -> usize {
+        MAGIC.len() + 2 + self.name.len() + 2 + 3 * 8
+    }
+
+    // Node array length must fit the leaf count
+    pub(crate) fn check<H: NamedHasher>(&self) -> Result<(), LoadError> {
+        let hasher = H::from_name(&self.name).ok_or_else(|| LoadError::UnknownAlgorithm(self.name.clone()))?;
+        let len = hasher.output_len();
+        if self.items_count > self.array_len / len || self.array_len > isize::MAX as usize / 2 { // Too large to be a tree
+            return Err(LoadError::BadLength { got: self.array_len, expected: self.items_count.saturating_mul(len) });
+        }
+        let expected = calculate_vec_len(self.items_count, &hasher, self.scheme);
+        if self.array_len != expected {
+            return Err(LoadError::BadLength { got: self.array_len, expected });
+        }
+        Ok(())
+    }
+}
+
+impl<H: NamedHasher> MerkleTree<H> {
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        self.header().write(&mut writer)?;
+        writer.write_all(&self.array)
+    }
+
+    // With check_root all nodes are recomputed from the leafs and compared to the stored ones
+    pub fn read_from<R: Read>(mut reader: R, check_root: bool) -> Result<MerkleTree<H>, LoadError> {
+        let header = Header::read(&mut reader)?;
+        header.check::<H>()?;
+        let mut array = Vec::new(); // Grows with the data actually read
+        reader.take(header.array_len as u64).read_to_end(&mut array)?;
+        if array.len() != header.array_len {
+            return Err(LoadError::Truncated);
+        }
+        let tree = MerkleTree::from_header(&header, array.into())?;
+        if check_root {
+            tree.check_nodes()?;
+        }
+        Ok(tree)
+    }
+
+    pub(crate) fn header(&self) -> Header {
+        Header {
+            name: self.hasher.name().to_string(),
+            scheme: self.scheme,
+            use_map: self.map.is_some(),
+            items_count: self.items_count,
+            height: self.height,
+            array_len: self.array.len(),
+        }
+    }
+
+    pub(crate) fn from_header(header: &Header, array: Nodes) -> Result<MerkleTree<H>, LoadError> {
+        MerkleTree::from_parts(&header.name, header.scheme, header.height, header.items_count, header.use_map, array)
+    }
+
+    // Recomputes all nodes from the leafs
+    pub(crate) fn check_nodes(&self) -> Result<(), LoadError> {
+        self.verify_integrity().map_err(|_| LoadError::RootMismatch)
+    }
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<usize, LoadError> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes) as usize)
+}
@@ -0,0 +1,174 @@
+use std::convert::AsRef;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+
+use error::Error;
+use hasher::MerkleHasher;
+#[cfg(feature = "ring")]
+use hasher::RingHasher;
+use leaf_map::{LeafMap, MapKeys};
+use scheme::{PaddingStrategy, Scheme};
+use MerkleTree;
+
+// Collects tree options; they are checked together when the tree is built. A later call
+// overrides an earlier one of the same option: use_map(false) after map_hasher builds no map,
+// the hasher of the map is kept for a later use_map(true), and hasher replaces what
+// with_hasher or algorithm set. Options that contradict each other are refused by build.
+pub struct MerkleTreeBuilder<H: MerkleHasher> {
+    hasher: Option<H>,
+    use_map: bool,
+    map_keys: Option<MapKeys>,
+    scheme: Scheme,
+    pad_single_leaf: Option<bool>, // None follows the padding
+    proof_positions: Option<bool>, // None follows sorted_pairs
+}
+
+#[cfg(feature = "ring")]
+impl MerkleTreeBuilder<RingHasher> {
+    pub fn new() -> MerkleTreeBuilder<RingHasher> {
+        MerkleTreeBuilder { hasher: None, use_map: false, map_keys: None, scheme: Scheme::LEGACY, pad_single_leaf: None, proof_positions: None }
+    }
+
+    pub fn algorithm<A: Into<&'static Algorithm>>(self, algo: A) -> MerkleTreeBuilder<RingHasher> {
+        self.hasher(RingHasher::new(algo.into()))
+    }
+}
+
+#[cfg(feature = "ring")]
+impl Default for MerkleTreeBuilder<RingHasher> {
+    fn default() -> MerkleTreeBuilder<RingHasher> {
+        MerkleTreeBuilder::new()
+    }
+}
+
+impl<H: MerkleHasher> MerkleTreeBuilder<H> {
+    pub fn with_hasher(hasher: H) -> MerkleTreeBuilder<H> {
+        MerkleTreeBuilder { hasher: Some(hasher), use_map: false, map_keys: None, scheme: Scheme::LEGACY, pad_single_leaf: None, proof_positions: None }
+    }
+
+    pub fn hasher(mut self, hasher: H) -> MerkleTreeBuilder<H> {
+        self.hasher = Some(hasher);
+        self
+    }
+
+    // Keep a map from leaf hash to index for constant time lookups
+    pub fn use_map(mut self, use_map: bool) -> MerkleTreeBuilder<H> {
+        self.use_map = use_map;
+        self
+    }
+
+    // Keep the map and hash its keys with the given hasher instead of SipHash,
+    // e.g. BuildHasherDefault::<PrefixHasher>::default(). Same as use_map(true) for the map.
+    pub fn map_hasher<S: BuildHasher + Send + Sync + 'static>(mut self, hasher: S) -> MerkleTreeBuilder<H> {
+        self.use_map = true;
+        self.map_keys = Some(MapKeys::with_hasher(hasher));
+        self
+    }
+
+    // With false parents are H(left || right) in tree order and proofs carry sibling positions
+    pub fn sorted_pairs(mut self, sorted_pairs: bool) -> MerkleTreeBuilder<H> {
+        self.scheme.sorted_pairs = sorted_pairs;
+        self
+    }
+
+    // Leafs are H(0x00 || value) and nodes H(0x01 || left || right), so a node can not pass for a leaf
+    pub fn domain_separation(mut self, domain_separation: bool) -> MerkleTreeBuilder<H> {
+        self.scheme.domain_separation = domain_separation;
+        self
+    }
+
+    // Leafs are made from H(value), e.g. H(H(value)), node hashing stays the same
+    pub fn double_hash_leaves(mut self, double_hash_leaves: bool) -> MerkleTreeBuilder<H> {
+        self.scheme.double_hash_leaves = double_hash_leaves;
+        self
+    }
+
+    // What the last node of an odd level is paired with, DuplicateLast by default
+    pub fn padding(mut self, padding: PaddingStrategy) -> MerkleTreeBuilder<H> {
+        self.scheme.padding = padding;
+        self
+    }
+
+    // A lone leaf is paired with its padding instead of being the root. Unless set it is, for
+    // any padding but PromoteOdd, which has nothing to pair it with.
+    pub fn pad_single_leaf(mut self, pad_single_leaf: bool) -> MerkleTreeBuilder<H> {
+        self.pad_single_leaf = Some(pad_single_leaf);
+        self
+    }
+
+    // Proofs carry the positions of their siblings. Unless set they do exactly when pairs are
+    // not sorted: sorted pairs need none and unsorted ones can not be checked without them.
+    pub fn proof_positions(mut self, proof_positions: bool) -> MerkleTreeBuilder<H> {
+        self.proof_positions = Some(proof_positions);
+        self
+    }
+
+    pub(crate) fn scheme(mut self, scheme: Scheme) -> MerkleTreeBuilder<H> {
+        self.scheme = scheme;
+        self
+    }
+
+    pub fn build<T: AsRef<[u8]>>(self, values: &[T]) -> Result<MerkleTree<H>, Error> {
+        let (hasher, use_map, scheme, keys) = self.check()?;
+        Ok(with_keys(MerkleTree::new_with_scheme(values, hasher, use_map, scheme), keys))
+    }
+
+    pub fn build_from_iter<T: AsRef<[u8]>, I: IntoIterator<Item = T>>(self, values: I) -> Result<MerkleTree<H>, Error> {
+        let (hasher, use_map, scheme, keys) = self.check()?;
+        let values = values.into_iter();
+        let capacity = values.size_hint().0;
+        Ok(with_keys(MerkleTree::new_from_iter_with_scheme(values, hasher, use_map, scheme, capacity), keys))
+    }
+
+    // Leafs are given already hashed
+    pub fn build_from_leaf_hashes<T: AsRef<[u8]>>(self, hashes: &[T]) -> Result<MerkleTree<H>, Error> {
+        let (hasher, use_map, scheme, keys) = self.check()?;
+        MerkleTree::from_leaf_hashes_with_scheme(hashes, hasher, use_map, scheme).map(|tree| with_keys(tree, keys))
+    }
+
+    // The tree is built without a map when its keys need a custom hasher, with_keys adds it after
+    fn check(self) -> Result<(H, bool, Scheme, Option<MapKeys>), Error> {
+        let hasher = self.hasher.ok_or(Error::MissingHasher)?;
+        let mut scheme = self.scheme;
+        match self.pad_single_leaf {
+            Some(true) if !scheme.pads() => return Err(Error::IncompatibleOptions("PromoteOdd padding and pad_single_leaf")),
+            Some(pad_single_leaf) => scheme.pad_single_leaf = pad_single_leaf,
+            None if !scheme.pads() => scheme.pad_single_leaf = false,
+            None => {}
+        }
+        match self.proof_positions {
+            Some(true) if scheme.sorted_pairs => return Err(Error::IncompatibleOptions("sorted pairs and proof positions")),
+            Some(false) if !scheme.sorted_pairs => return Err(Error::IncompatibleOptions("unsorted pairs and proofs without positions")),
+            _ => {}
+        }
+        let keys = if self.use_map { self.map_keys } else { None };
+        Ok((hasher, self.use_map && keys.is_none(), scheme, keys))
+    }
+}
+
+fn with_keys<H: MerkleHasher>(mut tree: MerkleTree<H>, keys: Option<MapKeys>) -> MerkleTree<H> {
+    if let Some(keys) = keys {
+        let len = tree.hasher.output_len();
+        tree.map = Some(LeafMap::with_keys(&tree.array[..tree.items_count * len], len, keys));
+    }
+    tree
+}
+
+impl<H: MerkleHasher + Sync> MerkleTreeBuilder<H> {
+    // Leafs are hashed on worker threads, all available cores if None
+    pub fn build_threaded<T: AsRef<[u8]> + Sync>(self, values: &[T], threads: Option<usize>) -> Result<MerkleTree<H>, Error> {
+        let (hasher, use_map, scheme, keys) = self.check()?;
+        Ok(with_keys(MerkleTree::new_threaded_with_scheme(values, hasher, use_map, scheme, threads), keys))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<H: MerkleHasher + Sync> MerkleTreeBuilder<H> {
+    // Hashes leafs and the nodes of every level on the rayon thread pool, the tree is the same as from build
+    pub fn build_parallel<T: AsRef<[u8]> + Sync>(self, values: &[T]) -> Result<MerkleTree<H>, Error> {
+        let (hasher, use_map, scheme, keys) = self.check()?;
+        Ok(with_keys(MerkleTree::new_parallel_with_scheme(values, hasher, use_map, scheme), keys))
+    }
+}
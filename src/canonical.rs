@@ -0,0 +1,79 @@
+use std::error::Error;
+use std::fmt;
+
+use ring::digest::Algorithm;
+use serde::Serialize;
+use serde_json::{self, Value};
+
+use proof::Proof;
+use MerkleTree;
+
+// Value that serde could not turn into JSON, e.g. a map with non-string keys
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodeError {
+    pub index: usize,
+    pub message: String,
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "value {} can not be encoded: {}", self.index, self.message)
+    }
+}
+
+impl Error for EncodeError {}
+
+// Leafs are hashes of the canonical JSON of the values: no whitespace, object keys sorted
+// by their UTF-8 bytes at every depth, strings and numbers as serde_json writes them.
+// The encoding is part of the root and will not change.
+impl MerkleTree {
+    pub fn from_serialize<T: Serialize, A: Into<&'static Algorithm>>(values: &[T], algo: A) -> Result<MerkleTree, EncodeError> {
+        let encoded = values.iter().enumerate()
+            .map(|(index, v)| canonical_json(v).map_err(|message| EncodeError { index, message }))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(MerkleTree::new(&encoded, algo))
+    }
+
+    // None also when the value can not be encoded, it can not be a leaf then
+    pub fn build_proof_serialized<T: Serialize>(&self, value: &T) -> Option<Proof> {
+        canonical_json(value).ok().and_then(|encoded| self.build_proof(&encoded))
+    }
+}
+
+fn canonical_json<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    let mut out = vec![];
+    write_value(&value, &mut out);
+    Ok(out)
+}
+
+// Keys are sorted here, the order of serde_json maps depends on its features
+fn write_value(value: &Value, out: &mut Vec<u8>) {
+    match *value {
+        Value::Object(ref map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+            out.push(b'{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                serde_json::to_writer(&mut *out, key).expect("strings are always written");
+                out.push(b':');
+                write_value(item, out);
+            }
+            out.push(b'}');
+        }
+        Value::Array(ref items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_value(item, out);
+            }
+            out.push(b']');
+        }
+        _ => serde_json::to_writer(&mut *out, value).expect("scalars are always written"),
+    }
+}
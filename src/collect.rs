@@ -0,0 +1,23 @@
+use std::convert::AsRef;
+use std::iter::FromIterator;
+
+use ring::digest::{Algorithm, SHA256};
+
+use MerkleTree;
+
+// collect builds SHA-256 trees, hashing every value as it comes like from_iter_streaming
+impl<T: AsRef<[u8]>> FromIterator<T> for MerkleTree {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> MerkleTree {
+        MerkleTree::from_iter_streaming(values, &SHA256, None)
+    }
+}
+
+// collect with another algorithm: MerkleTreeCollector(&SHA512).collect(values)
+#[derive(Clone, Copy)]
+pub struct MerkleTreeCollector(pub &'static Algorithm);
+
+impl MerkleTreeCollector {
+    pub fn collect<T: AsRef<[u8]>, I: IntoIterator<Item = T>>(self, values: I) -> MerkleTree {
+        MerkleTree::from_iter_streaming(values, self.0, None)
+    }
+}
@@ -0,0 +1,125 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+
+use proof::{Position, Proof, ProofFormatError};
+
+const VERSION: u8 = 1;
+
+// Layout: version, varint of siblings << 1 | 1 when positions are given, then for positions
+// one bit per sibling, least significant first and set for Left, unused bits zero,
+// then the hashes, leaf first. Varints are LEB128 in their shortest form.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompactError {
+    UnsupportedVersion(u8),
+    BadVarint,
+    Truncated,
+    TrailingBytes { len: usize },
+    PaddingBits,
+    Format(ProofFormatError),
+}
+
+impl Proof {
+    pub fn to_compact_bytes(&self) -> Vec<u8> {
+        let siblings = self.len().saturating_sub(1);
+        let mut bytes = vec![VERSION];
+        write_varint(&mut bytes, (siblings as u64) << 1 | self.positions().is_some() as u64);
+        if let Some(positions) = self.positions() {
+            let start = bytes.len();
+            bytes.resize(start + siblings.div_ceil(8), 0);
+            for (i, &position) in positions.iter().enumerate() {
+                if position == Position::Left {
+                    bytes[start + i / 8] |= 1 << (i % 8);
+                }
+            }
+        }
+        bytes.extend_from_slice(self.as_bytes());
+        bytes
+    }
+
+    #[cfg(feature = "ring")]
+    pub fn from_compact_bytes<A: Into<&'static Algorithm>>(bytes: &[u8], algo: A) -> Result<Proof, CompactError> {
+        Proof::from_compact_bytes_with_len(bytes, algo.into().output_len)
+    }
+
+    // Every byte must be taken, a proof followed by anything else is rejected
+    pub fn from_compact_bytes_with_len(bytes: &[u8], digest_len: usize) -> Result<Proof, CompactError> {
+        let (&version, mut rest) = bytes.split_first().ok_or(CompactError::Truncated)?;
+        if version != VERSION {
+            return Err(CompactError::UnsupportedVersion(version));
+        }
+        let header = read_varint(&mut rest)?;
+        let siblings = usize::try_from(header >> 1).map_err(|_| CompactError::Truncated)?;
+        let positions = if header & 1 == 1 {
+            let bits = take(&mut rest, siblings.div_ceil(8))?;
+            if !siblings.is_multiple_of(8) && bits[bits.len() - 1] >> (siblings % 8) != 0 {
+                return Err(CompactError::PaddingBits);
+            }
+            Some((0..siblings).map(|i| if bits[i / 8] >> (i % 8) & 1 == 1 { Position::Left } else { Position::Right }).collect())
+        } else {
+            None
+        };
+        let len = siblings.checked_add(1).and_then(|hashes| hashes.checked_mul(digest_len)).ok_or(CompactError::Truncated)?;
+        let hashes = take(&mut rest, len)?;
+        if !rest.is_empty() {
+            return Err(CompactError::TrailingBytes { len: rest.len() });
+        }
+        match positions {
+            Some(positions) => Proof::with_positions(hashes, digest_len, positions),
+            None => Proof::from_bytes(hashes, digest_len),
+        }.map_err(CompactError::Format)
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+// Rejects encodings longer than needed and values over 64 bits
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, CompactError> {
+    let mut value = 0u64;
+    for i in 0..10 {
+        let (&byte, rest) = bytes.split_first().ok_or(CompactError::Truncated)?;
+        *bytes = rest;
+        let bits = u64::from(byte & 0x7f);
+        if (i == 9 && bits > 1) || (i > 0 && byte == 0) {
+            return Err(CompactError::BadVarint);
+        }
+        value |= bits << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(CompactError::BadVarint)
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], CompactError> {
+    if bytes.len() < len {
+        return Err(CompactError::Truncated);
+    }
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(taken)
+}
+
+impl fmt::Display for CompactError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompactError::UnsupportedVersion(version) => write!(f, "unsupported compact proof version {}", version),
+            CompactError::BadVarint => write!(f, "sibling count is not a valid varint"),
+            CompactError::Truncated => write!(f, "compact proof ends before its hashes"),
+            CompactError::TrailingBytes { len } => write!(f, "{} bytes follow the compact proof", len),
+            CompactError::PaddingBits => write!(f, "unused direction bits are set"),
+            CompactError::Format(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for CompactError {}
@@ -0,0 +1,155 @@
+use std::mem;
+
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+
+use hasher::MerkleHasher;
+#[cfg(feature = "ring")]
+use hasher::RingHasher;
+use scheme::Scheme;
+use MerkleTree;
+
+// Proof layout: hashes of the maximal full subtrees (peaks) of the old tree from left to right,
+// followed by the new tree siblings needed to climb from those peaks to the new root, bottom-up.
+// Peaks are shared by both trees, so the verifier rebuilds the old root from peaks alone.
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn build_consistency_proof(&self, old_leaf_count: usize) -> Option<Vec<u8>> {
+        if old_leaf_count == 0 || old_leaf_count > self.items_count {
+            return None;
+        }
+        let len = self.hasher.output_len();
+        let peaks = calculate_peaks(old_leaf_count);
+        let mut result = Vec::with_capacity((peaks.len() + self.height) * len);
+
+        let mut starts = vec![0]; // First node of every level
+        let mut count = self.items_count;
+        while !self.scheme.is_root_level(starts.len() - 1, count) {
+            let start = starts[starts.len() - 1] + self.scheme.stored_len(count);
+            starts.push(start);
+            count = count.div_ceil(2);
+        }
+        for &(level, index) in &peaks {
+            let begin = (starts[level] + index) * len;
+            result.extend_from_slice(&self.array[begin..begin + len]);
+        }
+
+        let mut current: Vec<usize> = vec![];
+        let mut count = self.items_count;
+        let mut level = 0;
+        loop {
+            if let Some(&(_, index)) = peaks.iter().find(|&&(l, _)| l == level) {
+                current.insert(0, index); // Peak lies left from everything known on its level
+            }
+            if self.scheme.is_root_level(level, count) {
+                break;
+            }
+            let mut parents = Vec::with_capacity(current.len());
+            let mut k = 0;
+            while k < current.len() {
+                let index = current[k];
+                let sibling = index ^ 1;
+                if k + 1 < current.len() && current[k + 1] == sibling {
+                    k += 1;
+                } else if sibling < count {
+                    let begin = (starts[level] + sibling) * len;
+                    result.extend_from_slice(&self.array[begin..begin + len]);
+                }
+                parents.push(index / 2);
+                k += 1;
+            }
+            current = parents;
+            count = count.div_ceil(2);
+            level += 1;
+        }
+        Some(result)
+    }
+
+    // Checks that this tree extends the old one, using the hashing rules of this tree
+    pub fn validate_consistency(&self, old_root: &[u8], old_leaf_count: usize, proof: &[u8]) -> bool {
+        verify_consistency_with(old_root, old_leaf_count, self.get_root(), self.items_count, proof, &self.hasher, self.scheme)
+    }
+}
+
+#[cfg(feature = "ring")]
+pub fn verify_consistency<A: Into<&'static Algorithm>>(old_root: &[u8], old_count: usize, new_root: &[u8], new_count: usize,
+                                                       proof: &[u8], algo: A) -> bool {
+    verify_consistency_with(old_root, old_count, new_root, new_count, proof, &RingHasher::new(algo.into()), Scheme::LEGACY)
+}
+
+fn verify_consistency_with<H: MerkleHasher>(old_root: &[u8], old_count: usize, new_root: &[u8], new_count: usize,
+                            proof: &[u8], hasher: &H, scheme: Scheme) -> bool {
+    let len = hasher.output_len();
+    if old_count == 0 || old_count > new_count || !proof.len().is_multiple_of(len) {
+        return false;
+    }
+    let peaks = calculate_peaks(old_count);
+    if proof.len() < peaks.len() * len {
+        return false;
+    }
+    let (peak_bytes, rest) = proof.split_at(peaks.len() * len);
+    let known: Vec<(usize, usize, &[u8])> = peaks.iter()
+        .zip(peak_bytes.chunks(len))
+        .map(|(&(level, index), hash)| (level, index, hash))
+        .collect();
+
+    let mut no_hashes = [].chunks(len);
+    let mut hashes = rest.chunks(len);
+    match (fold_peaks(old_count, &known, &mut no_hashes, hasher, scheme), fold_peaks(new_count, &known, &mut hashes, hasher, scheme)) {
+        (Some(old), Some(new)) => hashes.next().is_none() && old == old_root && new == new_root,
+        _ => false,
+    }
+}
+
+// Climbs from the peaks to the root of a tree with count leaves, taking missing siblings from hashes
+fn fold_peaks<'a, I: Iterator<Item = &'a [u8]>, H: MerkleHasher>(count: usize, peaks: &[(usize, usize, &[u8])], hashes: &mut I,
+                                                hasher: &H, scheme: Scheme) -> Option<Vec<u8>> {
+    let mut current: Vec<(usize, Vec<u8>)> = vec![];
+    let mut count = count;
+    let mut level = 0;
+    loop {
+        if let Some(&(_, index, hash)) = peaks.iter().find(|&&(l, _, _)| l == level) {
+            current.insert(0, (index, hash.to_vec()));
+        }
+        if scheme.is_root_level(level, count) {
+            break;
+        }
+        let mut parents = Vec::with_capacity(current.len());
+        let mut k = 0;
+        while k < current.len() {
+            let index = current[k].0;
+            let sibling = index ^ 1;
+            let parent = if k + 1 < current.len() && current[k + 1].0 == sibling {
+                k += 1;
+                scheme.hash_pair(&current[k - 1].1, &current[k].1, hasher)
+            } else if sibling < count {
+                let hash = hashes.next()?;
+                if sibling < index {
+                    scheme.hash_pair(hash, &current[k].1, hasher)
+                } else {
+                    scheme.hash_pair(&current[k].1, hash, hasher)
+                }
+            } else { // Last node of the level has no sibling
+                scheme.hash_lone(&current[k].1, hasher)
+            };
+            parents.push((index / 2, parent));
+            k += 1;
+        }
+        current = parents;
+        count = count.div_ceil(2);
+        level += 1;
+    }
+    if current.len() == 1 { current.pop().map(|(_, hash)| hash) } else { None }
+}
+
+// Maximal full subtrees covering leaves [0, count) as (level, index), largest first
+fn calculate_peaks(count: usize) -> Vec<(usize, usize)> {
+    let mut result = vec![];
+    let mut offset = 0;
+    for level in (0..mem::size_of::<usize>() * 8).rev() {
+        if count & (1 << level) != 0 {
+            result.push((level, offset >> level));
+            offset += 1 << level;
+        }
+    }
+    result
+}
@@ -0,0 +1,33 @@
+use std::fmt;
+
+use hasher::NamedHasher;
+use hex;
+use MerkleTree;
+
+const SHOWN_BYTES: usize = 8;
+const SHOWN_NODES: usize = 3; // From each end of a long level
+
+// One line per level, leafs first, every hash cut to its first bytes
+impl<H: NamedHasher> fmt::Debug for MerkleTree<H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MerkleTree({}, leafs: {}, height: {})", self.algorithm_name(), self.items_count, self.height)?;
+        let len = self.hasher.output_len();
+        for (i, level) in self.levels().enumerate() {
+            write!(f, "\n  level {}:", i)?;
+            let count = level.len() / len;
+            for (j, node) in level.chunks(len).enumerate() {
+                if count > 2 * SHOWN_NODES && j >= SHOWN_NODES && j < count - SHOWN_NODES {
+                    if j == SHOWN_NODES {
+                        write!(f, " … ({} more)", count - 2 * SHOWN_NODES)?;
+                    }
+                    continue;
+                }
+                write!(f, " {}", hex::encode(&node[..len.min(SHOWN_BYTES)]))?;
+                if len > SHOWN_BYTES {
+                    write!(f, "…")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use error::DiffError;
+use hasher::MerkleHasher;
+use {level_layout, MerkleTree};
+
+impl<H: MerkleHasher + PartialEq> MerkleTree<H> {
+    // Indices of leafs that differ, leafs present in one tree only included. Walks down from the
+    // top, skipping every subtree whose hash is the same in both trees.
+    pub fn diff(&self, other: &MerkleTree<H>) -> Result<Vec<usize>, DiffError> {
+        if self.hasher != other.hasher {
+            return Err(DiffError::AlgorithmMismatch);
+        }
+        if self.scheme != other.scheme {
+            return Err(DiffError::SchemeMismatch);
+        }
+        let common = self.items_count.min(other.items_count);
+        let mut result = vec![];
+        if common > 0 {
+            let starts = (starts(self), starts(other));
+            let top = starts.0.len().min(starts.1.len()) - 1;
+            for index in 0..common.div_ceil(1 << top) {
+                self.diff_node(other, &starts, top, index, common, &mut result);
+            }
+        }
+        result.extend(common..self.items_count.max(other.items_count));
+        Ok(result)
+    }
+
+    fn diff_node(&self, other: &MerkleTree<H>, starts: &(Vec<usize>, Vec<usize>), level: usize, index: usize,
+                 common: usize, result: &mut Vec<usize>) {
+        if index << level >= common {
+            return;
+        }
+        // A node covers the same leafs in both trees when its subtree is full or the trees have the same shape
+        let same_cover = (index + 1) << level <= common || self.items_count == other.items_count;
+        if same_cover && self.node_at(starts.0[level], index) == other.node_at(starts.1[level], index) {
+            return;
+        }
+        if level == 0 {
+            result.push(index);
+        } else {
+            self.diff_node(other, starts, level - 1, 2 * index, common, result);
+            self.diff_node(other, starts, level - 1, 2 * index + 1, common, result);
+        }
+    }
+
+    fn node_at(&self, start: usize, index: usize) -> &[u8] {
+        let len = self.hasher.output_len();
+        &self.array[(start + index) * len..(start + index + 1) * len]
+    }
+}
+
+fn starts<H: MerkleHasher>(tree: &MerkleTree<H>) -> Vec<usize> {
+    level_layout(tree.items_count, tree.scheme).iter().map(|&(start, _)| start).collect()
+}
@@ -0,0 +1,90 @@
+use std::fmt::Write;
+
+use hasher::MerkleHasher;
+use hex;
+use proof::{Position, Proof};
+use {calculate_relatives, level_layout, MerkleTree};
+
+const LABEL_BYTES: usize = 4;
+const PATH_COLOR: &str = "lightblue";
+const SIBLING_COLOR: &str = "orange";
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    // Graphviz digraph, edges go from parents to children. Copies of the last node
+    // of odd levels have a dashed border.
+    pub fn to_dot(&self) -> String {
+        self.dot(None)
+    }
+
+    // Nodes from the proven leaf to the root are filled with one color, proof siblings with another
+    pub fn to_dot_with_proof(&self, proof: &Proof) -> String {
+        self.dot(self.proven_leaf(proof))
+    }
+
+    // With positions the leaf is the one of equal hash whose path turns the same way,
+    // without them the first leaf of that hash
+    fn proven_leaf(&self, proof: &Proof) -> Option<usize> {
+        let positions = match proof.positions() {
+            Some(positions) => positions,
+            None => return self.find_item(proof.leaf_hash()),
+        };
+        self.leaves().enumerate()
+            .filter(|&(_, leaf)| leaf == proof.leaf_hash())
+            .map(|(index, _)| index)
+            .find(|&index| self.path_positions(index) == positions)
+    }
+
+    fn path_positions(&self, mut index: usize) -> Vec<Position> {
+        let layout = level_layout(self.items_count, self.scheme);
+        let mut positions = vec![];
+        for &(_, level_len) in &layout[..layout.len().saturating_sub(1)] { // The root has no sibling
+            let (sibling, parent) = calculate_relatives(index);
+            if sibling < level_len || self.scheme.pads() {
+                positions.push(if sibling < index { Position::Left } else { Position::Right });
+            }
+            index = parent;
+        }
+        positions
+    }
+
+    fn dot(&self, leaf: Option<usize>) -> String {
+        let len = self.hasher.output_len();
+        let layout = level_layout(self.items_count, self.scheme);
+        let mut result = String::from("digraph merkle {\n    node [shape=box, fontname=monospace];\n");
+        let mut slots = vec![]; // Stored nodes of every level, padding included
+        for (level, &(start, level_len)) in layout.iter().enumerate() {
+            let padded = self.scheme.pads() && level_len & 1 == 1
+                && !self.scheme.is_root_level(level, level_len);
+            let stored = if padded { level_len + 1 } else { level_len };
+            for index in 0..stored {
+                let begin = (start + index) * len;
+                let label = hex::encode(&self.array[begin..begin + len.min(LABEL_BYTES)]);
+                let color = leaf.and_then(|leaf| if index == leaf >> level {
+                    Some(PATH_COLOR)
+                } else if index == (leaf >> level) ^ 1 && level + 1 < layout.len() {
+                    Some(SIBLING_COLOR)
+                } else {
+                    None
+                });
+                let style = match (color, index == level_len) {
+                    (Some(color), true) => format!(", style=\"filled,dashed\", fillcolor={}", color),
+                    (Some(color), false) => format!(", style=filled, fillcolor={}", color),
+                    (None, true) => ", style=dashed".to_string(),
+                    (None, false) => String::new(),
+                };
+                let attributes = format!("label=\"{}/{}\\n{}…\"{}", level, index, label, style);
+                writeln!(result, "    n{}_{} [{}];", level, index, attributes).expect("writing to a string");
+            }
+            slots.push(stored);
+        }
+        for level in 1..layout.len() {
+            for index in 0..layout[level].1 {
+                for child in 2 * index..(2 * index + 2).min(slots[level - 1]) {
+                    writeln!(result, "    n{}_{} -> n{}_{};", level, index, level - 1, child).expect("writing to a string");
+                }
+            }
+        }
+        result.push_str("}\n");
+        result
+    }
+}
@@ -0,0 +1,222 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::slice::Chunks;
+
+use proof::ProofFormatError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    AlgorithmMismatch,
+    BadHashLength { index: usize, len: usize, expected: usize },
+    IncompatibleOptions(&'static str), // builder options that no scheme has together
+    IndexOutOfRange { index: usize, len: usize },
+    MissingHasher,
+    SchemeMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::AlgorithmMismatch => write!(f, "trees use different hash algorithms"),
+            Error::BadHashLength { index, len, expected } =>
+                write!(f, "hash #{} has length {}, expected {}", index, len, expected),
+            Error::IncompatibleOptions(options) => write!(f, "{} can not be used together", options),
+            Error::IndexOutOfRange { index, len } => write!(f, "index {} is out of range for {} leafs", index, len),
+            Error::MissingHasher => write!(f, "no hash algorithm is set"),
+            Error::SchemeMismatch => write!(f, "trees use different hashing schemes"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+// Node array of the tree would not fit in usize
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityError {
+    pub leafs: usize,
+    pub digest_len: usize,
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "tree over {} leafs of {} byte digests needs more than {} bytes", self.leafs, self.digest_len, usize::MAX)
+    }
+}
+
+impl error::Error for CapacityError {}
+
+// Name that is not one of the digest algorithms
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnknownAlgorithm {
+    pub name: String,
+}
+
+impl fmt::Display for UnknownAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown digest algorithm '{}'", self.name)
+    }
+}
+
+impl error::Error for UnknownAlgorithm {}
+
+// Stored node that differs from the one recomputed from the tree, padding copies included
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IntegrityError {
+    pub level: usize,
+    pub index: usize,
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "node {} of level {} differs from the recomputed one", self.index, self.level)
+    }
+}
+
+impl error::Error for IntegrityError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffError {
+    AlgorithmMismatch,
+    SchemeMismatch,
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DiffError::AlgorithmMismatch => write!(f, "trees use different hash algorithms"),
+            DiffError::SchemeMismatch => write!(f, "trees use different hashing schemes"),
+        }
+    }
+}
+
+impl error::Error for DiffError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofError {
+    BadHashLength { len: usize, expected: usize },
+    EmptyTree,
+    NotFound,
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProofError::BadHashLength { len, expected } => write!(f, "hash has length {}, expected {}", len, expected),
+            ProofError::EmptyTree => write!(f, "tree is empty"),
+            ProofError::NotFound => write!(f, "value is not in the tree"),
+        }
+    }
+}
+
+impl error::Error for ProofError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    Format(ProofFormatError),
+    DigestMismatch { got: usize, expected: usize },
+    HashCount { got: usize, expected: usize },
+    EmptyTree,
+    MissingPositions,
+    RootMismatch { computed: Vec<u8>, trace: ValidationTrace },
+}
+
+// Hashes computed while a proof is walked: the leaf hash, then the hash after every sibling,
+// the computed root last. Step i is on level i of the tree unless odd nodes are promoted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationTrace {
+    hashes: Vec<u8>,
+    digest_len: usize,
+    mismatch: Option<usize>,
+}
+
+impl ValidationTrace {
+    pub(crate) fn new(hashes: Vec<u8>, digest_len: usize, mismatch: Option<usize>) -> ValidationTrace {
+        ValidationTrace { hashes, digest_len, mismatch }
+    }
+
+    pub fn hashes(&self) -> Chunks<'_, u8> {
+        self.hashes.chunks(self.digest_len)
+    }
+
+    pub fn step(&self, step: usize) -> Option<&[u8]> {
+        self.hashes.get(step * self.digest_len..(step + 1) * self.digest_len)
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len() / self.digest_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    // First step whose hash is no node of the levels it can be on: 0 for a wrong leaf hash,
+    // i + 1 for a wrong i-th sibling
+    pub fn first_mismatch(&self) -> Option<usize> {
+        self.mismatch
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::Format(ref e) => write!(f, "{}", e),
+            ValidationError::DigestMismatch { got, expected } =>
+                write!(f, "proof digest length {} does not match tree digest length {}", got, expected),
+            ValidationError::HashCount { got, expected } =>
+                write!(f, "proof has {} hashes, the tree height implies {}", got, expected),
+            ValidationError::EmptyTree => write!(f, "tree is empty"),
+            ValidationError::MissingPositions => write!(f, "proof has no sibling positions required by the tree"),
+            ValidationError::RootMismatch { .. } => write!(f, "computed root does not match tree root"),
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+// Stored tree does not describe a valid tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    UnknownAlgorithm(String),
+    BadLength { got: usize, expected: usize },
+    NotMultiple { got: usize, multiple: usize }, // bytes that do not split into whole records
+    BadHeight { got: usize, expected: usize },
+    BadMagic,
+    UnsupportedVersion(u8),
+    BadScheme(u8),
+    Truncated,
+    RootMismatch,
+    ChecksumMismatch,
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::UnknownAlgorithm(ref name) => write!(f, "unknown algorithm '{}'", name),
+            LoadError::BadLength { got, expected } =>
+                write!(f, "node array has {} bytes, expected {}", got, expected),
+            LoadError::NotMultiple { got, multiple } => write!(f, "{} bytes is not a multiple of {}", got, multiple),
+            LoadError::BadHeight { got, expected } => write!(f, "height is {}, expected {}", got, expected),
+            LoadError::BadMagic => write!(f, "not a stored merkle tree"),
+            LoadError::UnsupportedVersion(version) => write!(f, "unsupported format version {}", version),
+            LoadError::BadScheme(flags) => write!(f, "unknown scheme flags {:#04x}", flags),
+            LoadError::Truncated => write!(f, "unexpected end of data"),
+            LoadError::RootMismatch => write!(f, "stored nodes do not match the leafs"),
+            LoadError::ChecksumMismatch => write!(f, "checksum does not match the stored tree"),
+            LoadError::Io(kind) => write!(f, "i/o error: {:?}", kind),
+        }
+    }
+}
+
+impl error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> LoadError {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => LoadError::Truncated,
+            kind => LoadError::Io(kind),
+        }
+    }
+}
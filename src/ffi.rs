@@ -0,0 +1,170 @@
+// C interface, declared in include/vmt.h. A tree made by vmt_tree_new is owned by the caller
+// and released with vmt_tree_free. Every other pointer is borrowed for the duration of the call.
+// Functions return VMT_OK or one of the negative codes below, panics come out as VMT_PANIC.
+
+use std::os::raw::c_int;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use ring::digest::{self, Algorithm};
+
+use {verify_proof, MerkleTree};
+
+pub const VMT_OK: c_int = 0;
+pub const VMT_NULL_POINTER: c_int = -1;
+pub const VMT_UNKNOWN_ALGORITHM: c_int = -2;
+pub const VMT_BUFFER_TOO_SMALL: c_int = -3;
+pub const VMT_NOT_FOUND: c_int = -4;
+pub const VMT_PANIC: c_int = -5;
+
+pub const VMT_SHA1: c_int = 1;
+pub const VMT_SHA256: c_int = 2;
+pub const VMT_SHA384: c_int = 3;
+pub const VMT_SHA512: c_int = 4;
+
+// Opaque to C
+pub struct VmtTree {
+    tree: MerkleTree,
+}
+
+/// Builds a tree over count values, value i is lens[i] bytes at values[i].
+/// Returns NULL for an unknown algo_id or a null pointer.
+///
+/// # Safety
+///
+/// values and lens point to count elements, values[i] to lens[i] readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn vmt_tree_new(values: *const *const u8, lens: *const usize, count: usize, algo_id: c_int) -> *mut VmtTree {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let algo = algorithm(algo_id)?;
+        let values: Vec<&[u8]> = if count == 0 {
+            vec![]
+        } else {
+            if values.is_null() || lens.is_null() {
+                return None;
+            }
+            let values = slice::from_raw_parts(values, count);
+            let lens = slice::from_raw_parts(lens, count);
+            let mut result = Vec::with_capacity(count);
+            for (&value, &len) in values.iter().zip(lens) {
+                result.push(bytes(value, len)?);
+            }
+            result
+        };
+        Some(Box::into_raw(Box::new(VmtTree { tree: MerkleTree::new(&values, algo) })))
+    }));
+    match result {
+        Ok(Some(tree)) => tree,
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Copies the root to out and its length to written. When out_len is too small only written is set.
+///
+/// # Safety
+///
+/// tree comes from vmt_tree_new, out has out_len writable bytes, written is writable.
+#[no_mangle]
+pub unsafe extern "C" fn vmt_tree_root(tree: *const VmtTree, out: *mut u8, out_len: usize, written: *mut usize) -> c_int {
+    guard(|| match tree.as_ref() {
+        Some(tree) => copy_out(tree.tree.get_root(), out, out_len, written),
+        None => VMT_NULL_POINTER,
+    })
+}
+
+/// Copies the proof of the first occurrence of the value to out, as vmt_tree_root does with the root.
+///
+/// # Safety
+///
+/// tree comes from vmt_tree_new, value has value_len readable bytes, out has out_len writable
+/// bytes, written is writable.
+#[no_mangle]
+pub unsafe extern "C" fn vmt_tree_build_proof(tree: *const VmtTree, value: *const u8, value_len: usize,
+                                              out: *mut u8, out_len: usize, written: *mut usize) -> c_int {
+    guard(|| {
+        let (tree, value) = match (tree.as_ref(), bytes(value, value_len)) {
+            (Some(tree), Some(value)) => (tree, value),
+            _ => return VMT_NULL_POINTER,
+        };
+        match tree.tree.build_proof(&value) {
+            Some(proof) => copy_out(proof.as_bytes(), out, out_len, written),
+            None => VMT_NOT_FOUND,
+        }
+    })
+}
+
+/// Returns 1 when the proof leads to the root, 0 when it does not.
+///
+/// # Safety
+///
+/// root has root_len and proof has proof_len readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn vmt_verify_proof(root: *const u8, root_len: usize, proof: *const u8, proof_len: usize,
+                                          algo_id: c_int) -> c_int {
+    guard(|| {
+        let algo = match algorithm(algo_id) {
+            Some(algo) => algo,
+            None => return VMT_UNKNOWN_ALGORITHM,
+        };
+        match (bytes(root, root_len), bytes(proof, proof_len)) {
+            (Some(root), Some(proof)) => verify_proof(root, proof, algo) as c_int,
+            _ => VMT_NULL_POINTER,
+        }
+    })
+}
+
+/// Releases the tree, NULL is ignored.
+///
+/// # Safety
+///
+/// tree comes from vmt_tree_new and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn vmt_tree_free(tree: *mut VmtTree) {
+    if !tree.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(tree))));
+    }
+}
+
+fn guard<F: FnOnce() -> c_int>(f: F) -> c_int {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(VMT_PANIC)
+}
+
+fn algorithm(algo_id: c_int) -> Option<&'static Algorithm> {
+    match algo_id {
+        VMT_SHA1 => Some(&digest::SHA1),
+        VMT_SHA256 => Some(&digest::SHA256),
+        VMT_SHA384 => Some(&digest::SHA384),
+        VMT_SHA512 => Some(&digest::SHA512),
+        _ => None,
+    }
+}
+
+// Null is fine for an empty slice
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(data, len))
+    }
+}
+
+unsafe fn copy_out(data: &[u8], out: *mut u8, out_len: usize, written: *mut usize) -> c_int {
+    if written.is_null() {
+        return VMT_NULL_POINTER;
+    }
+    *written = data.len();
+    if out_len < data.len() {
+        return VMT_BUFFER_TOO_SMALL;
+    }
+    if data.is_empty() {
+        return VMT_OK;
+    }
+    if out.is_null() {
+        return VMT_NULL_POINTER;
+    }
+    ptr::copy_nonoverlapping(data.as_ptr(), out, data.len());
+    VMT_OK
+}
@@ -0,0 +1,182 @@
+#[cfg(feature = "ring")]
+use std::str::FromStr;
+
+#[cfg(feature = "ring")]
+use ring::digest::{self, Algorithm, Context};
+#[cfg(feature = "keccak")]
+use tiny_keccak::{Hasher, Keccak};
+
+#[cfg(feature = "ring")]
+use error::UnknownAlgorithm;
+
+// Digest function a tree is built with
+pub trait MerkleHasher {
+    fn output_len(&self) -> usize;
+
+    // Writes digest of the concatenated parts to out, which is output_len() bytes long
+    fn hash_parts(&self, parts: &[&[u8]], out: &mut [u8]);
+
+    fn hash_leaf(&self, data: &[u8], out: &mut [u8]) {
+        self.hash_parts(&[data], out)
+    }
+
+    fn hash_nodes(&self, left: &[u8], right: &[u8], out: &mut [u8]) {
+        self.hash_parts(&[left, right], out)
+    }
+}
+
+// Hasher with a stable name, used to store a tree and restore it later
+pub trait NamedHasher: MerkleHasher + Sized {
+    fn name(&self) -> &'static str;
+
+    fn from_name(name: &str) -> Option<Self>;
+}
+
+#[cfg(feature = "ring")]
+static RING_ALGORITHMS: [(&str, &Algorithm); 5] = [
+    ("sha1", &digest::SHA1),
+    ("sha256", &digest::SHA256),
+    ("sha384", &digest::SHA384),
+    ("sha512", &digest::SHA512),
+    ("sha512_256", &digest::SHA512_256),
+];
+
+#[cfg(feature = "ring")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RingHasher {
+    algo: &'static Algorithm,
+}
+
+#[cfg(feature = "ring")]
+impl RingHasher {
+    pub fn new(algo: &'static Algorithm) -> RingHasher {
+        RingHasher { algo }
+    }
+
+    pub fn algorithm(&self) -> &'static Algorithm {
+        self.algo
+    }
+}
+
+#[cfg(feature = "ring")]
+impl From<&'static Algorithm> for RingHasher {
+    fn from(algo: &'static Algorithm) -> RingHasher {
+        RingHasher::new(algo)
+    }
+}
+
+#[cfg(feature = "ring")]
+impl MerkleHasher for RingHasher {
+    fn output_len(&self) -> usize {
+        self.algo.output_len
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]], out: &mut [u8]) {
+        let mut ctx = Context::new(self.algo);
+        for part in parts {
+            ctx.update(part);
+        }
+        out.copy_from_slice(ctx.finish().as_ref());
+    }
+}
+
+#[cfg(feature = "ring")]
+impl NamedHasher for RingHasher {
+    fn name(&self) -> &'static str {
+        RING_ALGORITHMS.iter()
+            .find(|&&(_, algo)| algo == self.algo)
+            .map(|&(name, _)| name)
+            .expect("every ring digest algorithm is named")
+    }
+
+    fn from_name(name: &str) -> Option<RingHasher> {
+        RING_ALGORITHMS.iter()
+            .find(|&&(n, _)| n == name)
+            .map(|&(_, algo)| RingHasher::new(algo))
+    }
+}
+
+// Ring digest algorithm as a value, to be chosen at run time: "sha384".parse::<DigestAlg>().
+// Names are those of the stored formats, in any case.
+#[cfg(feature = "ring")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DigestAlg {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha512_256,
+}
+
+#[cfg(feature = "ring")]
+impl DigestAlg {
+    pub fn algorithm(self) -> &'static Algorithm {
+        match self {
+            DigestAlg::Sha1 => &digest::SHA1,
+            DigestAlg::Sha256 => &digest::SHA256,
+            DigestAlg::Sha384 => &digest::SHA384,
+            DigestAlg::Sha512 => &digest::SHA512,
+            DigestAlg::Sha512_256 => &digest::SHA512_256,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        RingHasher::from(self).name()
+    }
+}
+
+#[cfg(feature = "ring")]
+impl FromStr for DigestAlg {
+    type Err = UnknownAlgorithm;
+
+    fn from_str(name: &str) -> Result<DigestAlg, UnknownAlgorithm> {
+        [DigestAlg::Sha1, DigestAlg::Sha256, DigestAlg::Sha384, DigestAlg::Sha512, DigestAlg::Sha512_256].iter()
+            .find(|alg| alg.name().eq_ignore_ascii_case(name))
+            .cloned()
+            .ok_or_else(|| UnknownAlgorithm { name: name.to_string() })
+    }
+}
+
+#[cfg(feature = "ring")]
+impl From<DigestAlg> for &'static Algorithm {
+    fn from(alg: DigestAlg) -> &'static Algorithm {
+        alg.algorithm()
+    }
+}
+
+#[cfg(feature = "ring")]
+impl From<DigestAlg> for RingHasher {
+    fn from(alg: DigestAlg) -> RingHasher {
+        RingHasher::new(alg.algorithm())
+    }
+}
+
+#[cfg(feature = "keccak")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Keccak256Hasher;
+
+#[cfg(feature = "keccak")]
+impl MerkleHasher for Keccak256Hasher {
+    fn output_len(&self) -> usize {
+        32
+    }
+
+    fn hash_parts(&self, parts: &[&[u8]], out: &mut [u8]) {
+        let mut keccak = Keccak::v256();
+        for part in parts {
+            keccak.update(part);
+        }
+        keccak.finalize(out);
+    }
+}
+
+#[cfg(feature = "keccak")]
+impl NamedHasher for Keccak256Hasher {
+    fn name(&self) -> &'static str {
+        "keccak256"
+    }
+
+    fn from_name(name: &str) -> Option<Keccak256Hasher> {
+        if name == "keccak256" { Some(Keccak256Hasher) } else { None }
+    }
+}
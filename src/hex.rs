@@ -0,0 +1,62 @@
+use std::error::Error;
+use std::fmt;
+
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+
+use hasher::MerkleHasher;
+use proof::{Proof, ProofFormatError};
+use MerkleTree;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HexError {
+    BadChar { position: usize, ch: char },
+    OddLength { len: usize },
+    Format(ProofFormatError),
+}
+
+// Lowercase, two digits per byte
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Accepts both cases, position of a bad character is its byte offset in the string
+pub fn decode(hex: &str) -> Result<Vec<u8>, HexError> {
+    if let Some((position, ch)) = hex.char_indices().find(|&(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(HexError::BadChar { position, ch });
+    }
+    if !hex.len().is_multiple_of(2) {
+        return Err(HexError::OddLength { len: hex.len() });
+    }
+    Ok((0..hex.len() / 2).map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).expect("digits are checked")).collect())
+}
+
+// Proof as one hex string of concatenated hashes, leaf first
+#[cfg(feature = "ring")]
+pub fn decode_proof<A: Into<&'static Algorithm>>(hex: &str, algo: A) -> Result<Proof, HexError> {
+    Proof::from_bytes(&decode(hex)?, algo.into().output_len).map_err(HexError::Format)
+}
+
+impl Proof {
+    pub fn to_hex(&self) -> String {
+        encode(self.as_bytes())
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn root_hex(&self) -> String {
+        encode(self.get_root())
+    }
+}
+
+impl fmt::Display for HexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HexError::BadChar { position, ch } => write!(f, "'{}' at position {} is not a hex digit", ch, position),
+            HexError::OddLength { len } => write!(f, "hex string has odd length {}", len),
+            HexError::Format(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for HexError {}
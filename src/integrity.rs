@@ -0,0 +1,49 @@
+use error::IntegrityError;
+use hasher::MerkleHasher;
+use {level_layout, MerkleTree};
+
+// Stored nodes checked against their children. Leafs are taken as they are and every node above
+// is recomputed from the stored children, so a changed node is reported itself, not its ancestors.
+impl<H: MerkleHasher> MerkleTree<H> {
+    // First mismatch bottom-up and left to right, the padding copy of a level has index level_len
+    pub fn verify_integrity(&self) -> Result<(), IntegrityError> {
+        let layout = level_layout(self.items_count, self.scheme);
+        for level in 0..layout.len() {
+            if level > 0 {
+                if let Some(index) = (0..layout[level].1).find(|&i| !self.node_matches(&layout, level, i)) {
+                    return Err(IntegrityError { level, index });
+                }
+            }
+            if !self.padding_matches(&layout, level) {
+                return Err(IntegrityError { level, index: layout[level].1 });
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn node_matches(&self, layout: &[(usize, usize)], level: usize, index: usize) -> bool {
+        let len = self.hasher.output_len();
+        let (start, _) = layout[level];
+        let (child_start, child_len) = layout[level - 1];
+        let left = (child_start + 2 * index) * len;
+        let hash = if 2 * index + 1 < child_len {
+            self.scheme.hash_pair(&self.array[left..left + len], &self.array[left + len..left + 2 * len], &self.hasher)
+        } else {
+            self.scheme.hash_lone(&self.array[left..left + len], &self.hasher)
+        };
+        hash[..] == self.array[(start + index) * len..(start + index + 1) * len]
+    }
+
+    // Padding is never hashed into the level above, only proofs read it
+    pub(crate) fn padding_matches(&self, layout: &[(usize, usize)], level: usize) -> bool {
+        let (start, level_len) = layout[level];
+        if !self.scheme.pads() || level_len & 1 == 0 || self.scheme.is_root_level(level, level_len) {
+            return true;
+        }
+        let len = self.hasher.output_len();
+        let last = (start + level_len - 1) * len;
+        let mut expected = self.array[last..last + 2 * len].to_vec();
+        self.scheme.write_padding(&mut expected, 0, len);
+        expected[..] == self.array[last..last + 2 * len]
+    }
+}
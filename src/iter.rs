@@ -0,0 +1,50 @@
+use std::slice::Chunks;
+
+use hasher::MerkleHasher;
+use MerkleTree;
+
+// Leaf hashes of the tree in order, the padding copy of the last one left out
+impl<'a, H: MerkleHasher> IntoIterator for &'a MerkleTree<H> {
+    type Item = &'a [u8];
+    type IntoIter = Chunks<'a, u8>;
+
+    fn into_iter(self) -> Chunks<'a, u8> {
+        self.leaves()
+    }
+}
+
+impl<H: MerkleHasher> IntoIterator for MerkleTree<H> {
+    type Item = Vec<u8>;
+    type IntoIter = IntoLeaves;
+
+    // Keeps the node array and drops the levels above the leafs
+    fn into_iter(self) -> IntoLeaves {
+        let len = self.hasher.output_len();
+        let mut array = self.array.into_vec();
+        array.truncate(self.items_count * len);
+        IntoLeaves { array, len, next: 0 }
+    }
+}
+
+pub struct IntoLeaves {
+    array: Vec<u8>,
+    len: usize,
+    next: usize,
+}
+
+impl Iterator for IntoLeaves {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let hash = self.array.get(self.next..self.next + self.len)?.to_vec();
+        self.next += self.len;
+        Some(hash)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = (self.array.len() - self.next) / self.len;
+        (left, Some(left))
+    }
+}
+
+impl ExactSizeIterator for IntoLeaves {}
@@ -0,0 +1,79 @@
+use std::error::Error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json;
+
+use hex;
+use proof::{Position, Proof, ProofFormatError};
+
+// {"algorithm": "sha256", "leaf": "<hex>", "path": ["<hex>", ...], "positions": ["left", ...]},
+// positions are present only for order sensitive trees
+#[derive(Serialize, Deserialize)]
+struct ProofObject {
+    algorithm: String,
+    leaf: String,
+    path: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    positions: Option<Vec<Position>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProofJsonError {
+    Syntax(String),
+    AlgorithmMismatch { got: String, expected: String },
+    BadLeaf,
+    BadPathHash { index: usize },
+    Format(ProofFormatError),
+}
+
+impl fmt::Display for ProofJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProofJsonError::Syntax(ref msg) => write!(f, "not a proof object: {}", msg),
+            ProofJsonError::AlgorithmMismatch { ref got, ref expected } =>
+                write!(f, "proof algorithm is '{}', expected '{}'", got, expected),
+            ProofJsonError::BadLeaf => write!(f, "leaf is not a hex encoded hash"),
+            ProofJsonError::BadPathHash { index } =>
+                write!(f, "path element {} is not a hex encoded hash of the leaf length", index),
+            ProofJsonError::Format(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ProofJsonError {}
+
+impl Proof {
+    pub fn to_json(&self, algorithm: &str) -> String {
+        let object = ProofObject {
+            algorithm: algorithm.to_string(),
+            leaf: hex::encode(self.leaf_hash()),
+            path: self.siblings().map(hex::encode).collect(),
+            positions: self.positions().map(|p| p.to_vec()),
+        };
+        serde_json::to_string(&object).expect("proof object is always serializable")
+    }
+
+    // Digest length is taken from the leaf, every path element must have the same length
+    pub fn from_json(json: &str, algorithm: &str) -> Result<Proof, ProofJsonError> {
+        let object: ProofObject = serde_json::from_str(json).map_err(|e| ProofJsonError::Syntax(e.to_string()))?;
+        if object.algorithm != algorithm {
+            return Err(ProofJsonError::AlgorithmMismatch { got: object.algorithm, expected: algorithm.to_string() });
+        }
+        let mut bytes = match hex::decode(&object.leaf) {
+            Ok(ref leaf) if !leaf.is_empty() => leaf.clone(),
+            _ => return Err(ProofJsonError::BadLeaf),
+        };
+        let digest_len = bytes.len();
+        for (index, path_hash) in object.path.iter().enumerate() {
+            match hex::decode(path_hash) {
+                Ok(ref hash) if hash.len() == digest_len => bytes.extend_from_slice(hash),
+                _ => return Err(ProofJsonError::BadPathHash { index }),
+            }
+        }
+        match object.positions {
+            Some(positions) => Proof::with_positions(&bytes, digest_len, positions),
+            None => Proof::from_bytes(&bytes, digest_len),
+        }.map_err(ProofJsonError::Format)
+    }
+}
@@ -0,0 +1,30 @@
+use hasher::MerkleHasher;
+use {level_layout, MerkleTree};
+
+// One level of the node array. physical_nodes counts the padding copy stored after an odd level
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LevelInfo {
+    pub logical_nodes: usize,
+    pub physical_nodes: usize,
+    pub offset_bytes: usize,
+}
+
+impl LevelInfo {
+    pub fn is_padded(&self) -> bool {
+        self.physical_nodes > self.logical_nodes
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    // Levels bottom-up as build_levels lays them out, they tile the node array in order.
+    // Every level but the root takes its stored length.
+    pub fn level_sizes(&self) -> Vec<LevelInfo> {
+        let len = self.hasher.output_len();
+        let layout = level_layout(self.items_count, self.scheme);
+        layout.iter().enumerate().map(|(level, &(start, level_len))| LevelInfo {
+            logical_nodes: level_len,
+            physical_nodes: if level + 1 < layout.len() { self.scheme.stored_len(level_len) } else { level_len },
+            offset_bytes: start * len,
+        }).collect()
+    }
+}
@@ -0,0 +1,144 @@
+use std::convert::AsRef;
+use std::slice::Chunks;
+use std::sync::OnceLock;
+
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+
+use error::ValidationError;
+use hasher::MerkleHasher;
+#[cfg(feature = "ring")]
+use hasher::RingHasher;
+use proof::Proof;
+use scheme::Scheme;
+use view::Parts;
+use {build_from_leafs, calculate_height, calculate_vec_len, MerkleTree};
+
+// Tree that hashes only the leafs when built. Upper levels are hashed once, on the first call
+// that needs them, and kept from then on next to the leafs, which are never copied for good.
+#[cfg(feature = "ring")]
+pub struct LazyMerkleTree<H: MerkleHasher = RingHasher> {
+    leafs: Vec<u8>,
+    items_count: usize,
+    hasher: H,
+    scheme: Scheme,
+    upper: OnceLock<Vec<u8>>,
+}
+
+#[cfg(not(feature = "ring"))]
+pub struct LazyMerkleTree<H: MerkleHasher> {
+    leafs: Vec<u8>,
+    items_count: usize,
+    hasher: H,
+    scheme: Scheme,
+    upper: OnceLock<Vec<u8>>,
+}
+
+#[cfg(feature = "ring")]
+impl MerkleTree<RingHasher> {
+    // Legacy scheme only, as new builds it. There are no lazy RFC6962 or Bitcoin trees.
+    pub fn new_lazy<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(values: &[T], algo: A) -> LazyMerkleTree {
+        LazyMerkleTree::new_with_hasher(values, RingHasher::new(algo.into()))
+    }
+}
+
+impl<H: MerkleHasher> LazyMerkleTree<H> {
+    // Legacy scheme only, as new_with_hasher of MerkleTree
+    pub fn new_with_hasher<T: AsRef<[u8]>>(values: &[T], hasher: H) -> LazyMerkleTree<H> {
+        let scheme = Scheme::LEGACY;
+        let mut leafs = Vec::with_capacity(values.len() * hasher.output_len());
+        for v in values {
+            leafs.extend_from_slice(&scheme.hash_leaf(v.as_ref(), &hasher));
+        }
+        LazyMerkleTree { leafs, items_count: values.len(), hasher, scheme, upper: OnceLock::new() }
+    }
+
+    pub fn leafs_count(&self) -> usize {
+        self.items_count
+    }
+
+    // Never hashes the upper levels
+    pub fn leaves(&self) -> Chunks<'_, u8> {
+        self.leafs.chunks(self.hasher.output_len())
+    }
+
+    // Whether the upper levels are hashed already
+    pub fn is_computed(&self) -> bool {
+        self.upper.get().is_some()
+    }
+
+    pub fn get_root(&self) -> &[u8] {
+        self.parts().get_root()
+    }
+
+    // Leafs are read without hashing the upper levels
+    pub fn node(&self, level: usize, index: usize) -> Option<&[u8]> {
+        if level == 0 {
+            let len = self.hasher.output_len();
+            return self.leafs.get(index * len..(index + 1) * len);
+        }
+        self.parts().node(level, index)
+    }
+
+    // The value is searched among the leafs, there is no map
+    pub fn build_proof<T: AsRef<[u8]>>(&self, value: &T) -> Option<Proof> {
+        let hash = self.scheme.hash_leaf(value.as_ref(), &self.hasher);
+        self.leaves().position(|leaf| leaf == &hash[..]).and_then(|i| self.build_proof_by_index(i))
+    }
+
+    pub fn build_proof_by_index(&self, index: usize) -> Option<Proof> {
+        if index >= self.items_count { // Levels are not needed to refuse
+            return None;
+        }
+        self.parts().build_proof_by_index(index)
+    }
+
+    pub fn validate(&self, proof: &Proof) -> bool {
+        self.try_validate(proof).is_ok()
+    }
+
+    pub fn try_validate(&self, proof: &Proof) -> Result<(), ValidationError> {
+        self.parts().try_validate(proof)
+    }
+
+    // Eager tree with the same nodes, hashing the upper levels now if they are not yet
+    pub fn into_tree(self) -> MerkleTree<H> {
+        let LazyMerkleTree { mut leafs, items_count, hasher, scheme, upper } = self;
+        let array = match upper.into_inner() {
+            Some(upper) => {
+                leafs.reserve_exact(upper.len());
+                leafs.extend_from_slice(&upper);
+                leafs
+            }
+            None => build_array(leafs, items_count, &hasher, scheme),
+        };
+        MerkleTree {
+            array: array.into(),
+            height: calculate_height(items_count, scheme),
+            items_count,
+            map: None,
+            hasher,
+            scheme,
+        }
+    }
+
+    fn parts(&self) -> Parts<'_, '_, H> {
+        Parts {
+            leafs: &self.leafs,
+            // The copy of the leafs the levels are built over is dropped once they are
+            upper: self.upper.get_or_init(|| {
+                build_array(self.leafs.clone(), self.items_count, &self.hasher, self.scheme).split_off(self.leafs.len())
+            }),
+            items_count: self.items_count,
+            height: calculate_height(self.items_count, self.scheme),
+            hasher: &self.hasher,
+            scheme: self.scheme,
+        }
+    }
+}
+
+fn build_array<H: MerkleHasher>(mut leafs: Vec<u8>, count: usize, hasher: &H, scheme: Scheme) -> Vec<u8> {
+    let upper_len = calculate_vec_len(count, hasher, scheme) - leafs.len();
+    leafs.reserve_exact(upper_len);
+    build_from_leafs(leafs, count, hasher, false, scheme).1
+}
@@ -0,0 +1,167 @@
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, BuildHasherDefault, Hasher};
+use std::mem;
+
+// Hasher for the leaf map that keeps the first 8 bytes of a digest as they are. Leaf hashes
+// are uniformly random already, so there is nothing left to mix. Use it as
+// BuildHasherDefault::<PrefixHasher>::default().
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PrefixHasher(u64);
+
+impl Hasher for PrefixHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.rotate_left(8) ^ u64::from(byte);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = n;
+    }
+}
+
+// How the prefix of a leaf hash becomes a key of the map, SipHash with random keys by default
+pub(crate) enum MapKeys {
+    Random(RandomState),
+    Custom(Box<dyn Fn(u64) -> u64 + Send + Sync>),
+}
+
+impl MapKeys {
+    pub(crate) fn with_hasher<S: BuildHasher + Send + Sync + 'static>(hasher: S) -> MapKeys {
+        MapKeys::Custom(Box::new(move |prefix| hasher.hash_one(prefix)))
+    }
+
+    fn key(&self, hash: &[u8]) -> u64 {
+        match *self {
+            MapKeys::Random(ref state) => state.hash_one(prefix(hash)),
+            MapKeys::Custom(ref f) => f(prefix(hash)),
+        }
+    }
+}
+
+// Keys are hashed already, the tables take them as they are
+type Table<V> = HashMap<u64, V, BuildHasherDefault<PrefixHasher>>;
+
+// Index from leaf hash to leaf positions that does not copy the hashes: positions are kept by
+// a 64 bit key made from the first 8 bytes of the hash, and candidates are compared against the
+// leafs of the tree, so hashes with the same key are told apart. A key that occurs once takes
+// no allocation.
+pub(crate) struct LeafMap {
+    keys: MapKeys,
+    first: Table<usize>, // Lowest position with the key
+    rest: Table<Vec<usize>>, // Other positions with the key, ascending
+}
+
+impl LeafMap {
+    pub(crate) fn build(leafs: &[u8], len: usize) -> LeafMap {
+        LeafMap::with_keys(leafs, len, MapKeys::Random(RandomState::new()))
+    }
+
+    pub(crate) fn with_keys(leafs: &[u8], len: usize, keys: MapKeys) -> LeafMap {
+        let mut map = LeafMap { keys, first: Table::with_capacity_and_hasher(leafs.len() / len, Default::default()), rest: Table::default() };
+        for (i, hash) in leafs.chunks(len).enumerate() {
+            map.insert(hash, i);
+        }
+        map
+    }
+
+    // Same as with_keys over the new leafs, the tables keep their allocations
+    pub(crate) fn rebuild(&mut self, leafs: &[u8], len: usize) {
+        self.first.clear();
+        self.rest.clear();
+        self.first.reserve(leafs.len() / len);
+        for (i, hash) in leafs.chunks(len).enumerate() {
+            self.insert(hash, i);
+        }
+    }
+
+    pub(crate) fn into_keys(self) -> MapKeys {
+        self.keys
+    }
+
+    pub(crate) fn insert(&mut self, hash: &[u8], index: usize) {
+        let key = self.keys.key(hash);
+        let first = self.first.entry(key).or_insert(index);
+        if *first == index {
+            return;
+        }
+        let later = if index < *first { mem::replace(first, index) } else { index };
+        let rest = self.rest.entry(key).or_default();
+        if let Err(position) = rest.binary_search(&later) {
+            rest.insert(position, later);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, hash: &[u8], index: usize) {
+        let key = self.keys.key(hash);
+        let next = match self.rest.get_mut(&key) {
+            Some(rest) if self.first.get(&key) == Some(&index) => Some(rest.remove(0)),
+            Some(rest) => {
+                rest.retain(|&i| i != index);
+                None
+            }
+            None => {
+                if self.first.get(&key) == Some(&index) {
+                    self.first.remove(&key);
+                }
+                return;
+            }
+        };
+        if let Some(next) = next {
+            self.first.insert(key, next);
+        }
+        if self.rest.get(&key).is_some_and(|rest| rest.is_empty()) {
+            self.rest.remove(&key);
+        }
+    }
+
+    // Positions of the hash in leafs, ascending
+    pub(crate) fn indices(&self, hash: &[u8], leafs: &[u8]) -> Vec<usize> {
+        self.candidates(hash).filter(|&i| is_at(hash, leafs, i)).collect()
+    }
+
+    pub(crate) fn find(&self, hash: &[u8], leafs: &[u8]) -> Option<usize> {
+        self.candidates(hash).find(|&i| is_at(hash, leafs, i))
+    }
+
+    // Approximate heap bytes, one control byte per table slot besides the entries
+    pub(crate) fn heap_size(&self) -> usize {
+        let first = self.first.capacity() * (mem::size_of::<(u64, usize)>() + 1);
+        let rest = self.rest.capacity() * (mem::size_of::<(u64, Vec<usize>)>() + 1);
+        first + rest + self.rest.values().map(|r| r.capacity() * mem::size_of::<usize>()).sum::<usize>()
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.first.shrink_to_fit();
+        self.rest.shrink_to_fit();
+        for rest in self.rest.values_mut() {
+            rest.shrink_to_fit();
+        }
+    }
+
+    // Lowest position and the other ones of every key, hashes sharing a key are not told apart
+    pub(crate) fn groups(&self) -> impl Iterator<Item = (usize, Option<&[usize]>)> + '_ {
+        self.first.iter().map(move |(key, &first)| (first, self.rest.get(key).map(|rest| &rest[..])))
+    }
+
+    fn candidates<'a>(&'a self, hash: &[u8]) -> impl Iterator<Item = usize> + 'a {
+        let key = self.keys.key(hash);
+        self.first.get(&key).cloned().into_iter().chain(self.rest.get(&key).into_iter().flat_map(|r| r.iter().cloned()))
+    }
+}
+
+fn prefix(hash: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    let n = hash.len().min(8);
+    bytes[..n].copy_from_slice(&hash[..n]);
+    u64::from_le_bytes(bytes)
+}
+
+fn is_at(hash: &[u8], leafs: &[u8], index: usize) -> bool {
+    leafs.get(index * hash.len()..(index + 1) * hash.len()) == Some(hash)
+}
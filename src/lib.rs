@@ -1,68 +1,501 @@
+#[cfg(feature = "wasm")]
+extern crate js_sys;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "ring")]
 extern crate ring;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(feature = "keccak")]
+extern crate tiny_keccak;
+#[cfg(feature = "signing")]
+extern crate untrusted;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "zeroize")]
+extern crate zeroize;
 
-use std::collections::HashMap;
-use std::convert::AsRef;
-use std::hash::Hash;
-use std::mem;
+use std::convert::{AsRef, TryFrom};
+use std::hash::{Hash, Hasher};
+use std::ops::{Index, Range};
+use std::slice::Chunks;
 
-use ring::digest::{Algorithm, Context, Digest};
+#[cfg(feature = "ring")]
+use ring::digest::{self, Algorithm, Context, Digest, SHA256};
 
-pub struct MerkleTree {
-    array: Vec<u8>,
+use leaf_map::LeafMap;
+use nodes::Nodes;
+use view::Parts;
+use scheme::Scheme;
+
+#[cfg(feature = "signing")]
+pub use attestation::SignedRoot;
+pub use builder::MerkleTreeBuilder;
+#[cfg(all(feature = "serde", feature = "ring"))]
+pub use canonical::EncodeError;
+#[cfg(feature = "ring")]
+pub use collect::MerkleTreeCollector;
+pub use compact::CompactError;
+#[cfg(feature = "ring")]
+pub use consistency::verify_consistency;
+pub use error::{CapacityError, DiffError, Error, IntegrityError, LoadError, ProofError, UnknownAlgorithm, ValidationError, ValidationTrace};
+#[cfg(feature = "keccak")]
+pub use hasher::Keccak256Hasher;
+pub use hasher::{MerkleHasher, NamedHasher};
+#[cfg(feature = "ring")]
+pub use hasher::{DigestAlg, RingHasher};
+pub use iter::IntoLeaves;
+#[cfg(feature = "serde")]
+pub use json::ProofJsonError;
+pub use layout::LevelInfo;
+pub use lazy::LazyMerkleTree;
+pub use leaf_map::PrefixHasher;
+pub use memory::MemoryUsage;
+#[cfg(feature = "ring")]
+pub use multiproof::verify_multiproof;
+pub use multiproof::MultiProof;
+pub use proof::{Position, Proof, ProofFormatError, ProofStep, StepPosition};
+#[cfg(feature = "ring")]
+pub use range::verify_range;
+pub use range::RangeProof;
+#[cfg(feature = "ring")]
+pub use root::{roots_equal, Root};
+pub use root_log::{RootEntry, RootLog};
+#[cfg(feature = "ring")]
+pub use salted::{verify_salted_value, SaltedMerkleTree, Salts, SALT_LEN};
+pub use scheme::PaddingStrategy;
+pub use shared::SharedMerkleTree;
+#[cfg(feature = "ring")]
+pub use sorted::{verify_absence, AbsenceProof, Neighbour, PrefixProof, SortedMerkleTree};
+pub use stats::LeafStats;
+pub use view::MerkleTreeView;
+
+mod append;
+#[cfg(feature = "signing")]
+mod attestation;
+mod batch;
+mod binary;
+mod builder;
+#[cfg(all(feature = "serde", feature = "ring"))]
+mod canonical;
+#[cfg(feature = "ring")]
+mod collect;
+mod compact;
+mod consistency;
+mod debug;
+mod diff;
+mod dot;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod hasher;
+pub mod hex;
+mod integrity;
+mod iter;
+#[cfg(feature = "serde")]
+mod json;
+mod layout;
+mod lazy;
+mod leaf_map;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod memory;
+#[cfg(feature = "ring")]
+pub mod mmr;
+mod multiproof;
+mod nodes;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod proof;
+mod range;
+#[cfg(feature = "ring")]
+mod reader;
+#[cfg(feature = "ring")]
+mod root;
+mod root_log;
+#[cfg(feature = "ring")]
+mod salted;
+mod save;
+mod scheme;
+mod shared;
+#[cfg(feature = "ring")]
+pub mod sparse;
+mod subtree;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "ring")]
+mod sorted;
+mod stats;
+mod threaded;
+mod update;
+#[cfg(all(feature = "serde", feature = "ring"))]
+pub mod vectors;
+mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "zeroize")]
+mod wipe;
+
+#[cfg(feature = "ring")]
+pub struct MerkleTree<H: MerkleHasher = RingHasher> {
+    array: Nodes,
+    height: usize,
+    items_count: usize,
+    map: Option<LeafMap>,
+    hasher: H,
+    scheme: Scheme,
+}
+
+// There is no default hasher without ring
+#[cfg(not(feature = "ring"))]
+pub struct MerkleTree<H: MerkleHasher> {
+    array: Nodes,
     height: usize,
     items_count: usize,
-    map: Option<HashMap<Vec<u8>, usize>>,
-    algo: &'static Algorithm,
+    map: Option<LeafMap>,
+    hasher: H,
+    scheme: Scheme,
+}
+
+// Same hasher, scheme and nodes. The leaf map is only an index and does not count
+impl<H: MerkleHasher + PartialEq> PartialEq for MerkleTree<H> {
+    fn eq(&self, other: &MerkleTree<H>) -> bool {
+        self.items_count == other.items_count
+            && self.get_root() == other.get_root() // Differs first in most cases
+            && self.hasher == other.hasher
+            && self.scheme == other.scheme
+            && self.array[..] == other.array[..]
+    }
+}
+
+impl<H: MerkleHasher + Eq> Eq for MerkleTree<H> {}
+
+impl<H: MerkleHasher> Hash for MerkleTree<H> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.get_root().hash(state);
+    }
 }
 
-impl MerkleTree {
-    pub fn new<T: AsRef<[u8]>>(values: &Vec<T>, algo: &'static Algorithm) -> MerkleTree {
+// Leaf hash as get_leaf_hash gives it, the padding copy of the last leaf is out of range
+impl<H: MerkleHasher> Index<usize> for MerkleTree<H> {
+    type Output = [u8];
+
+    fn index(&self, index: usize) -> &[u8] {
+        match self.get_leaf_hash(index) {
+            Some(hash) => hash,
+            None => panic!("leaf index {} is out of range for a tree of {} leafs", index, self.items_count),
+        }
+    }
+}
+
+#[cfg(feature = "ring")]
+impl MerkleTree<RingHasher> {
+    pub fn new<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(values: &[T], algo: A) -> MerkleTree {
         Self::new_with_flag(values, algo, false)
     }
 
-    pub fn new_with_map<T: AsRef<[u8]>>(values: &Vec<T>, algo: &'static Algorithm) -> MerkleTree {
+    // Same as new, with an error instead of a panic when the node array would not fit in usize
+    pub fn try_new<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(values: &[T], algo: A) -> Result<MerkleTree, CapacityError> {
+        MerkleTree::try_new_with_hasher(values, RingHasher::new(algo.into()))
+    }
+
+    // Bytes of the node array new would allocate over count leafs, nothing is allocated
+    pub fn required_size<A: Into<&'static Algorithm>>(count: usize, algo: A) -> Result<usize, CapacityError> {
+        checked_vec_len(count, algo.into().output_len, Scheme::LEGACY)
+    }
+
+    pub fn new_with_map<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(values: &[T], algo: A) -> MerkleTree {
         Self::new_with_flag(values, algo, true)
     }
 
-    pub fn new_with_flag<T: AsRef<[u8]>>(values: &Vec<T>, algo: &'static Algorithm, use_map: bool) -> MerkleTree {
-        let (height, array, map) = build_tree(values, algo, use_map);
+    pub fn new_with_flag<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(values: &[T], algo: A, use_map: bool) -> MerkleTree {
+        MerkleTreeBuilder::new().algorithm(algo).use_map(use_map).build(values).expect("algorithm is set")
+    }
+
+    // Values can come from any collection or iterator, not only a slice
+    pub fn new_from_iter<T: AsRef<[u8]>, I: IntoIterator<Item = T>, A: Into<&'static Algorithm>>(values: I, algo: A) -> MerkleTree {
+        MerkleTreeBuilder::new().algorithm(algo).build_from_iter(values).expect("algorithm is set")
+    }
+
+    // Consumes values once, only their hashes are kept. The leaf level is reserved for
+    // size_hint leafs if given, otherwise for the lower bound reported by the iterator.
+    pub fn from_iter_streaming<T: AsRef<[u8]>, I: IntoIterator<Item = T>, A: Into<&'static Algorithm>>(values: I, algo: A, size_hint: Option<usize>) -> MerkleTree {
+        let values = values.into_iter();
+        let capacity = size_hint.unwrap_or_else(|| values.size_hint().0);
+        MerkleTree::new_from_iter_with_scheme(values, RingHasher::new(algo.into()), false, Scheme::LEGACY, capacity)
+    }
+
+    // Same layout and sorted pairs as new, with leaf and node hashes domain separated
+    pub fn new_domain_separated<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(values: &[T], algo: A) -> MerkleTree {
+        MerkleTreeBuilder::new().algorithm(algo).domain_separation(true).build(values).expect("algorithm is set")
+    }
+
+    // Leafs are H(0x00 || value), nodes are H(0x01 || left || right) in tree order,
+    // odd nodes are promoted to the next level unchanged
+    pub fn new_rfc6962<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(values: &[T], algo: A) -> MerkleTree {
+        Self::new_rfc6962_with_hasher(values, RingHasher::new(algo.into()))
+    }
+
+    pub fn from_leaf_hashes<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(hashes: &[T], algo: A) -> Result<MerkleTree, Error> {
+        MerkleTreeBuilder::new().algorithm(algo).build_from_leaf_hashes(hashes)
+    }
+
+    pub fn from_leaf_hashes_with_map<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(hashes: &[T], algo: A) -> Result<MerkleTree, Error> {
+        MerkleTreeBuilder::new().algorithm(algo).use_map(true).build_from_leaf_hashes(hashes)
+    }
+
+    // Leaf is the digest of whatever f feeds into the context for the value, pairs are hashed as in new
+    pub fn new_with_leaf_hasher<T, F: Fn(&T, &mut Context), A: Into<&'static Algorithm>>(values: &[T], algo: A, f: F) -> MerkleTree {
+        let algo = algo.into();
+        let hashes: Vec<_> = values.iter().map(|v| leaf_digest(v, algo, &f)).collect();
+        MerkleTree::from_leaf_hashes(&hashes, algo).expect("leafs are digests of the algorithm")
+    }
+
+    // Proof of a value of a tree built by new_with_leaf_hasher, f must be the same
+    pub fn build_proof_with_leaf_hasher<T, F: Fn(&T, &mut Context)>(&self, value: &T, f: F) -> Option<Proof> {
+        self.build_proof_by_hash(leaf_digest(value, self.algorithm(), &f).as_ref())
+    }
+
+    // Leaf is H(len(key) as u64 LE || key || value), so no two entries share their bytes
+    pub fn from_entries<K: AsRef<[u8]>, V: AsRef<[u8]>, A: Into<&'static Algorithm>>(entries: &[(K, V)], algo: A) -> MerkleTree {
+        MerkleTree::new_with_leaf_hasher(entries, algo, hash_entry)
+    }
+
+    pub fn build_proof_for_key<K: AsRef<[u8]>, V: AsRef<[u8]>>(&self, key: K, value: V) -> Option<Proof> {
+        self.build_proof_with_leaf_hasher(&(key, value), hash_entry)
+    }
+
+    // Transaction merkle tree of a block: txids are leafs as is, nodes are SHA256(SHA256(left || right))
+    // in tree order, odd node is paired with itself. Txids are in internal byte order,
+    // which is reversed compared to the hex shown by block explorers.
+    pub fn new_bitcoin<T: AsRef<[u8]>>(txids: &[T]) -> Result<MerkleTree, Error> {
+        MerkleTreeBuilder::new().algorithm(&SHA256).scheme(Scheme::BITCOIN).build_from_leaf_hashes(txids)
+    }
+
+    pub fn from_bytes<A: Into<&'static Algorithm>>(bytes: &[u8], items_count: usize, algo: A) -> Result<MerkleTree, LoadError> {
+        MerkleTree::from_bytes_with_hasher(bytes, items_count, RingHasher::new(algo.into()))
+    }
+
+    pub fn algorithm(&self) -> &'static Algorithm {
+        self.hasher.algorithm()
+    }
+}
+
+#[cfg(feature = "keccak")]
+impl MerkleTree<Keccak256Hasher> {
+    // Sorted pair keccak256 tree, verifiable with OpenZeppelin MerkleProof
+    pub fn new_keccak256<T: AsRef<[u8]>>(values: &[T]) -> MerkleTree<Keccak256Hasher> {
+        Self::new_with_hasher(values, Keccak256Hasher, false)
+    }
+
+    pub fn from_keccak256_leaf_hashes<T: AsRef<[u8]>>(hashes: &[T]) -> Result<MerkleTree<Keccak256Hasher>, Error> {
+        Self::from_leaf_hashes_with_hasher(hashes, Keccak256Hasher, false)
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn new_with_hasher<T: AsRef<[u8]>>(values: &[T], hasher: H, use_map: bool) -> MerkleTree<H> {
+        MerkleTreeBuilder::with_hasher(hasher).use_map(use_map).build(values).expect("hasher is set")
+    }
+
+    pub fn try_new_with_hasher<T: AsRef<[u8]>>(values: &[T], hasher: H) -> Result<MerkleTree<H>, CapacityError> {
+        checked_vec_len(values.len(), hasher.output_len(), Scheme::LEGACY)?;
+        Ok(MerkleTree::new_with_hasher(values, hasher, false))
+    }
+
+    pub fn new_rfc6962_with_hasher<T: AsRef<[u8]>>(values: &[T], hasher: H) -> MerkleTree<H> {
+        MerkleTreeBuilder::with_hasher(hasher).scheme(Scheme::RFC6962).build(values).expect("hasher is set")
+    }
+
+    pub fn from_leaf_hashes_with_hasher<T: AsRef<[u8]>>(hashes: &[T], hasher: H, use_map: bool) -> Result<MerkleTree<H>, Error> {
+        MerkleTreeBuilder::with_hasher(hasher).use_map(use_map).build_from_leaf_hashes(hashes)
+    }
+
+    pub(crate) fn new_with_scheme<T: AsRef<[u8]>>(values: &[T], hasher: H, use_map: bool, scheme: Scheme) -> MerkleTree<H> {
+        let (height, array, map) = build_tree(values, &hasher, use_map, scheme);
         MerkleTree {
-            array: array,
-            height: height,
+            array: array.into(),
+            height,
             items_count: values.len(),
-            map: map,
-            algo: algo,
+            map,
+            hasher,
+            scheme,
         }
     }
 
-    pub fn build_proof<T: Eq + Hash + AsRef<[u8]>>(&self, value: &T) -> Option<Vec<&[u8]>> {
-        let hash = get_hash(value.as_ref(), self.algo).as_ref().to_vec();
-        let index = self.find_item(&hash);
-        let mut vec = vec![];
-        match index {
-            Some(i) => {
-                vec.push(&self.array[(i * self.algo.output_len)..(i * self.algo.output_len + self.algo.output_len)]);
-                Some(self.add_level(0, i, self.items_count, vec))
+    pub(crate) fn new_from_iter_with_scheme<T: AsRef<[u8]>, I: Iterator<Item = T>>(values: I, hasher: H, use_map: bool, scheme: Scheme,
+                                                                                   capacity: usize) -> MerkleTree<H> {
+        let mut tree: Vec<u8> = Vec::with_capacity(capacity * hasher.output_len());
+        for v in values { //Hash leafs as they come
+            tree.extend_from_slice(scheme.hash_leaf(v.as_ref(), &hasher).as_ref());
+        }
+        let count = tree.len() / hasher.output_len();
+        let upper_len = calculate_vec_len(count, &hasher, scheme) - tree.len();
+        tree.reserve_exact(upper_len);
+        let (height, array, map) = build_from_leafs(tree, count, &hasher, use_map, scheme);
+        MerkleTree {
+            array: array.into(),
+            height,
+            items_count: count,
+            map,
+            hasher,
+            scheme,
+        }
+    }
+
+    // Takes back the bytes of as_bytes. Only the length is checked, not the hashes
+    pub fn from_bytes_with_hasher(bytes: &[u8], items_count: usize, hasher: H) -> Result<MerkleTree<H>, LoadError> {
+        MerkleTree::from_array(bytes.to_vec().into(), items_count, hasher, false, Scheme::LEGACY)
+    }
+
+    fn from_array(array: Nodes, items_count: usize, hasher: H, use_map: bool, scheme: Scheme) -> Result<MerkleTree<H>, LoadError> {
+        let expected = calculate_vec_len(items_count, &hasher, scheme);
+        if array.len() != expected {
+            return Err(LoadError::BadLength { got: array.len(), expected });
+        }
+        let map = if use_map { Some(build_map(&array[..items_count * hasher.output_len()], &hasher)) } else { None };
+        Ok(MerkleTree {
+            array,
+            height: calculate_height(items_count, scheme),
+            items_count,
+            map,
+            hasher,
+            scheme,
+        })
+    }
+
+    // Standalone tree over the leafs in range, built from their stored hashes
+    pub fn subtree(&self, range: Range<usize>) -> Option<MerkleTree<H>> where H: Clone {
+        if range.start >= range.end || range.end > self.items_count {
+            return None;
+        }
+        let len = self.hasher.output_len();
+        let count = range.end - range.start;
+        let mut tree = Vec::with_capacity(calculate_vec_len(count, &self.hasher, self.scheme));
+        tree.extend_from_slice(&self.array[range.start * len..range.end * len]);
+        let (height, array, map) = build_from_leafs(tree, count, &self.hasher, self.map.is_some(), self.scheme);
+        Some(MerkleTree {
+            array: array.into(),
+            height,
+            items_count: count,
+            map,
+            hasher: self.hasher.clone(),
+            scheme: self.scheme,
+        })
+    }
+
+    pub(crate) fn from_leaf_hashes_with_scheme<T: AsRef<[u8]>>(hashes: &[T], hasher: H, use_map: bool, scheme: Scheme) -> Result<MerkleTree<H>, Error> {
+        let mut tree: Vec<u8> = Vec::with_capacity(calculate_vec_len(hashes.len(), &hasher, scheme));
+        for (i, h) in hashes.iter().enumerate() {
+            let hash = h.as_ref();
+            if hash.len() != hasher.output_len() {
+                return Err(Error::BadHashLength { index: i, len: hash.len(), expected: hasher.output_len() });
             }
-            None => None
+            tree.extend_from_slice(hash);
         }
+        let (height, array, map) = build_from_leafs(tree, hashes.len(), &hasher, use_map, scheme);
+        Ok(MerkleTree {
+            array: array.into(),
+            height,
+            items_count: hashes.len(),
+            map,
+            hasher,
+            scheme,
+        })
+    }
+
+    // Proves the first occurrence of the value, with or without the map
+    pub fn build_proof<T: Eq + Hash + AsRef<[u8]>>(&self, value: &T) -> Option<Proof> {
+        self.try_build_proof(value).ok()
+    }
+
+    pub fn try_build_proof<T: AsRef<[u8]>>(&self, value: &T) -> Result<Proof, ProofError> {
+        let hash = self.scheme.hash_leaf(value.as_ref(), &self.hasher);
+        self.try_build_proof_by_hash(&hash)
+    }
+
+    // One proof per occurrence of the value, in ascending index order
+    pub fn build_proof_all<T: AsRef<[u8]>>(&self, value: &T) -> Vec<Proof> {
+        self.indices_of(value).into_iter().filter_map(|i| self.build_proof_by_index(i)).collect()
+    }
+
+    pub fn build_proof_by_hash(&self, hash: &[u8]) -> Option<Proof> {
+        self.try_build_proof_by_hash(hash).ok()
+    }
+
+    pub fn try_build_proof_by_hash(&self, hash: &[u8]) -> Result<Proof, ProofError> {
+        if self.items_count == 0 {
+            return Err(ProofError::EmptyTree);
+        }
+        if hash.len() != self.hasher.output_len() {
+            return Err(ProofError::BadHashLength { len: hash.len(), expected: self.hasher.output_len() });
+        }
+        self.find_item(hash).and_then(|i| self.build_proof_by_index(i)).ok_or(ProofError::NotFound)
+    }
+
+    pub fn build_proof_by_index(&self, index: usize) -> Option<Proof> {
+        self.parts().build_proof_by_index(index)
+    }
+
+    pub fn contains<T: AsRef<[u8]>>(&self, value: &T) -> bool {
+        self.index_of(value).is_some()
+    }
+
+    // Lowest index when the value occurs more than once
+    pub fn index_of<T: AsRef<[u8]>>(&self, value: &T) -> Option<usize> {
+        self.find_item(self.scheme.hash_leaf(value.as_ref(), &self.hasher).as_ref())
+    }
+
+    pub fn indices_of<T: AsRef<[u8]>>(&self, value: &T) -> Vec<usize> {
+        let hash = self.scheme.hash_leaf(value.as_ref(), &self.hasher);
+        if let Some(ref m) = self.map {
+            return m.indices(&hash, &self.array[..self.items_count * self.hasher.output_len()]);
+        }
+        self.array[..self.items_count * self.hasher.output_len()]
+            .chunks(self.hasher.output_len())
+            .enumerate()
+            .filter(|&(_, leaf)| leaf == &hash[..])
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    // Builds the map from the leaf hashes already in the tree, nothing is hashed again
+    pub fn enable_map(&mut self) {
+        if self.map.is_none() {
+            self.map = Some(build_map(&self.array[..self.items_count * self.hasher.output_len()], &self.hasher));
+        }
+    }
+
+    pub fn disable_map(&mut self) {
+        self.map = None;
+    }
+
+    pub fn has_map(&self) -> bool {
+        self.map.is_some()
     }
 
-    fn find_item(&self, hash: &Vec<u8>) -> Option<usize> {
+    // Approximate heap bytes taken by the leaf map, None without one
+    pub fn map_size(&self) -> Option<usize> {
+        self.map.as_ref().map(|m| m.heap_size())
+    }
+
+    // Bytes allocated for the node array, which rebuild reuses
+    pub fn capacity(&self) -> usize {
+        self.array.capacity()
+    }
+
+    fn find_item(&self, hash: &[u8]) -> Option<usize> {
         match self.map {
-            Some(ref m) => { // if we have a map of items
-                match m.get(hash) {
-                    None => None,
-                    Some(index) => {
-                        Some(*index)
-                    }
-                }
-            }
+            Some(ref m) => m.find(hash, &self.array[..self.items_count * self.hasher.output_len()]), // if we have a map of items
             None => { // linear search item in a loop
                 let mut result = None;
                 for index in 0..self.items_count {
-                    let start = index * self.algo.output_len;
-                    if hash.as_slice() == &self.array[start..(start + self.algo.output_len)] {
+                    let start = index * self.hasher.output_len();
+                    if hash == &self.array[start..(start + self.hasher.output_len())] {
                         result = Some(index);
                         break;
                     }
@@ -72,53 +505,210 @@ impl MerkleTree {
         }
     }
 
-    fn add_level<'a>(&'a self, start_index: usize, index: usize, mut level_len: usize, mut result: Vec<&'a [u8]>) -> Vec<&'a [u8]> {
-        level_len += level_len & 1;
-        let (sibling, parent) = calculate_relatives(index);
-        result.push(&self.array[
-            (start_index + sibling * self.algo.output_len)..(start_index + sibling * self.algo.output_len + self.algo.output_len)
-            ]); //Add sibling to result
-        let next_level_len = level_len / 2;
-        if next_level_len == 1 { // Do not include root to proof
-            return result;
-        }
-        self.add_level(start_index + level_len * self.algo.output_len, parent, next_level_len, result)
-    }
 
     pub fn is_empty(&self) -> bool {
         self.nodes_count() == 0
     }
 
     pub fn get_root(&self) -> &[u8] {
-        if self.is_empty() {
-            return &[];
-        }
-        let root_index = self.array.len() - self.algo.output_len;
-        &self.array[root_index..] // Last item
+        self.parts().get_root()
+    }
+
+    // Root as N bytes, None when N is not the digest length or the tree is empty
+    pub fn root_array<const N: usize>(&self) -> Option<[u8; N]> {
+        <[u8; N]>::try_from(self.get_root()).ok()
     }
 
     pub fn nodes_count(&self) -> usize {
-        self.array.len() / self.algo.output_len
+        self.level_sizes().iter().map(|level| level.physical_nodes).sum()
     }
 
     pub fn leafs_count(&self) -> usize {
         self.items_count
     }
 
+    pub fn get_leaf_hash(&self, index: usize) -> Option<&[u8]> {
+        if index >= self.items_count {
+            return None; // Duplicate of the last leaf included
+        }
+        let len = self.hasher.output_len();
+        Some(&self.array[index * len..(index + 1) * len])
+    }
+
+    // Same as get_leaf_hash as N bytes, None also when N is not the digest length
+    pub fn get_leaf_hash_array<const N: usize>(&self, index: usize) -> Option<[u8; N]> {
+        self.get_leaf_hash(index).and_then(|hash| <[u8; N]>::try_from(hash).ok())
+    }
+
+    // Leaf hashes in order, without the duplicate of the last one
+    pub fn leaves(&self) -> Chunks<'_, u8> {
+        self.parts().leaves()
+    }
+
+    // Real nodes of level i, leafs are level 0 and the root is the last one. Padding duplicates are left out.
+    pub fn level(&self, i: usize) -> Option<&[u8]> {
+        self.levels().nth(i)
+    }
+
+    // Node by level and index within the level, offsets are computed from the leaf count
+    pub fn node(&self, level: usize, index: usize) -> Option<&[u8]> {
+        self.parts().node(level, index)
+    }
+
+    pub fn levels(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.parts().levels()
+    }
+
+    // Whole node array: leaf hashes, then every level bottom-up, the root last. A level of odd
+    // length is followed by a copy of its last node. This layout is kept stable between versions.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.array
+    }
+
     pub fn data_size(&self) -> usize {
-        self.array.len()
+        let size = self.nodes_count() * self.hasher.output_len();
+        debug_assert_eq!(self.array.len(), size, "node array does not match its layout");
+        size
     }
 
     pub fn height(&self) -> usize {
         self.height
     }
 
-    pub fn validate(&self, proof: &Vec<&[u8]>) -> bool {
-        proof[2..].iter()
-            .fold(
-                get_pair_hash(proof[0], proof[1], self.algo),
-                |a, b| get_pair_hash(a.as_ref(), b, self.algo)
-            ).as_ref() == self.get_root()
+    // Bytes of a proof from build_proof, leaf hash included. The longest one when odd nodes are
+    // promoted, all proofs have this length otherwise. 0 for an empty tree
+    pub fn expected_proof_len(&self) -> usize {
+        proof_hashes(self.items_count, self.scheme) * self.hasher.output_len()
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn digest_len(&self) -> usize {
+        self.hasher.output_len()
+    }
+
+    pub fn validate(&self, proof: &Proof) -> bool {
+        self.try_validate(proof).is_ok()
+    }
+
+    pub fn validate_bytes(&self, proof: &[u8]) -> bool {
+        match Proof::from_bytes(proof, self.hasher.output_len()) {
+            Ok(p) => self.validate(&p),
+            Err(_) => false,
+        }
+    }
+
+    pub fn try_validate(&self, proof: &Proof) -> Result<(), ValidationError> {
+        self.parts().try_validate(proof)
+    }
+
+    pub(crate) fn parts(&self) -> Parts<'_, '_, H> {
+        Parts::new(&self.array, self.items_count, self.height, &self.hasher, self.scheme)
+    }
+
+    pub fn validate_value<T: AsRef<[u8]>>(&self, value: &T, proof: &Proof) -> bool {
+        self.scheme.hash_leaf(value.as_ref(), &self.hasher) == proof.leaf_hash() && self.validate(proof)
+    }
+}
+
+impl<H: NamedHasher> MerkleTree<H> {
+    // Name of the digest algorithm, same as used by the stored formats
+    pub fn algorithm_name(&self) -> &'static str {
+        self.hasher.name()
+    }
+
+    // Restores a stored tree, checking that the parts fit together
+    fn from_parts(algorithm: &str, scheme: Scheme, height: usize, items_count: usize, use_map: bool,
+                  array: Nodes) -> Result<MerkleTree<H>, LoadError> {
+        let hasher = H::from_name(algorithm).ok_or_else(|| LoadError::UnknownAlgorithm(algorithm.to_string()))?;
+        let expected = calculate_height(items_count, scheme);
+        if height != expected {
+            return Err(LoadError::BadHeight { got: height, expected });
+        }
+        MerkleTree::from_array(array, items_count, hasher, use_map, scheme)
+    }
+}
+
+#[cfg(feature = "ring")]
+fn hash_entry<K: AsRef<[u8]>, V: AsRef<[u8]>>(entry: &(K, V), ctx: &mut Context) {
+    let key = entry.0.as_ref();
+    ctx.update(&(key.len() as u64).to_le_bytes());
+    ctx.update(key);
+    ctx.update(entry.1.as_ref());
+}
+
+#[cfg(feature = "ring")]
+fn leaf_digest<T, F: Fn(&T, &mut Context)>(value: &T, algo: &'static Algorithm, f: &F) -> Digest {
+    let mut ctx = Context::new(algo);
+    f(value, &mut ctx);
+    ctx.finish()
+}
+
+#[cfg(feature = "ring")]
+pub fn verify_proof<A: Into<&'static Algorithm>>(root: &[u8], proof: &[u8], algo: A) -> bool {
+    let algo = algo.into();
+    match Proof::from_bytes(proof, algo.output_len) {
+        Ok(p) => root.len() == algo.output_len && roots_equal(&calculate_root(&p, &RingHasher::new(algo), Scheme::LEGACY), root),
+        Err(_) => false,
+    }
+}
+
+// Height of MerkleTree::new over leaf_count values, nothing is built
+pub fn height_for(leaf_count: usize) -> usize {
+    calculate_height(leaf_count, Scheme::LEGACY)
+}
+
+// nodes_count of MerkleTree::new over leaf_count values, padding copies included.
+// Panics when the count does not fit in usize.
+pub fn nodes_for(leaf_count: usize) -> usize {
+    checked_vec_len(leaf_count, 1, Scheme::LEGACY).unwrap_or_else(|_| panic!("{} leafs take more than {} nodes", leaf_count, usize::MAX))
+}
+
+// data_size of MerkleTree::new over leaf_count values, the same as MerkleTree::required_size
+#[cfg(feature = "ring")]
+pub fn data_size_for<A: Into<&'static Algorithm>>(leaf_count: usize, algo: A) -> Result<usize, CapacityError> {
+    checked_vec_len(leaf_count, algo.into().output_len, Scheme::LEGACY)
+}
+
+// expected_proof_len of MerkleTree::new over leaf_count values, a lone leaf is proven with its copy
+#[cfg(feature = "ring")]
+pub fn proof_len_for<A: Into<&'static Algorithm>>(leaf_count: usize, algo: A) -> usize {
+    proof_hashes(leaf_count, Scheme::LEGACY) * algo.into().output_len
+}
+
+#[cfg(feature = "ring")]
+pub fn verify_value<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(root: &[u8], value: &T, proof: &[u8], algo: A) -> bool {
+    let algo = algo.into();
+    proof.len() >= algo.output_len
+        && get_hash(value.as_ref(), algo).as_ref() == &proof[..algo.output_len]
+        && verify_proof(root, proof, algo)
+}
+
+// Positions are only consulted for order sensitive schemes
+fn calculate_root<H: MerkleHasher>(proof: &Proof, hasher: &H, scheme: Scheme) -> Vec<u8> {
+    proof.siblings().enumerate().fold(proof.leaf_hash().to_vec(), |hash, (i, sibling)| {
+        proof_step(proof, i, &hash, sibling, hasher, scheme)
+    })
+}
+
+// Every hash calculate_root goes through, leaf hash first and root last
+fn calculate_chain<H: MerkleHasher>(proof: &Proof, hasher: &H, scheme: Scheme) -> Vec<u8> {
+    let len = proof.digest_len();
+    let mut chain = Vec::with_capacity(proof.as_bytes().len());
+    chain.extend_from_slice(proof.leaf_hash());
+    for (i, sibling) in proof.siblings().enumerate() {
+        let hash = proof_step(proof, i, &chain[i * len..(i + 1) * len], sibling, hasher, scheme);
+        chain.extend_from_slice(&hash);
+    }
+    chain
+}
+
+fn proof_step<H: MerkleHasher>(proof: &Proof, i: usize, hash: &[u8], sibling: &[u8], hasher: &H, scheme: Scheme) -> Vec<u8> {
+    match proof.positions().map(|p| p[i]) {
+        Some(Position::Left) => scheme.hash_pair(sibling, hash, hasher),
+        _ => scheme.hash_pair(hash, sibling, hasher),
     }
 }
 
@@ -133,81 +723,128 @@ fn calculate_relatives(index: usize) -> (usize, usize) {
     (sibling, parent)
 }
 
-fn build_tree<T: AsRef<[u8]>>(values: &Vec<T>, algo: &'static Algorithm, use_map: bool) -> (usize, Vec<u8>, Option<HashMap<Vec<u8>, usize>>) {
-    let mut map: Option<HashMap<Vec<u8>, usize>> = if use_map { Some(HashMap::new()) } else { None };
-    let vec_len = calculate_vec_len(values.len(), algo);
+fn build_tree<T: AsRef<[u8]>, H: MerkleHasher>(values: &[T], hasher: &H, use_map: bool, scheme: Scheme) -> (usize, Vec<u8>, Option<LeafMap>) {
+    let vec_len = calculate_vec_len(values.len(), hasher, scheme);
     let mut tree: Vec<u8> = Vec::with_capacity(vec_len);
-    for (i, v) in values.iter().enumerate() { //Hash leafs
-        let digest = get_hash(v.as_ref(), algo);
-        let hash = digest.as_ref();
-        tree.extend_from_slice(hash);
-        match map {
-            Some(ref mut m) => m.insert(hash.to_vec(), i),
-            None => None,
-        };
-    }
-    let height = build_level(&mut tree, 0, values.len(), algo);
+    for v in values.iter() { //Hash leafs
+        tree.extend_from_slice(scheme.hash_leaf(v.as_ref(), hasher).as_ref());
+    }
+    build_from_leafs(tree, values.len(), hasher, use_map, scheme)
+}
+
+fn build_from_leafs<H: MerkleHasher>(mut tree: Vec<u8>, count: usize, hasher: &H, use_map: bool, scheme: Scheme) -> (usize, Vec<u8>, Option<LeafMap>) {
+    let map = if use_map { Some(build_map(&tree[..count * hasher.output_len()], hasher)) } else { None };
+    let height = if scheme.is_root_level(0, count) { 1 } else { build_levels(&mut tree, 0, count, hasher, scheme) };
     (height, tree, map)
 }
 
-fn calculate_vec_len(len: usize, algo: &'static Algorithm) -> usize {
-    let mut result = len + (len & 1);
-    let mut level = result;
-    while level > 1 {
-        level += level & 1;
-        level = level / 2;
-        result += level;
-    }
-    result * algo.output_len
-}
-
-fn build_level(tree: &mut Vec<u8>, prev_level_start: usize, mut prev_level_len: usize, algo: &'static Algorithm) -> usize {
-    if prev_level_len & 1 == 1 { //Previous level has odd number of children
-        let prev = &tree[(prev_level_start * algo.output_len + (prev_level_len - 1) * algo.output_len)..]
-            .to_owned();
-        tree.extend_from_slice(prev); //Duplicate last item
-        prev_level_len += 1;
-    }
-    let level_len = prev_level_len / 2;
-    for i in 0..level_len {
-        let begin = prev_level_start * algo.output_len + i * 2 * algo.output_len;
-        let middle = begin + algo.output_len;
-        let end = middle + algo.output_len;
-        let hash = get_pair_hash(
-            &tree[begin..middle], //Left node
-            &tree[middle..end], //Right node
-            algo);
-        tree.extend_from_slice(hash.as_ref());
-    };
-    if level_len > 1 {
-        return build_level(tree, prev_level_start + prev_level_len, level_len, algo) + 1;
+fn build_map<H: MerkleHasher>(leafs: &[u8], hasher: &H) -> LeafMap {
+    LeafMap::build(leafs, hasher.output_len())
+}
+
+fn calculate_height(count: usize, scheme: Scheme) -> usize {
+    if count == 0 {
+        return 0;
     }
-    if level_len > 0 {
-        return 2;
+    let mut level = 0;
+    let mut level_len = count;
+    while !scheme.is_root_level(level, level_len) {
+        level_len = level_len.div_ceil(2);
+        level += 1;
     }
-    return 0;
+    level + 1
 }
 
-pub fn get_pair_hash(x: &[u8], y: &[u8], algo: &'static Algorithm) -> Digest {
-    let mut left = x;
-    let mut right = y;
-    for i in 0..algo.output_len { //Sort left and right before concatenation
-        if left[i] > right[i] {
-            mem::swap(&mut left, &mut right);
+// Leaf and a sibling per level below the root, leaf 0 has one wherever a node can
+fn proof_hashes(count: usize, scheme: Scheme) -> usize {
+    if count == 1 && !scheme.pads() { // A promoted lone leaf is its own root
+        return 1;
+    }
+    calculate_height(count, scheme)
+}
+
+// First node and number of real nodes of every level, leafs first
+fn level_layout(count: usize, scheme: Scheme) -> Vec<(usize, usize)> {
+    let mut result = vec![];
+    let mut start = 0;
+    let mut level_len = count;
+    while level_len > 0 {
+        result.push((start, level_len));
+        if scheme.is_root_level(result.len() - 1, level_len) {
             break;
         }
-        if left[i] < right[i] {
-            break;
+        start += scheme.stored_len(level_len);
+        level_len = level_len.div_ceil(2);
+    }
+    result
+}
+
+fn calculate_vec_len<H: MerkleHasher>(len: usize, hasher: &H, scheme: Scheme) -> usize {
+    checked_vec_len(len, hasher.output_len(), scheme).unwrap_or_else(|e| panic!("{}", e))
+}
+
+fn checked_vec_len(len: usize, digest_len: usize, scheme: Scheme) -> Result<usize, CapacityError> {
+    let error = CapacityError { leafs: len, digest_len };
+    let mut result: usize = 0;
+    let mut level = len;
+    let mut index = 0;
+    while level > 0 && !scheme.is_root_level(index, level) {
+        result = scheme.checked_stored_len(level).and_then(|stored| result.checked_add(stored)).ok_or(error)?;
+        level = level.div_ceil(2);
+        index += 1;
+    }
+    result.checked_add(level).and_then(|nodes| nodes.checked_mul(digest_len)).ok_or(error)
+}
+
+// Appends every level above the given one, returns the number of levels including it
+fn build_levels<H: MerkleHasher>(tree: &mut Vec<u8>, mut prev_level_start: usize, mut prev_level_len: usize, hasher: &H, scheme: Scheme) -> usize {
+    if prev_level_len == 0 {
+        return 0;
+    }
+    let len = hasher.output_len();
+    let mut height = 1;
+    loop {
+        let odd = prev_level_len & 1 == 1; //Previous level has odd number of children
+        let last = (prev_level_start + prev_level_len - 1) * len;
+        if odd && scheme.pads() {
+            tree.resize(last + 2 * len, 0);
+            scheme.write_padding(tree, last, len); //Duplicate last item or add a zero one
+            prev_level_len += 1;
+        }
+        let promoted = odd && !scheme.pads();
+        let pairs = prev_level_len / 2;
+        let level_start = tree.len();
+        tree.resize(level_start + pairs * len, 0);
+        {
+            let (children, level) = tree.split_at_mut(level_start);
+            let children = &children[prev_level_start * len..];
+            for (node, pair) in level.chunks_mut(len).zip(children.chunks(2 * len)) {
+                node.copy_from_slice(&scheme.hash_pair(&pair[..len], &pair[len..], hasher)); //Left and right nodes
+            }
+        }
+        if promoted {
+            tree.extend_from_within(last..last + len); //Move last item to the next level
+        }
+        let level_len = pairs + promoted as usize;
+        height += 1;
+        if level_len == 1 {
+            return height;
         }
+        prev_level_start += prev_level_len;
+        prev_level_len = level_len;
     }
+}
+
+#[cfg(feature = "ring")]
+pub fn get_pair_hash(x: &[u8], y: &[u8], algo: &'static Algorithm) -> Digest {
+    let (left, right) = Scheme::LEGACY.order(x, y);
     let mut ctx = Context::new(algo);
     ctx.update(left);
     ctx.update(right);
     ctx.finish()
 }
 
+#[cfg(feature = "ring")]
 pub fn get_hash(x: &[u8], algo: &'static Algorithm) -> Digest {
-    let mut ctx = Context::new(algo);
-    ctx.update(x);
-    ctx.finish()
-}
\ No newline at end of file
+    digest::digest(algo, x)
+}
@@ -0,0 +1,117 @@
+use std::convert::AsRef;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+use memmap2::{Mmap, MmapMut};
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+
+use binary::Header;
+use error::LoadError;
+use hasher::{MerkleHasher, NamedHasher};
+#[cfg(feature = "ring")]
+use hasher::RingHasher;
+use nodes::Nodes;
+use scheme::Scheme;
+use {calculate_height, calculate_vec_len, level_layout, MerkleTree};
+
+#[cfg(feature = "ring")]
+impl MerkleTree<RingHasher> {
+    pub fn build_file<T, I, P, A: Into<&'static Algorithm>>(values: I, algo: A, path: P) -> io::Result<()>
+        where T: AsRef<[u8]>, I: IntoIterator<Item = T>, I::IntoIter: ExactSizeIterator, P: AsRef<Path> {
+        MerkleTree::build_file_with_hasher(values, RingHasher::new(algo.into()), path)
+    }
+}
+
+impl<H: NamedHasher> MerkleTree<H> {
+    // Writes the tree in the format of write_to straight into the file, levels one after another.
+    // Nodes are never held in memory, the file is mapped while it is written.
+    pub fn build_file_with_hasher<T, I, P>(values: I, hasher: H, path: P) -> io::Result<()>
+        where T: AsRef<[u8]>, I: IntoIterator<Item = T>, I::IntoIter: ExactSizeIterator, P: AsRef<Path> {
+        let scheme = Scheme::LEGACY;
+        let values = values.into_iter();
+        let count = values.len();
+        let len = hasher.output_len();
+        let header = Header {
+            name: hasher.name().to_string(),
+            scheme,
+            use_map: false,
+            items_count: count,
+            height: calculate_height(count, scheme),
+            array_len: calculate_vec_len(count, &hasher, scheme),
+        };
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.set_len((header.len() + header.array_len) as u64)?;
+        // The file was just created by us and nothing else writes to it
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        header.write(&mut &mut map[..header.len()])?;
+        let nodes = &mut map[header.len()..];
+        let mut hashed = 0;
+        for (node, value) in nodes[..count * len].chunks_mut(len).zip(values) {
+            node.copy_from_slice(&scheme.hash_leaf(value.as_ref(), &hasher));
+            hashed += 1;
+        }
+        if hashed != count {
+            return Err(io::Error::other("values ended before their reported length"));
+        }
+        build_levels(nodes, count, &hasher, scheme);
+        map.flush()
+    }
+
+    // Maps a file written by write_to or build_file, nodes are read from the file when needed.
+    // Changing the tree copies the nodes to memory, the file stays as it is. With check_root
+    // all nodes are recomputed from the leafs, which reads the whole file.
+    pub fn open_mapped<P: AsRef<Path>>(path: P, check_root: bool) -> Result<MerkleTree<H>, LoadError> {
+        let file = File::open(path)?;
+        // Trees are not stored in files that change while they are open
+        let map = unsafe { Mmap::map(&file)? };
+        let header = Header::read(&mut &map[..])?;
+        header.check::<H>()?;
+        let offset = header.len();
+        let stored = map.len() - offset;
+        if stored < header.array_len {
+            return Err(LoadError::Truncated);
+        }
+        if stored > header.array_len {
+            return Err(LoadError::BadLength { got: stored, expected: header.array_len });
+        }
+        let tree = MerkleTree::from_header(&header, Nodes::Mapped { map, offset })?;
+        if check_root {
+            tree.check_nodes()?;
+        }
+        Ok(tree)
+    }
+
+    // Whether the nodes are read from a mapped file
+    pub fn is_mapped(&self) -> bool {
+        match self.array {
+            Nodes::Mapped { .. } => true,
+            Nodes::Memory(_) => false,
+        }
+    }
+}
+
+// Hashes every level above the leafs in place
+fn build_levels<H: MerkleHasher>(nodes: &mut [u8], count: usize, hasher: &H, scheme: Scheme) {
+    let len = hasher.output_len();
+    let layout = level_layout(count, scheme);
+    for level in 0..layout.len() {
+        let (start, level_len) = layout[level];
+        if level > 0 {
+            let (child_start, child_len) = layout[level - 1];
+            for i in 0..level_len {
+                let left = (child_start + 2 * i) * len;
+                let hash = if 2 * i + 1 < child_len {
+                    scheme.hash_pair(&nodes[left..left + len], &nodes[left + len..left + 2 * len], hasher)
+                } else {
+                    scheme.hash_lone(&nodes[left..left + len], hasher)
+                };
+                nodes[(start + i) * len..(start + i + 1) * len].copy_from_slice(&hash);
+            }
+        }
+        if scheme.pads() && level_len & 1 == 1 && !scheme.is_root_level(level, level_len) {
+            scheme.write_padding(nodes, (start + level_len - 1) * len, len);
+        }
+    }
+}
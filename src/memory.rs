@@ -0,0 +1,34 @@
+use hasher::MerkleHasher;
+use MerkleTree;
+
+// Heap bytes held by a tree. A mapped node array is in the page cache and counts as none
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub nodes: usize, // Node bytes in use, same as data_size
+    pub node_capacity: usize, // Allocated for nodes, at least nodes for an in-memory array
+    pub map: usize, // Estimate of the leaf map with its table overhead, 0 without a map
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.node_capacity + self.map
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            nodes: self.array.len(),
+            node_capacity: self.array.capacity(),
+            map: self.map_size().unwrap_or(0),
+        }
+    }
+
+    // Gives back the memory not needed by the current nodes and map
+    pub fn shrink_to_fit(&mut self) {
+        self.array.shrink_to_fit();
+        if let Some(ref mut map) = self.map {
+            map.shrink_to_fit();
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::slice::Chunks;
+
+use ring::digest::Algorithm;
+
+use hasher::{MerkleHasher, RingHasher};
+use scheme::Scheme;
+
+// Nodes are hashed in tree order, so a proof is bound to the position of the leaf
+const SCHEME: Scheme = Scheme { sorted_pairs: false, ..Scheme::LEGACY };
+
+// Merkle mountain range: a list of perfect trees (mountains) of decreasing size, kept in one
+// vector in the order nodes are appended. Appending a leaf merges the equal mountains at the end,
+// nothing written before changes. Positions are indices of nodes in that vector.
+// The root bags the mountain peaks from right to left: H(p0 || H(p1 || p2)). A single
+// mountain is its own root, so the root of one leaf is the hash of that leaf.
+pub struct Mmr {
+    nodes: Vec<u8>,
+    leaf_count: usize,
+    hasher: RingHasher,
+}
+
+// Siblings from the leaf to its peak bottom-up, then the other peaks left to right
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MmrProof {
+    leaf_count: usize,
+    siblings: Vec<u8>,
+    peaks: Vec<u8>,
+    digest_len: usize,
+}
+
+impl MmrProof {
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    pub fn siblings(&self) -> Chunks<'_, u8> {
+        self.siblings.chunks(self.digest_len)
+    }
+
+    pub fn peaks(&self) -> Chunks<'_, u8> {
+        self.peaks.chunks(self.digest_len)
+    }
+}
+
+impl Mmr {
+    pub fn new<A: Into<&'static Algorithm>>(algo: A) -> Mmr {
+        Mmr { nodes: vec![], leaf_count: 0, hasher: RingHasher::new(algo.into()) }
+    }
+
+    // Returns the position of the new leaf
+    pub fn append<T: AsRef<[u8]>>(&mut self, value: &T) -> usize {
+        let len = self.hasher.output_len();
+        let position = self.size();
+        self.nodes.extend_from_slice(&SCHEME.hash_leaf(value.as_ref(), &self.hasher));
+        let mut height = 0;
+        let mut merged = self.leaf_count;
+        while merged & 1 == 1 { // Mountain of the same height on the left
+            let right = self.size() - 1;
+            let left = right + 1 - (2 << height);
+            let parent = SCHEME.hash_pair(&self.nodes[left * len..(left + 1) * len], &self.nodes[right * len..(right + 1) * len], &self.hasher);
+            self.nodes.extend_from_slice(&parent);
+            height += 1;
+            merged >>= 1;
+        }
+        self.leaf_count += 1;
+        position
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    // Number of nodes
+    pub fn size(&self) -> usize {
+        self.nodes.len() / self.hasher.output_len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaf_count == 0
+    }
+
+    // Empty for an empty range
+    pub fn root(&self) -> Vec<u8> {
+        let peaks: Vec<&[u8]> = mountains(self.leaf_count).iter().map(|&(_, peak)| self.node(peak)).collect();
+        bag(&peaks, &self.hasher)
+    }
+
+    pub fn prove(&self, position: usize) -> Option<MmrProof> {
+        let (mountain, sides) = locate(self.leaf_count, position)?;
+        let mut siblings = Vec::with_capacity(sides.len() * self.hasher.output_len());
+        for &(sibling, _) in sides.iter().rev() {
+            siblings.extend_from_slice(self.node(sibling));
+        }
+        let mut peaks = vec![];
+        for (i, &(_, peak)) in mountains(self.leaf_count).iter().enumerate() {
+            if i != mountain {
+                peaks.extend_from_slice(self.node(peak));
+            }
+        }
+        Some(MmrProof { leaf_count: self.leaf_count, siblings, peaks, digest_len: self.hasher.output_len() })
+    }
+
+    fn node(&self, position: usize) -> &[u8] {
+        let len = self.hasher.output_len();
+        &self.nodes[position * len..(position + 1) * len]
+    }
+}
+
+pub fn verify<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(root: &[u8], position: usize, value: &T, proof: &MmrProof, algo: A) -> bool {
+    let hasher = RingHasher::new(algo.into());
+    let len = hasher.output_len();
+    let (mountain, sides) = match locate(proof.leaf_count, position) {
+        Some(found) => found,
+        None => return false,
+    };
+    let peak_count = mountains(proof.leaf_count).len();
+    if proof.digest_len != len || proof.siblings.len() != sides.len() * len || proof.peaks.len() != (peak_count - 1) * len {
+        return false;
+    }
+    let mut hash = SCHEME.hash_leaf(value.as_ref(), &hasher);
+    for (sibling, &(_, left)) in proof.siblings().zip(sides.iter().rev()) {
+        hash = if left {
+            SCHEME.hash_pair(sibling, &hash, &hasher)
+        } else {
+            SCHEME.hash_pair(&hash, sibling, &hasher)
+        };
+    }
+    let mut peaks: Vec<&[u8]> = proof.peaks().collect();
+    peaks.insert(mountain, &hash);
+    bag(&peaks, &hasher) == root
+}
+
+// Size is 2^(h+1) - 1 for a mountain of height h
+fn mountains(leaf_count: usize) -> Vec<(usize, usize)> { // Height and peak position, left to right
+    let mut result = vec![];
+    let mut offset = 0;
+    for height in (0..usize::BITS as usize).rev() {
+        if leaf_count & (1 << height) != 0 {
+            offset += (2 << height) - 1;
+            result.push((height, offset - 1));
+        }
+    }
+    result
+}
+
+// Mountain of the leaf at position and the siblings on the way down from its peak,
+// with true for siblings on the left. None if there is no leaf at position.
+fn locate(leaf_count: usize, position: usize) -> Option<(usize, Vec<(usize, bool)>)> {
+    let mut offset = 0; // First node of the mountain
+    for (i, &(height, peak)) in mountains(leaf_count).iter().enumerate() {
+        if position <= peak {
+            let mut sides = vec![];
+            let (mut height, mut offset, mut top) = (height, offset, peak);
+            while height > 0 {
+                let left = offset + (1 << height) - 2; // Left subtree has 2^h - 1 nodes
+                let right = top - 1;
+                if position <= left {
+                    sides.push((right, false));
+                    top = left;
+                } else {
+                    sides.push((left, true));
+                    offset = left + 1;
+                    top = right;
+                }
+                height -= 1;
+            }
+            return if top == position { Some((i, sides)) } else { None }; // Otherwise an inner node
+        }
+        offset = peak + 1;
+    }
+    None
+}
+
+fn bag(peaks: &[&[u8]], hasher: &RingHasher) -> Vec<u8> {
+    let mut iter = peaks.iter().rev();
+    let last = match iter.next() {
+        Some(peak) => peak.to_vec(),
+        None => return vec![],
+    };
+    iter.fold(last, |root, peak| SCHEME.hash_pair(peak, &root, hasher))
+}
@@ -0,0 +1,150 @@
+use std::slice::Chunks;
+
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+
+use hasher::MerkleHasher;
+#[cfg(feature = "ring")]
+use hasher::RingHasher;
+use scheme::Scheme;
+use MerkleTree;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiProof {
+    leaf_count: usize,
+    indices: Vec<usize>,
+    leaf_hashes: Vec<u8>,
+    hashes: Vec<u8>,
+    digest_len: usize,
+}
+
+impl MultiProof {
+    pub fn new(leaf_count: usize, indices: Vec<usize>, leaf_hashes: Vec<u8>, hashes: Vec<u8>, digest_len: usize) -> MultiProof {
+        MultiProof { leaf_count, indices, leaf_hashes, hashes, digest_len }
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    // Sorted and deduplicated leaf indices
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    // Hashes of the proven leaves, in the order of indices()
+    pub fn leaf_hashes(&self) -> Chunks<'_, u8> {
+        self.leaf_hashes.chunks(self.digest_len)
+    }
+
+    // Sibling hashes bottom-up, left to right within each level
+    pub fn hashes(&self) -> Chunks<'_, u8> {
+        self.hashes.chunks(self.digest_len)
+    }
+
+    pub fn digest_len(&self) -> usize {
+        self.digest_len
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn build_multiproof(&self, indices: &[usize]) -> Option<MultiProof> {
+        if indices.is_empty() || indices.iter().any(|&i| i >= self.items_count) {
+            return None;
+        }
+        let len = self.hasher.output_len();
+        let mut known = indices.to_vec();
+        known.sort();
+        known.dedup();
+
+        let mut leaf_hashes = Vec::with_capacity(known.len() * len);
+        for &i in &known {
+            leaf_hashes.extend_from_slice(&self.array[i * len..(i + 1) * len]);
+        }
+
+        let mut hashes = vec![];
+        let mut level_start = 0;
+        let mut level_len = self.items_count;
+        let mut current = known.clone();
+        let mut level = 0;
+        while !self.scheme.is_root_level(level, level_len) { // Root is not included to proof
+            let mut parents = Vec::with_capacity(current.len());
+            let mut k = 0;
+            while k < current.len() {
+                let index = current[k];
+                let sibling = index ^ 1;
+                if k + 1 < current.len() && current[k + 1] == sibling {
+                    k += 1; // Both children are known to the verifier
+                } else if sibling < level_len {
+                    let start = (level_start + sibling) * len;
+                    hashes.extend_from_slice(&self.array[start..start + len]);
+                } // Otherwise sibling is the padding duplicate, verifier restores it
+                parents.push(index / 2);
+                k += 1;
+            }
+            level_start += self.scheme.stored_len(level_len);
+            level_len = level_len.div_ceil(2);
+            current = parents;
+            level += 1;
+        }
+        Some(MultiProof::new(self.items_count, known, leaf_hashes, hashes, len))
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn validate_multiproof(&self, proof: &MultiProof) -> bool {
+        proof.leaf_count == self.items_count && verify_multiproof_with(self.get_root(), proof, &self.hasher, self.scheme)
+    }
+}
+
+#[cfg(feature = "ring")]
+pub fn verify_multiproof<A: Into<&'static Algorithm>>(root: &[u8], proof: &MultiProof, algo: A) -> bool {
+    verify_multiproof_with(root, proof, &RingHasher::new(algo.into()), Scheme::LEGACY)
+}
+
+pub(crate) fn verify_multiproof_with<H: MerkleHasher>(root: &[u8], proof: &MultiProof, hasher: &H, scheme: Scheme) -> bool {
+    let len = hasher.output_len();
+    if proof.digest_len != len || root.len() != len
+        || proof.indices.is_empty()
+        || proof.leaf_hashes.len() != proof.indices.len() * len
+        || !proof.hashes.len().is_multiple_of(len) {
+        return false;
+    }
+    if proof.indices.windows(2).any(|w| w[0] >= w[1]) || proof.indices[proof.indices.len() - 1] >= proof.leaf_count {
+        return false; // Indices must be sorted, unique and inside the tree
+    }
+
+    let mut current: Vec<(usize, Vec<u8>)> = proof.indices.iter()
+        .zip(proof.leaf_hashes())
+        .map(|(&i, h)| (i, h.to_vec()))
+        .collect();
+    let mut hashes = proof.hashes();
+    let mut level_len = proof.leaf_count;
+    let mut level = 0;
+    while !scheme.is_root_level(level, level_len) {
+        let mut parents = Vec::with_capacity(current.len());
+        let mut k = 0;
+        while k < current.len() {
+            let (index, ref hash) = current[k];
+            let sibling = index ^ 1;
+            let parent = if k + 1 < current.len() && current[k + 1].0 == sibling {
+                k += 1;
+                scheme.hash_pair(hash, &current[k].1, hasher)
+            } else if sibling < level_len {
+                match hashes.next() {
+                    Some(s) if sibling < index => scheme.hash_pair(s, hash, hasher),
+                    Some(s) => scheme.hash_pair(hash, s, hasher),
+                    None => return false,
+                }
+            } else { // Last node of the level has no sibling
+                scheme.hash_lone(hash, hasher)
+            };
+            parents.push((index / 2, parent));
+            k += 1;
+        }
+        current = parents;
+        level_len = level_len.div_ceil(2);
+        level += 1;
+    }
+    hashes.next().is_none() && current[0].1 == root
+}
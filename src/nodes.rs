@@ -0,0 +1,116 @@
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+// Node array of a tree, in memory or in a mapped file. Every reader goes through the slice,
+// a change to a mapped array copies it to memory first and leaves the file as it was.
+pub(crate) enum Nodes {
+    Memory(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped { map: Mmap, offset: usize },
+}
+
+impl Nodes {
+    // Bytes allocated for the array, a mapped array takes none of the heap
+    pub(crate) fn capacity(&self) -> usize {
+        match *self {
+            Nodes::Memory(ref array) => array.capacity(),
+            #[cfg(feature = "mmap")]
+            Nodes::Mapped { .. } => 0,
+        }
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        match *self {
+            Nodes::Memory(ref mut array) => array.shrink_to_fit(),
+            #[cfg(feature = "mmap")]
+            Nodes::Mapped { .. } => {}
+        }
+    }
+
+    // Sets the length of the array in memory, a mapped one is copied first. Growing reserves
+    // spare capacity, so appending one node at a time does not reallocate every time.
+    pub(crate) fn resize(&mut self, len: usize) {
+        #[cfg(feature = "mmap")]
+        {
+            if let Nodes::Mapped { .. } = *self {
+                *self = Nodes::Memory(self.to_vec());
+            }
+        }
+        match *self {
+            Nodes::Memory(ref mut array) => {
+                #[cfg(feature = "zeroize")]
+                {
+                    if len < array.len() { // Dropped nodes are not left in the spare capacity
+                        array[len..].zeroize();
+                    }
+                }
+                array.resize(len, 0);
+            }
+            #[cfg(feature = "mmap")]
+            Nodes::Mapped { .. } => unreachable!("copied to memory above"),
+        }
+    }
+
+    // The array in memory, a mapped one is copied
+    pub(crate) fn into_vec(mut self) -> Vec<u8> {
+        match self {
+            Nodes::Memory(ref mut array) => mem::take(array),
+            #[cfg(feature = "mmap")]
+            Nodes::Mapped { .. } => self.to_vec(),
+        }
+    }
+
+    // Empty vector keeping the allocation of an in-memory array
+    pub(crate) fn into_empty_vec(mut self) -> Vec<u8> {
+        match self {
+            Nodes::Memory(ref mut array) => {
+                let mut array = mem::take(array);
+                #[cfg(feature = "zeroize")]
+                array.zeroize(); // Old nodes are not left in the reused allocation
+                array.clear();
+                array
+            }
+            #[cfg(feature = "mmap")]
+            Nodes::Mapped { .. } => Vec::new(),
+        }
+    }
+}
+
+impl Deref for Nodes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match *self {
+            Nodes::Memory(ref array) => array,
+            #[cfg(feature = "mmap")]
+            Nodes::Mapped { ref map, offset } => &map[offset..],
+        }
+    }
+}
+
+impl DerefMut for Nodes {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        #[cfg(feature = "mmap")]
+        {
+            if let Nodes::Mapped { .. } = *self {
+                *self = Nodes::Memory(self.to_vec());
+            }
+        }
+        match *self {
+            Nodes::Memory(ref mut array) => array,
+            #[cfg(feature = "mmap")]
+            Nodes::Mapped { .. } => unreachable!("copied to memory above"),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Nodes {
+    fn from(array: Vec<u8>) -> Nodes {
+        Nodes::Memory(array)
+    }
+}
@@ -0,0 +1,82 @@
+use std::convert::AsRef;
+
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+use rayon::prelude::*;
+
+use error::IntegrityError;
+use hasher::MerkleHasher;
+#[cfg(feature = "ring")]
+use hasher::RingHasher;
+use scheme::Scheme;
+#[cfg(feature = "ring")]
+use MerkleTreeBuilder;
+use {build_map, calculate_vec_len, level_layout, MerkleTree};
+
+#[cfg(feature = "ring")]
+impl MerkleTree<RingHasher> {
+    pub fn new_parallel<T: AsRef<[u8]> + Sync, A: Into<&'static Algorithm>>(values: &[T], algo: A) -> MerkleTree {
+        MerkleTreeBuilder::new().algorithm(algo).build_parallel(values).expect("algorithm is set")
+    }
+}
+
+impl<H: MerkleHasher + Sync> MerkleTree<H> {
+    // Same array as the serial build: every level is written in place, its nodes hashed in parallel
+    pub(crate) fn new_parallel_with_scheme<T: AsRef<[u8]> + Sync>(values: &[T], hasher: H, use_map: bool, scheme: Scheme) -> MerkleTree<H> {
+        let len = hasher.output_len();
+        let count = values.len();
+        let layout = level_layout(count, scheme);
+        let mut array = vec![0; calculate_vec_len(count, &hasher, scheme)];
+
+        array[..count * len].par_chunks_mut(len).zip(values.par_iter()).for_each(|(node, value)| {
+            node.copy_from_slice(&scheme.hash_leaf(value.as_ref(), &hasher));
+        });
+        let map = if use_map { Some(build_map(&array[..count * len], &hasher)) } else { None };
+
+        for level in 0..layout.len() {
+            let (start, level_len) = layout[level];
+            if level > 0 {
+                let (child_start, child_len) = layout[level - 1];
+                let (children, nodes) = array.split_at_mut(start * len);
+                let children = &children[child_start * len..];
+                nodes[..level_len * len].par_chunks_mut(len).enumerate().for_each(|(i, node)| {
+                    let left = 2 * i * len;
+                    let hash = if 2 * i + 1 < child_len {
+                        scheme.hash_pair(&children[left..left + len], &children[left + len..left + 2 * len], &hasher)
+                    } else {
+                        scheme.hash_lone(&children[left..left + len], &hasher)
+                    };
+                    node.copy_from_slice(&hash);
+                });
+            }
+            if scheme.pads() && level_len & 1 == 1 && !scheme.is_root_level(level, level_len) {
+                scheme.write_padding(&mut array, (start + level_len - 1) * len, len);
+            }
+        }
+
+        MerkleTree {
+            array: array.into(),
+            height: layout.len(),
+            items_count: count,
+            map,
+            hasher,
+            scheme,
+        }
+    }
+
+    // Same result as verify_integrity, the nodes of every level checked in parallel
+    pub fn verify_integrity_parallel(&self) -> Result<(), IntegrityError> {
+        let layout = level_layout(self.items_count, self.scheme);
+        for level in 0..layout.len() {
+            if level > 0 {
+                if let Some(index) = (0..layout[level].1).into_par_iter().find_first(|&i| !self.node_matches(&layout, level, i)) {
+                    return Err(IntegrityError { level, index });
+                }
+            }
+            if !self.padding_matches(&layout, level) {
+                return Err(IntegrityError { level, index: layout[level].1 });
+            }
+        }
+        Ok(())
+    }
+}
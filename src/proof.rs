@@ -0,0 +1,200 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::slice::Chunks;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    bytes: Vec<u8>,
+    digest_len: usize,
+    positions: Option<Vec<Position>>,
+}
+
+// Side of the sibling relative to the node being proven
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
+pub enum Position {
+    Left,
+    Right,
+}
+
+// One level of a proof walked bottom-up
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofStep<'a> {
+    pub sibling: &'a [u8],
+    pub position: StepPosition,
+}
+
+// Unordered where pairs are sorted before hashing and the proof keeps no positions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepPosition {
+    Left,
+    Right,
+    Unordered,
+}
+
+impl From<Position> for StepPosition {
+    fn from(position: Position) -> StepPosition {
+        match position {
+            Position::Left => StepPosition::Left,
+            Position::Right => StepPosition::Right,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofFormatError {
+    ZeroDigestLen,
+    NotAligned { len: usize, digest_len: usize },
+    TooShort { hashes: usize },
+    PositionsMismatch { siblings: usize, positions: usize },
+}
+
+impl Proof {
+    // Bytes produced by the tree itself are already well formed
+    pub(crate) fn new(bytes: Vec<u8>, digest_len: usize) -> Proof {
+        Proof { bytes, digest_len, positions: None }
+    }
+
+    pub(crate) fn with_positions_unchecked(bytes: Vec<u8>, digest_len: usize, positions: Vec<Position>) -> Proof {
+        Proof { bytes, digest_len, positions: Some(positions) }
+    }
+
+    pub fn from_bytes(bytes: &[u8], digest_len: usize) -> Result<Proof, ProofFormatError> {
+        check_format(bytes.len(), digest_len)?;
+        Ok(Proof::new(bytes.to_vec(), digest_len))
+    }
+
+    // For order sensitive trees, one position per sibling. A lone leaf is a valid proof here
+    pub fn with_positions(bytes: &[u8], digest_len: usize, positions: Vec<Position>) -> Result<Proof, ProofFormatError> {
+        match check_format(bytes.len(), digest_len) {
+            Ok(()) | Err(ProofFormatError::TooShort { hashes: 1 }) => (),
+            Err(e) => return Err(e),
+        }
+        let siblings = bytes.len() / digest_len - 1;
+        if siblings != positions.len() {
+            return Err(ProofFormatError::PositionsMismatch { siblings, positions: positions.len() });
+        }
+        Ok(Proof::with_positions_unchecked(bytes.to_vec(), digest_len, positions))
+    }
+
+    pub fn leaf_hash(&self) -> &[u8] {
+        &self.bytes[..self.digest_len]
+    }
+
+    pub fn siblings(&self) -> Chunks<'_, u8> {
+        self.bytes[self.digest_len..].chunks(self.digest_len)
+    }
+
+    pub fn path(&self) -> impl Iterator<Item = ProofStep<'_>> + '_ {
+        self.siblings().enumerate().map(move |(i, sibling)| ProofStep {
+            sibling,
+            position: self.positions.as_ref().map_or(StepPosition::Unordered, |p| p[i].into()),
+        })
+    }
+
+    // Number of hashes in the proof, leaf hash included
+    pub fn len(&self) -> usize {
+        self.bytes.len() / self.digest_len
+    }
+
+    // Same as len, expected_proof_len is this times the digest length
+    pub fn hash_count(&self) -> usize {
+        self.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    // All hashes as N bytes, leaf hash first. None when N is not the digest length.
+    pub fn hashes_array<const N: usize>(&self) -> Option<Vec<[u8; N]>> {
+        if N != self.digest_len {
+            return None;
+        }
+        self.bytes.chunks(N).map(|hash| <[u8; N]>::try_from(hash).ok()).collect()
+    }
+
+    pub fn positions(&self) -> Option<&[Position]> {
+        self.positions.as_deref()
+    }
+
+    pub fn digest_len(&self) -> usize {
+        self.digest_len
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        mem::take(&mut self.bytes) // Proof is wiped on drop with zeroize, so the bytes are taken
+    }
+
+    #[cfg(feature = "zeroize")]
+    pub(crate) fn wipe(&mut self) {
+        self.bytes.zeroize();
+        self.positions = None;
+    }
+
+    // Siblings bottom-up as bytes32[], the proof argument of OpenZeppelin MerkleProof.verify.
+    // None for digests other than 32 bytes.
+    pub fn to_openzeppelin(&self) -> Option<Vec<[u8; 32]>> {
+        if self.digest_len != 32 {
+            return None;
+        }
+        Some(self.siblings().map(|s| {
+            let mut hash = [0; 32];
+            hash.copy_from_slice(s);
+            hash
+        }).collect())
+    }
+}
+
+impl AsRef<[u8]> for Proof {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+// The one alignment check of proof parsing and validation
+pub(crate) fn check_alignment(len: usize, digest_len: usize) -> Result<(), ProofFormatError> {
+    if digest_len == 0 {
+        return Err(ProofFormatError::ZeroDigestLen);
+    }
+    if !len.is_multiple_of(digest_len) {
+        return Err(ProofFormatError::NotAligned { len, digest_len });
+    }
+    Ok(())
+}
+
+fn check_format(len: usize, digest_len: usize) -> Result<(), ProofFormatError> {
+    check_alignment(len, digest_len)?;
+    let hashes = len / digest_len;
+    if hashes < 2 { // Leaf and at least one sibling
+        return Err(ProofFormatError::TooShort { hashes });
+    }
+    Ok(())
+}
+
+impl fmt::Display for ProofFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProofFormatError::ZeroDigestLen => write!(f, "digest length must be non-zero"),
+            ProofFormatError::NotAligned { len, digest_len } =>
+                write!(f, "proof length {} is not a multiple of digest length {}", len, digest_len),
+            ProofFormatError::TooShort { hashes } =>
+                write!(f, "proof has {} hashes, at least 2 are required", hashes),
+            ProofFormatError::PositionsMismatch { siblings, positions } =>
+                write!(f, "proof has {} siblings but {} positions", siblings, positions),
+        }
+    }
+}
+
+impl Error for ProofFormatError {}
@@ -0,0 +1,70 @@
+use std::ops::Range;
+use std::slice::Chunks;
+
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+
+use hasher::MerkleHasher;
+#[cfg(feature = "ring")]
+use hasher::RingHasher;
+use multiproof::{verify_multiproof_with, MultiProof};
+use scheme::Scheme;
+use MerkleTree;
+
+// Siblings of the paths from both ends of a leaf range to the root. Everything between
+// the ends is rebuilt by the verifier from the leaf hashes of the range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RangeProof {
+    leaf_count: usize,
+    hashes: Vec<u8>,
+    digest_len: usize,
+}
+
+impl RangeProof {
+    pub fn new(leaf_count: usize, hashes: Vec<u8>, digest_len: usize) -> RangeProof {
+        RangeProof { leaf_count, hashes, digest_len }
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    // Sibling hashes bottom-up, left to right within each level
+    pub fn hashes(&self) -> Chunks<'_, u8> {
+        self.hashes.chunks(self.digest_len)
+    }
+
+    pub fn digest_len(&self) -> usize {
+        self.digest_len
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn build_range_proof(&self, range: Range<usize>) -> Option<RangeProof> {
+        let indices: Vec<usize> = range.collect();
+        self.build_multiproof(&indices).map(|proof| {
+            RangeProof::new(self.items_count, proof.hashes().flat_map(|h| h.iter().cloned()).collect(), proof.digest_len())
+        })
+    }
+
+    pub fn validate_range<T: AsRef<[u8]>>(&self, range: Range<usize>, leaf_hashes: &[T], proof: &RangeProof) -> bool {
+        proof.leaf_count == self.items_count
+            && verify_range_with(self.get_root(), range, leaf_hashes, proof, &self.hasher, self.scheme)
+    }
+}
+
+#[cfg(feature = "ring")]
+pub fn verify_range<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(root: &[u8], range: Range<usize>, leaf_hashes: &[T], proof: &RangeProof,
+                                                                 algo: A) -> bool {
+    verify_range_with(root, range, leaf_hashes, proof, &RingHasher::new(algo.into()), Scheme::LEGACY)
+}
+
+fn verify_range_with<T: AsRef<[u8]>, H: MerkleHasher>(root: &[u8], range: Range<usize>, leaf_hashes: &[T], proof: &RangeProof,
+                                                      hasher: &H, scheme: Scheme) -> bool {
+    if range.len() != leaf_hashes.len() || leaf_hashes.iter().any(|h| h.as_ref().len() != hasher.output_len()) {
+        return false;
+    }
+    let hashes: Vec<u8> = leaf_hashes.iter().flat_map(|h| h.as_ref().iter().cloned()).collect();
+    let multiproof = MultiProof::new(proof.leaf_count, range.collect(), hashes, proof.hashes.clone(), proof.digest_len);
+    verify_multiproof_with(root, &multiproof, hasher, scheme)
+}
@@ -0,0 +1,41 @@
+use std::io::{self, Read};
+
+use ring::digest::{Algorithm, Context};
+
+use hasher::RingHasher;
+use scheme::Scheme;
+use {build_from_leafs, MerkleTree};
+
+const BUFFER_LEN: usize = 64 * 1024;
+
+impl MerkleTree<RingHasher> {
+    // Every reader is one leaf, hashed through a fixed buffer without loading it into memory
+    pub fn from_readers<R: Read, I: IntoIterator<Item = R>, A: Into<&'static Algorithm>>(readers: I, algo: A) -> io::Result<MerkleTree> {
+        let algo = algo.into();
+        let mut buffer = vec![0; BUFFER_LEN];
+        let mut tree: Vec<u8> = vec![];
+        for mut reader in readers {
+            let mut ctx = Context::new(algo);
+            loop {
+                match reader.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => ctx.update(&buffer[..n]),
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                    Err(e) => return Err(e),
+                }
+            }
+            tree.extend_from_slice(ctx.finish().as_ref());
+        }
+        let hasher = RingHasher::new(algo);
+        let count = tree.len() / algo.output_len;
+        let (height, array, map) = build_from_leafs(tree, count, &hasher, false, Scheme::LEGACY);
+        Ok(MerkleTree {
+            array: array.into(),
+            height,
+            items_count: count,
+            map,
+            hasher,
+            scheme: Scheme::LEGACY,
+        })
+    }
+}
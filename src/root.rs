@@ -0,0 +1,91 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use ring::constant_time;
+
+use hasher::MerkleHasher;
+use MerkleTree;
+
+// Tree root compared in constant time. Roots of different digest lengths come from
+// different algorithms, comparing them is a bug and panics.
+#[derive(Clone, Debug)]
+pub struct Root {
+    bytes: Vec<u8>,
+    digest_len: usize,
+}
+
+impl Root {
+    pub fn new(bytes: &[u8], digest_len: usize) -> Root {
+        Root { bytes: bytes.to_vec(), digest_len }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn digest_len(&self) -> usize {
+        self.digest_len
+    }
+
+    // Empty tree has no root hash
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+// Panics when the digest lengths differ
+impl PartialEq for Root {
+    fn eq(&self, other: &Root) -> bool {
+        assert_eq!(self.digest_len, other.digest_len, "roots of different digest lengths are compared");
+        roots_equal(&self.bytes, &other.bytes)
+    }
+}
+
+impl Eq for Root {}
+
+// Over the raw bytes, same as the slice hash so that maps keyed by roots can be looked up
+// by slice. Roots are public, nothing is leaked by hashing them without constant time.
+impl Hash for Root {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.bytes.hash(state);
+    }
+}
+
+// Lexicographic over the bytes, roots of different digest lengths panic as with eq
+impl Ord for Root {
+    fn cmp(&self, other: &Root) -> Ordering {
+        assert_eq!(self.digest_len, other.digest_len, "roots of different digest lengths are compared");
+        self.bytes.cmp(&other.bytes)
+    }
+}
+
+impl PartialOrd for Root {
+    fn partial_cmp(&self, other: &Root) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Borrow<[u8]> for Root {
+    fn borrow(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl AsRef<[u8]> for Root {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+// Compares two roots without leaking where they differ. Lengths are not secret and
+// differing ones give false at once.
+pub fn roots_equal(a: &[u8], b: &[u8]) -> bool {
+    constant_time::verify_slices_are_equal(a, b).is_ok()
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn root(&self) -> Root {
+        Root::new(self.get_root(), self.hasher.output_len())
+    }
+}
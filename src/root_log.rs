@@ -0,0 +1,79 @@
+use std::convert::TryFrom;
+use std::slice::Iter;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use hasher::MerkleHasher;
+use MerkleTree;
+
+// Roots a tree had over time, one entry per record. Sequence numbers start at 0 and go up by one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RootLog {
+    entries: Vec<RootEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RootEntry {
+    pub sequence: u64,
+    pub leaf_count: usize,
+    pub root: Vec<u8>,
+}
+
+impl RootLog {
+    pub fn new() -> RootLog {
+        RootLog::default()
+    }
+
+    // Call after every rebuild, push or extend of the tree, gives the sequence of the entry
+    pub fn record<H: MerkleHasher>(&mut self, tree: &MerkleTree<H>) -> u64 {
+        let sequence = self.entries.len() as u64;
+        self.entries.push(RootEntry { sequence, leaf_count: tree.leafs_count(), root: tree.get_root().to_vec() });
+        sequence
+    }
+
+    pub fn latest(&self) -> Option<&RootEntry> {
+        self.entries.last()
+    }
+
+    pub fn get(&self, sequence: u64) -> Option<&RootEntry> {
+        self.entries.get(usize::try_from(sequence).ok()?).filter(|e| e.sequence == sequence)
+    }
+
+    pub fn iter(&self) -> Iter<'_, RootEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Consistency proof between two recorded roots, tree being the one recorded at sequence b.
+    // None unless the tree only had leafs appended between them, as the entries are checked.
+    pub fn prove_consistent<H: MerkleHasher>(&self, a: u64, b: u64, tree: &MerkleTree<H>) -> Option<Vec<u8>> {
+        let (old, new) = (self.get(a)?, self.get(b)?);
+        if a > b || new.leaf_count != tree.leafs_count() || new.root[..] != *tree.get_root() {
+            return None;
+        }
+        let proof = tree.build_consistency_proof(old.leaf_count)?;
+        if !tree.validate_consistency(&old.root, old.leaf_count, &proof) {
+            return None;
+        }
+        Some(proof)
+    }
+}
+
+impl<'a> IntoIterator for &'a RootLog {
+    type Item = &'a RootEntry;
+    type IntoIter = Iter<'a, RootEntry>;
+
+    fn into_iter(self) -> Iter<'a, RootEntry> {
+        self.entries.iter()
+    }
+}
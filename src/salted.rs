@@ -0,0 +1,112 @@
+use std::convert::AsRef;
+
+use ring::digest::{self, Algorithm};
+use ring::error::Unspecified;
+use ring::rand::SecureRandom;
+
+use error::LoadError;
+use hasher::RingHasher;
+use proof::Proof;
+use {verify_proof, MerkleTree};
+
+pub const SALT_LEN: usize = 32;
+
+// Random salts of the leafs, one after another. Kept apart from the tree, so the tree can be
+// published or stored alone while the salts are handed out only with the proofs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Salts {
+    bytes: Vec<u8>,
+}
+
+impl Salts {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Salts, LoadError> {
+        if !bytes.len().is_multiple_of(SALT_LEN) {
+            return Err(LoadError::NotMultiple { got: bytes.len(), multiple: SALT_LEN });
+        }
+        Ok(Salts { bytes: bytes.to_vec() })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len() / SALT_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        self.bytes.get(index * SALT_LEN..(index + 1) * SALT_LEN)
+    }
+}
+
+// Tree whose leafs are H(salt || value), so the root tells nothing about guessable values
+pub struct SaltedMerkleTree {
+    tree: MerkleTree,
+    salts: Salts,
+}
+
+impl MerkleTree<RingHasher> {
+    pub fn new_salted<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(values: &[T], algo: A, rng: &dyn SecureRandom) -> Result<SaltedMerkleTree, Unspecified> {
+        let algo = algo.into();
+        let mut bytes = vec![0; values.len() * SALT_LEN];
+        rng.fill(&mut bytes)?;
+        let leafs: Vec<_> = values.iter().zip(bytes.chunks(SALT_LEN))
+            .map(|(v, salt)| salted_hash(v.as_ref(), salt, algo))
+            .collect();
+        let tree = MerkleTree::from_leaf_hashes(&leafs, algo).expect("leafs are digests of the algorithm");
+        Ok(SaltedMerkleTree { tree, salts: Salts { bytes } })
+    }
+}
+
+impl SaltedMerkleTree {
+    // Puts back a tree and salts stored apart, they must have the same number of leafs
+    pub fn from_parts(tree: MerkleTree, salts: Salts) -> Result<SaltedMerkleTree, LoadError> {
+        if salts.len() != tree.leafs_count() {
+            return Err(LoadError::BadLength { got: salts.bytes.len(), expected: tree.leafs_count() * SALT_LEN });
+        }
+        Ok(SaltedMerkleTree { tree, salts })
+    }
+
+    pub fn into_parts(self) -> (MerkleTree, Salts) {
+        (self.tree, self.salts)
+    }
+
+    pub fn tree(&self) -> &MerkleTree {
+        &self.tree
+    }
+
+    pub fn salts(&self) -> &Salts {
+        &self.salts
+    }
+
+    pub fn get_root(&self) -> &[u8] {
+        self.tree.get_root()
+    }
+
+    pub fn salt(&self, index: usize) -> Option<&[u8]> {
+        self.salts.get(index)
+    }
+
+    // Salt of the value and its proof, both are needed to verify the value
+    pub fn build_proof_with_salt(&self, index: usize) -> Option<(&[u8], Proof)> {
+        self.salt(index).and_then(|salt| self.tree.build_proof_by_index(index).map(|proof| (salt, proof)))
+    }
+}
+
+pub fn verify_salted_value<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(root: &[u8], value: &T, salt: &[u8], proof: &[u8], algo: A) -> bool {
+    let algo = algo.into();
+    proof.len() >= algo.output_len
+        && salted_hash(value.as_ref(), salt, algo) == proof[..algo.output_len]
+        && verify_proof(root, proof, algo)
+}
+
+fn salted_hash(value: &[u8], salt: &[u8], algo: &'static Algorithm) -> Vec<u8> {
+    let mut ctx = digest::Context::new(algo);
+    ctx.update(salt);
+    ctx.update(value);
+    ctx.finish().as_ref().to_vec()
+}
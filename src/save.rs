@@ -0,0 +1,52 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use binary::Header;
+use error::LoadError;
+use hasher::NamedHasher;
+use MerkleTree;
+
+// Snapshot file: the write_to format followed by a digest of everything before it,
+// made with the hasher of the tree
+impl<H: NamedHasher> MerkleTree<H> {
+    // Written next to the path first and renamed over it, so the file is either old or complete
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut body = Vec::with_capacity(self.header().len() + self.array.len());
+        self.write_to(&mut body)?;
+        let mut checksum = vec![0; self.hasher.output_len()];
+        self.hasher.hash_parts(&[&body], &mut checksum);
+
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        writer.write_all(&body)?;
+        writer.write_all(&checksum)?;
+        writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&temp, path)
+    }
+
+    // The map is built again from the leafs when use_map is set, it is never stored
+    pub fn load<P: AsRef<Path>>(path: P, use_map: bool) -> Result<MerkleTree<H>, LoadError> {
+        let data = fs::read(path)?;
+        let mut header = Header::read(&mut &data[..])?;
+        header.check::<H>()?;
+        let hasher = H::from_name(&header.name).expect("name is checked");
+        let body_len = header.len() + header.array_len;
+        let expected = body_len + hasher.output_len();
+        if data.len() < expected {
+            return Err(LoadError::Truncated);
+        }
+        if data.len() > expected {
+            return Err(LoadError::BadLength { got: data.len(), expected });
+        }
+        let mut checksum = vec![0; hasher.output_len()];
+        hasher.hash_parts(&[&data[..body_len]], &mut checksum);
+        if checksum[..] != data[body_len..] {
+            return Err(LoadError::ChecksumMismatch);
+        }
+        header.use_map = use_map;
+        MerkleTree::from_header(&header, data[header.len()..body_len].to_vec().into())
+    }
+}
@@ -0,0 +1,181 @@
+use std::cmp;
+use std::mem;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use hasher::MerkleHasher;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+// How the last node of a level with odd number of nodes gets its parent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PaddingStrategy {
+    DuplicateLast, // paired with its own copy stored right after it
+    ZeroHash, // paired with an all zero digest stored right after it
+    PromoteOdd, // moved to the next level unchanged, its proofs skip the level
+}
+
+// Hashing and layout rules the tree is built with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Scheme {
+    pub domain_separation: bool,
+    pub sorted_pairs: bool,
+    pub padding: PaddingStrategy,
+    pub double_hash: bool, // nodes are H(H(left || right))
+    pub pad_single_leaf: bool, // lone leaf is paired with its copy instead of being the root
+    #[cfg_attr(feature = "serde", serde(default))] // Absent from trees serialized before it
+    pub double_hash_leaves: bool, // leafs are made from H(value) instead of the value
+}
+
+impl Scheme {
+    pub const LEGACY: Scheme = Scheme {
+        domain_separation: false,
+        sorted_pairs: true,
+        padding: PaddingStrategy::DuplicateLast,
+        double_hash: false,
+        pad_single_leaf: true,
+        double_hash_leaves: false,
+    };
+
+    pub const RFC6962: Scheme = Scheme {
+        domain_separation: true,
+        sorted_pairs: false,
+        padding: PaddingStrategy::PromoteOdd,
+        double_hash: false,
+        pad_single_leaf: false,
+        double_hash_leaves: false,
+    };
+
+    #[cfg(feature = "ring")]
+    pub const BITCOIN: Scheme = Scheme {
+        domain_separation: false,
+        sorted_pairs: false,
+        padding: PaddingStrategy::DuplicateLast,
+        double_hash: true,
+        pad_single_leaf: false,
+        double_hash_leaves: false,
+    };
+
+    pub fn hash_leaf<H: MerkleHasher>(&self, value: &[u8], hasher: &H) -> Vec<u8> {
+        if self.double_hash_leaves { // H(value) takes the place of the value
+            let mut inner = vec![0; hasher.output_len()];
+            hasher.hash_leaf(value, &mut inner);
+            return self.hash_leaf_once(&inner, hasher);
+        }
+        self.hash_leaf_once(value, hasher)
+    }
+
+    fn hash_leaf_once<H: MerkleHasher>(&self, value: &[u8], hasher: &H) -> Vec<u8> {
+        let mut out = vec![0; hasher.output_len()];
+        if self.domain_separation {
+            hasher.hash_parts(&[&[LEAF_PREFIX], value], &mut out);
+        } else {
+            hasher.hash_leaf(value, &mut out);
+        }
+        out
+    }
+
+    // Left and right are given in tree order
+    pub fn hash_pair<H: MerkleHasher>(&self, left: &[u8], right: &[u8], hasher: &H) -> Vec<u8> {
+        let (left, right) = self.order(left, right);
+        let mut out = vec![0; hasher.output_len()];
+        if self.domain_separation {
+            hasher.hash_parts(&[&[NODE_PREFIX], left, right], &mut out);
+        } else {
+            hasher.hash_nodes(left, right, &mut out);
+        }
+        if self.double_hash {
+            let first = out.clone();
+            hasher.hash_parts(&[&first], &mut out);
+        }
+        out
+    }
+
+    // Order in which the pair is concatenated
+    pub fn order<'a>(&self, left: &'a [u8], right: &'a [u8]) -> (&'a [u8], &'a [u8]) {
+        let (mut left, mut right) = (left, right);
+        if self.sorted_pairs {
+            for i in 0..cmp::min(left.len(), right.len()) {
+                if left[i] > right[i] {
+                    mem::swap(&mut left, &mut right);
+                    break;
+                }
+                if left[i] < right[i] {
+                    break;
+                }
+            }
+        }
+        (left, right)
+    }
+
+    // Parent of the last node on a level with odd number of nodes
+    pub fn hash_lone<H: MerkleHasher>(&self, node: &[u8], hasher: &H) -> Vec<u8> {
+        match self.padding {
+            PaddingStrategy::DuplicateLast => self.hash_pair(node, node, hasher),
+            PaddingStrategy::ZeroHash => self.hash_pair(node, &vec![0; node.len()], hasher),
+            PaddingStrategy::PromoteOdd => node.to_vec(),
+        }
+    }
+
+    // Whether an odd level stores a padding node after its last one
+    pub fn pads(&self) -> bool {
+        self.padding != PaddingStrategy::PromoteOdd
+    }
+
+    // Writes the padding node of a level whose last node starts at last, the slot follows it
+    pub fn write_padding(&self, nodes: &mut [u8], last: usize, len: usize) {
+        match self.padding {
+            PaddingStrategy::DuplicateLast => nodes.copy_within(last..last + len, last + len),
+            PaddingStrategy::ZeroHash => nodes[last + len..last + 2 * len].fill(0),
+            PaddingStrategy::PromoteOdd => {}
+        }
+    }
+
+    // Packs the scheme into a byte for the binary format
+    pub fn flags(&self) -> u8 {
+        (self.domain_separation as u8)
+            | (self.sorted_pairs as u8) << 1
+            | ((self.padding == PaddingStrategy::PromoteOdd) as u8) << 2
+            | (self.double_hash as u8) << 3
+            | (self.pad_single_leaf as u8) << 4
+            | ((self.padding == PaddingStrategy::ZeroHash) as u8) << 5
+            | (self.double_hash_leaves as u8) << 6
+    }
+
+    pub fn from_flags(flags: u8) -> Option<Scheme> {
+        if flags >> 7 != 0 {
+            return None;
+        }
+        let padding = match (flags & 1 << 2 != 0, flags & 1 << 5 != 0) {
+            (false, false) => PaddingStrategy::DuplicateLast,
+            (true, false) => PaddingStrategy::PromoteOdd,
+            (false, true) => PaddingStrategy::ZeroHash,
+            (true, true) => return None,
+        };
+        Some(Scheme {
+            domain_separation: flags & 1 != 0,
+            sorted_pairs: flags & 1 << 1 != 0,
+            padding,
+            double_hash: flags & 1 << 3 != 0,
+            pad_single_leaf: flags & 1 << 4 != 0,
+            double_hash_leaves: flags & 1 << 6 != 0,
+        })
+    }
+
+    // Number of nodes physically stored for a level of count nodes
+    pub fn stored_len(&self, count: usize) -> usize {
+        if self.pads() { count + (count & 1) } else { count }
+    }
+
+    pub fn checked_stored_len(&self, count: usize) -> Option<usize> {
+        if self.pads() { count.checked_add(count & 1) } else { Some(count) }
+    }
+
+    pub fn is_root_level(&self, level: usize, count: usize) -> bool {
+        count == 1 && (level > 0 || !self.pad_single_leaf)
+    }
+}
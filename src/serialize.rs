@@ -0,0 +1,49 @@
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+
+use hasher::NamedHasher;
+use scheme::Scheme;
+use MerkleTree;
+
+// Leaf map is not stored, only whether to rebuild it on load
+#[derive(Serialize)]
+struct TreeRef<'a> {
+    algorithm: &'static str,
+    scheme: Scheme,
+    height: usize,
+    items_count: usize,
+    use_map: bool,
+    array: &'a [u8],
+}
+
+#[derive(Deserialize)]
+struct TreeData {
+    algorithm: String,
+    scheme: Scheme,
+    height: usize,
+    items_count: usize,
+    use_map: bool,
+    array: Vec<u8>,
+}
+
+impl<H: NamedHasher> Serialize for MerkleTree<H> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        TreeRef {
+            algorithm: self.hasher.name(),
+            scheme: self.scheme,
+            height: self.height,
+            items_count: self.items_count,
+            use_map: self.map.is_some(),
+            array: &self.array,
+        }.serialize(serializer)
+    }
+}
+
+impl<'de, H: NamedHasher> Deserialize<'de> for MerkleTree<H> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MerkleTree<H>, D::Error> {
+        let data = TreeData::deserialize(deserializer)?;
+        MerkleTree::from_parts(&data.algorithm, data.scheme, data.height, data.items_count, data.use_map, data.array.into())
+            .map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,53 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use hasher::MerkleHasher;
+#[cfg(feature = "ring")]
+use hasher::RingHasher;
+use MerkleTree;
+
+// Immutable tree behind an Arc: nodes, map and hasher are one allocation shared by all clones,
+// so a clone costs a reference count. Every read-only method of MerkleTree is reached through Deref.
+#[cfg(feature = "ring")]
+pub struct SharedMerkleTree<H: MerkleHasher = RingHasher>(Arc<MerkleTree<H>>);
+
+#[cfg(not(feature = "ring"))]
+pub struct SharedMerkleTree<H: MerkleHasher>(Arc<MerkleTree<H>>);
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn into_shared(self) -> SharedMerkleTree<H> {
+        SharedMerkleTree(Arc::new(self))
+    }
+}
+
+impl<H: MerkleHasher> SharedMerkleTree<H> {
+    // The tree back if this is the last clone, otherwise self
+    pub fn try_unwrap(self) -> Result<MerkleTree<H>, SharedMerkleTree<H>> {
+        Arc::try_unwrap(self.0).map_err(SharedMerkleTree)
+    }
+
+    // Whether both clones share the same tree
+    pub fn ptr_eq(&self, other: &SharedMerkleTree<H>) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<H: MerkleHasher> Clone for SharedMerkleTree<H> {
+    fn clone(&self) -> SharedMerkleTree<H> {
+        SharedMerkleTree(Arc::clone(&self.0))
+    }
+}
+
+impl<H: MerkleHasher> Deref for SharedMerkleTree<H> {
+    type Target = MerkleTree<H>;
+
+    fn deref(&self) -> &MerkleTree<H> {
+        &self.0
+    }
+}
+
+impl<H: MerkleHasher> From<MerkleTree<H>> for SharedMerkleTree<H> {
+    fn from(tree: MerkleTree<H>) -> SharedMerkleTree<H> {
+        tree.into_shared()
+    }
+}
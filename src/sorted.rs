@@ -0,0 +1,187 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+
+use ring::digest::Algorithm;
+
+use hasher::{MerkleHasher, RingHasher};
+use proof::{Position, Proof};
+use range::RangeProof;
+use scheme::Scheme;
+use {get_hash, MerkleTree, MerkleTreeBuilder};
+
+// Nodes are hashed in tree order, so neighbouring leafs have neighbouring paths.
+// Leafs are H(0x00 || key) and nodes H(0x01 || left || right), so a node can not pose as a leaf.
+const SCHEME: Scheme = Scheme { domain_separation: true, sorted_pairs: false, ..Scheme::LEGACY };
+
+// Tree over the sorted and deduplicated keys of the values, the key of a value is H(value).
+// A value is absent when its key falls between the keys of two neighbouring leafs,
+// or before the first or after the last one.
+pub struct SortedMerkleTree {
+    tree: MerkleTree,
+    keys: Vec<u8>,
+}
+
+// Inclusion proofs of the leafs right before and right after the missing key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AbsenceProof {
+    pub left: Option<Neighbour>,
+    pub right: Option<Neighbour>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Neighbour {
+    pub key: Vec<u8>,
+    pub proof: Proof,
+}
+
+// Keys of the leafs in range with the range proof of their leafs. Only shows the keys are
+// in the tree, not that no other key has the prefix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrefixProof {
+    pub range: Range<usize>,
+    pub keys: Vec<Vec<u8>>,
+    pub proof: RangeProof,
+}
+
+impl SortedMerkleTree {
+    pub fn new<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(values: &[T], algo: A) -> SortedMerkleTree {
+        let algo = algo.into();
+        let mut keys: Vec<Vec<u8>> = values.iter().map(|v| get_hash(v.as_ref(), algo).as_ref().to_vec()).collect();
+        keys.sort();
+        keys.dedup();
+        let hasher = RingHasher::new(algo);
+        let leafs: Vec<Vec<u8>> = keys.iter().map(|k| SCHEME.hash_leaf(k, &hasher)).collect();
+        let tree = MerkleTreeBuilder::new().algorithm(algo).scheme(SCHEME).build_from_leaf_hashes(&leafs)
+            .expect("hashes have the digest length");
+        SortedMerkleTree { tree, keys: keys.concat() }
+    }
+
+    pub fn tree(&self) -> &MerkleTree {
+        &self.tree
+    }
+
+    pub fn get_root(&self) -> &[u8] {
+        self.tree.get_root()
+    }
+
+    pub fn build_proof<T: AsRef<[u8]>>(&self, value: &T) -> Option<Proof> {
+        self.find(value).ok().and_then(|index| self.tree.build_proof_by_index(index))
+    }
+
+    pub fn validate_value<T: AsRef<[u8]>>(&self, value: &T, proof: &Proof) -> bool {
+        let key = get_hash(value.as_ref(), self.tree.algorithm());
+        SCHEME.hash_leaf(key.as_ref(), self.tree.hasher()) == proof.leaf_hash() && self.tree.validate(proof)
+    }
+
+    // None if the value is in the tree
+    pub fn prove_absence<T: AsRef<[u8]>>(&self, value: &T) -> Option<AbsenceProof> {
+        let index = self.find(value).err()?;
+        let neighbour = |i: usize| self.tree.build_proof_by_index(i).map(|proof| Neighbour { key: self.key(i).to_vec(), proof });
+        Some(AbsenceProof {
+            left: if index > 0 { neighbour(index - 1) } else { None },
+            right: neighbour(index),
+        })
+    }
+
+    // Leafs whose keys start with the prefix, they are next to each other as keys are sorted.
+    // With none it is the empty range where they would be, a prefix longer than the keys has none.
+    pub fn range(&self, prefix: &[u8]) -> Range<usize> {
+        let start = self.partition(|key| key < prefix);
+        let end = self.partition(|key| key < prefix || key.starts_with(prefix));
+        start..end
+    }
+
+    // None when no key has the prefix
+    pub fn prove_range_by_prefix(&self, prefix: &[u8]) -> Option<PrefixProof> {
+        let range = self.range(prefix);
+        let proof = self.tree.build_range_proof(range.clone())?;
+        Some(PrefixProof { keys: range.clone().map(|i| self.key(i).to_vec()).collect(), range, proof })
+    }
+
+    pub fn validate_prefix_range(&self, prefix: &[u8], proof: &PrefixProof) -> bool {
+        if proof.keys.len() != proof.range.len() || proof.keys.iter().any(|key| !key.starts_with(prefix)) {
+            return false;
+        }
+        let leafs: Vec<Vec<u8>> = proof.keys.iter().map(|key| SCHEME.hash_leaf(key, self.tree.hasher())).collect();
+        self.tree.validate_range(proof.range.clone(), &leafs, &proof.proof)
+    }
+
+    fn key(&self, index: usize) -> &[u8] {
+        let len = self.tree.digest_len();
+        &self.keys[index * len..(index + 1) * len]
+    }
+
+    // Index of the key, or where it would be inserted
+    fn find<T: AsRef<[u8]>>(&self, value: &T) -> Result<usize, usize> {
+        let key = get_hash(value.as_ref(), self.tree.algorithm());
+        let (mut low, mut high) = (0, self.tree.leafs_count());
+        while low < high {
+            let middle = (low + high) / 2;
+            match self.key(middle).cmp(key.as_ref()) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return Ok(middle),
+            }
+        }
+        Err(low)
+    }
+
+    // Number of leading keys for which below holds, it must not hold again after it stops
+    fn partition<F: Fn(&[u8]) -> bool>(&self, below: F) -> usize {
+        let (mut low, mut high) = (0, self.tree.leafs_count());
+        while low < high {
+            let middle = (low + high) / 2;
+            if below(self.key(middle)) {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+}
+
+pub fn verify_absence<T: AsRef<[u8]>, A: Into<&'static Algorithm>>(root: &[u8], value: &T, proof: &AbsenceProof, algo: A) -> bool {
+    let algo = algo.into();
+    let hasher = RingHasher::new(algo);
+    let key = get_hash(value.as_ref(), algo);
+    let climbed = |neighbour: &Option<Neighbour>, order: Ordering| match *neighbour {
+        Some(ref n) if n.key[..].cmp(key.as_ref()) == order => climb(n, root, &hasher).map(Some),
+        Some(_) => None,
+        None => Some(None),
+    };
+    match (climbed(&proof.left, Ordering::Less), climbed(&proof.right, Ordering::Greater)) {
+        (Some(Some((left, _))), Some(Some((right, _)))) => right == left + 1,
+        (Some(None), Some(Some((right, _)))) => right == 0, // Before the first leaf
+        (Some(Some((_, last))), Some(None)) => last, // After the last leaf
+        (Some(None), Some(None)) => root.is_empty(),
+        _ => false,
+    }
+}
+
+// Leaf index of a valid proof and whether it is the last leaf: the node is on the right edge
+// when at every level its sibling is either on the left or the padding copy of itself.
+fn climb(neighbour: &Neighbour, root: &[u8], hasher: &RingHasher) -> Option<(usize, bool)> {
+    let proof = &neighbour.proof;
+    let positions = proof.positions()?;
+    if proof.digest_len() != hasher.output_len() || positions.len() >= usize::BITS as usize
+        || SCHEME.hash_leaf(&neighbour.key, hasher) != proof.leaf_hash() {
+        return None;
+    }
+    let mut hash = proof.leaf_hash().to_vec();
+    let mut index = 0;
+    let mut last = true;
+    for (level, (sibling, &position)) in proof.siblings().zip(positions).enumerate() {
+        hash = match position {
+            Position::Left => {
+                index |= 1 << level;
+                SCHEME.hash_pair(sibling, &hash, hasher)
+            }
+            Position::Right => {
+                last &= sibling == &hash[..];
+                SCHEME.hash_pair(&hash, sibling, hasher)
+            }
+        };
+    }
+    if hash == root { Some((index, last)) } else { None }
+}
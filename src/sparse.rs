@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use ring::digest::{Algorithm, Context};
+
+use get_hash;
+
+// Tree over every possible key hash, one level per bit of the digest. A key lives in the leaf
+// at the path given by the bits of its hash, most significant bit first. Subtrees without
+// keys have a precomputed hash per level, so only the paths of inserted keys are stored.
+pub struct SparseMerkleTree {
+    algo: &'static Algorithm,
+    defaults: Vec<Vec<u8>>, // Hash of an empty subtree, by height
+    nodes: HashMap<(usize, Vec<u8>), Vec<u8>>, // By height and path with the bits below zeroed
+    values: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+// Siblings bottom-up, empty subtrees are left out and marked by a zero bit in the bitmap
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SparseProof {
+    bitmap: Vec<u8>,
+    siblings: Vec<u8>,
+}
+
+impl SparseProof {
+    pub fn siblings_count(&self) -> usize {
+        self.bitmap.iter().map(|b| b.count_ones() as usize).sum()
+    }
+}
+
+impl SparseMerkleTree {
+    pub fn new<A: Into<&'static Algorithm>>(algo: A) -> SparseMerkleTree {
+        let algo = algo.into();
+        SparseMerkleTree { algo, defaults: defaults(algo), nodes: HashMap::new(), values: HashMap::new() }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.algo.output_len * 8
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn root(&self) -> &[u8] {
+        let depth = self.depth();
+        self.node(depth, &vec![0; self.algo.output_len])
+    }
+
+    pub fn get<K: AsRef<[u8]>>(&self, key: &K) -> Option<&[u8]> {
+        self.values.get(get_hash(key.as_ref(), self.algo).as_ref()).map(|v| v.as_slice())
+    }
+
+    // Replaces the value of a present key
+    pub fn insert<K: AsRef<[u8]>, V: AsRef<[u8]>>(&mut self, key: &K, value: &V) {
+        let path = get_hash(key.as_ref(), self.algo).as_ref().to_vec();
+        let mut hash = get_hash(value.as_ref(), self.algo).as_ref().to_vec();
+        for height in 0..self.depth() {
+            let node = masked(&path, height);
+            let parent = {
+                let sibling = self.node(height, &flipped(&node, self.depth() - 1 - height));
+                if bit(&path, self.depth() - 1 - height) {
+                    node_hash(sibling, &hash, self.algo)
+                } else {
+                    node_hash(&hash, sibling, self.algo)
+                }
+            };
+            self.nodes.insert((height, node), hash);
+            hash = parent;
+        }
+        self.nodes.insert((self.depth(), vec![0; self.algo.output_len]), hash);
+        self.values.insert(path, value.as_ref().to_vec());
+    }
+
+    // Proves the value of the key, or its absence when the key is not in the tree
+    pub fn prove<K: AsRef<[u8]>>(&self, key: &K) -> SparseProof {
+        let path = get_hash(key.as_ref(), self.algo).as_ref().to_vec();
+        let mut bitmap = vec![0; self.algo.output_len];
+        let mut siblings = vec![];
+        for height in 0..self.depth() {
+            let sibling = self.node(height, &flipped(&masked(&path, height), self.depth() - 1 - height));
+            if sibling != &self.defaults[height][..] {
+                bitmap[height / 8] |= 1 << (height % 8);
+                siblings.extend_from_slice(sibling);
+            }
+        }
+        SparseProof { bitmap, siblings }
+    }
+
+    fn node(&self, height: usize, path: &[u8]) -> &[u8] {
+        match self.nodes.get(&(height, path.to_vec())) {
+            Some(hash) => hash,
+            None => &self.defaults[height],
+        }
+    }
+}
+
+// Inclusion of the value for the key, or exclusion of the key when value is None
+pub fn verify_sparse<K: AsRef<[u8]>, A: Into<&'static Algorithm>>(root: &[u8], key: &K, value: Option<&[u8]>, proof: &SparseProof,
+                                                                  algo: A) -> bool {
+    let algo = algo.into();
+    let len = algo.output_len;
+    let depth = len * 8;
+    if proof.bitmap.len() != len || proof.siblings.len() != proof.siblings_count() * len {
+        return false;
+    }
+    let defaults = defaults(algo);
+    let path = get_hash(key.as_ref(), algo).as_ref().to_vec();
+    let mut hash = match value {
+        Some(v) => get_hash(v, algo).as_ref().to_vec(),
+        None => defaults[0].clone(),
+    };
+    let mut siblings = proof.siblings.chunks(len);
+    for (height, default) in defaults[..depth].iter().enumerate() {
+        let sibling = if proof.bitmap[height / 8] & (1 << (height % 8)) != 0 {
+            siblings.next().expect("siblings count is checked")
+        } else {
+            default
+        };
+        hash = if bit(&path, depth - 1 - height) {
+            node_hash(sibling, &hash, algo)
+        } else {
+            node_hash(&hash, sibling, algo)
+        };
+    }
+    hash == root
+}
+
+// Nodes are hashed in tree order, sorted pairs would let a proof stand for another key
+fn node_hash(left: &[u8], right: &[u8], algo: &'static Algorithm) -> Vec<u8> {
+    let mut ctx = Context::new(algo);
+    ctx.update(left);
+    ctx.update(right);
+    ctx.finish().as_ref().to_vec()
+}
+
+// Empty leaf is all zeros
+fn defaults(algo: &'static Algorithm) -> Vec<Vec<u8>> {
+    let mut result = vec![vec![0; algo.output_len]];
+    for height in 0..algo.output_len * 8 {
+        let hash = node_hash(&result[height], &result[height], algo);
+        result.push(hash);
+    }
+    result
+}
+
+// Bit i of the path, counting from the most significant bit
+fn bit(path: &[u8], i: usize) -> bool {
+    path[i / 8] & (0x80 >> (i % 8)) != 0
+}
+
+fn flipped(path: &[u8], i: usize) -> Vec<u8> {
+    let mut result = path.to_vec();
+    result[i / 8] ^= 0x80 >> (i % 8);
+    result
+}
+
+// Zeroes the lowest height bits, so every node of a subtree has the same path
+fn masked(path: &[u8], height: usize) -> Vec<u8> {
+    let mut result = path.to_vec();
+    let bits = path.len() * 8;
+    for i in bits - height..bits {
+        result[i / 8] &= !(0x80 >> (i % 8));
+    }
+    result
+}
@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+use hasher::MerkleHasher;
+use MerkleTree;
+
+// Duplication of the leaf digests. histogram maps a multiplicity to the number of digests
+// occurring that many times, max_multiplicity is 0 for the empty tree.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LeafStats {
+    pub leafs: usize,
+    pub distinct: usize,
+    pub max_multiplicity: usize,
+    pub histogram: BTreeMap<usize, usize>,
+}
+
+impl LeafStats {
+    pub fn has_duplicates(&self) -> bool {
+        self.max_multiplicity > 1
+    }
+
+    fn add(&mut self, multiplicity: usize) {
+        self.leafs += multiplicity;
+        self.distinct += 1;
+        self.max_multiplicity = self.max_multiplicity.max(multiplicity);
+        *self.histogram.entry(multiplicity).or_insert(0) += 1;
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    // Counted from the positions of the map when there is one, otherwise from a sorted copy
+    // of the leaf hash references. Values are never hashed again.
+    pub fn stats(&self) -> LeafStats {
+        let len = self.hasher.output_len();
+        let leafs = &self.array[..self.items_count * len];
+        let hash = |i: usize| &leafs[i * len..(i + 1) * len];
+        let mut stats = LeafStats::default();
+        if let Some(ref map) = self.map {
+            for (first, rest) in map.groups() {
+                match rest {
+                    None => stats.add(1),
+                    // Different hashes may share a key
+                    Some(rest) => add_sorted(&mut stats, Some(first).into_iter().chain(rest.iter().cloned()).map(hash).collect()),
+                }
+            }
+        } else {
+            add_sorted(&mut stats, leafs.chunks(len).collect());
+        }
+        stats
+    }
+}
+
+fn add_sorted(stats: &mut LeafStats, mut hashes: Vec<&[u8]>) {
+    hashes.sort_unstable();
+    for group in hashes.chunk_by(|a, b| a == b) {
+        stats.add(group.len());
+    }
+}
@@ -0,0 +1,42 @@
+use hasher::MerkleHasher;
+use {level_layout, MerkleTree};
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    // Siblings from the node at (level, index) up to the root, bottom-up. The root itself has no proof.
+    pub fn build_subtree_proof(&self, level: usize, index: usize) -> Option<Vec<u8>> {
+        let layout = level_layout(self.items_count, self.scheme);
+        if level + 1 >= layout.len() || index >= layout[level].1 {
+            return None;
+        }
+        let (start, level_len) = layout[level];
+        let mut result = Vec::with_capacity((layout.len() - level) * self.hasher.output_len());
+        self.parts().add_levels(start * self.hasher.output_len(), index, level_len, &mut result, &mut vec![]);
+        Some(result)
+    }
+
+    // Checks that subtree_root is the node at (level, index) of this tree
+    pub fn validate_subtree(&self, level: usize, index: usize, subtree_root: &[u8], proof: &[u8]) -> bool {
+        let len = self.hasher.output_len();
+        let layout = level_layout(self.items_count, self.scheme);
+        if level + 1 >= layout.len() || index >= layout[level].1 || subtree_root.len() != len {
+            return false;
+        }
+        let mut siblings = proof.chunks(len);
+        let mut hash = subtree_root.to_vec();
+        let mut index = index;
+        for &(_, level_len) in &layout[level..layout.len() - 1] {
+            let sibling = index ^ 1;
+            hash = if sibling < level_len || self.scheme.pads() {
+                match siblings.next() {
+                    Some(s) if s.len() == len && sibling < index => self.scheme.hash_pair(s, &hash, &self.hasher),
+                    Some(s) if s.len() == len => self.scheme.hash_pair(&hash, s, &self.hasher),
+                    _ => return false,
+                }
+            } else { // Promoted to the next level
+                hash
+            };
+            index /= 2;
+        }
+        siblings.next().is_none() && hash == self.get_root()
+    }
+}
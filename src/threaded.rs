@@ -0,0 +1,61 @@
+use std::convert::AsRef;
+use std::panic;
+use std::thread;
+
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+
+use hasher::MerkleHasher;
+#[cfg(feature = "ring")]
+use hasher::RingHasher;
+use scheme::Scheme;
+#[cfg(feature = "ring")]
+use MerkleTreeBuilder;
+use {build_from_leafs, calculate_vec_len, MerkleTree};
+
+#[cfg(feature = "ring")]
+impl MerkleTree<RingHasher> {
+    // Hashes leafs on the given number of threads, one per available core if None
+    pub fn new_threaded<T: AsRef<[u8]> + Sync, A: Into<&'static Algorithm>>(values: &[T], algo: A, threads: Option<usize>) -> MerkleTree {
+        MerkleTreeBuilder::new().algorithm(algo).build_threaded(values, threads).expect("algorithm is set")
+    }
+}
+
+impl<H: MerkleHasher + Sync> MerkleTree<H> {
+    // Every thread hashes a contiguous chunk of leafs, levels are built serially afterwards
+    pub(crate) fn new_threaded_with_scheme<T: AsRef<[u8]> + Sync>(values: &[T], hasher: H, use_map: bool, scheme: Scheme,
+                                                                  threads: Option<usize>) -> MerkleTree<H> {
+        let threads = threads.unwrap_or_else(default_threads).min(values.len());
+        if threads <= 1 { // Nothing to split
+            return MerkleTree::new_with_scheme(values, hasher, use_map, scheme);
+        }
+        let len = hasher.output_len();
+        let mut tree: Vec<u8> = Vec::with_capacity(calculate_vec_len(values.len(), &hasher, scheme));
+        thread::scope(|s| {
+            let hasher = &hasher;
+            let workers: Vec<_> = values.chunks(values.len().div_ceil(threads)).map(|chunk| s.spawn(move || {
+                let mut leafs = Vec::with_capacity(chunk.len() * len);
+                for v in chunk {
+                    leafs.extend_from_slice(&scheme.hash_leaf(v.as_ref(), hasher));
+                }
+                leafs
+            })).collect();
+            for worker in workers {
+                tree.extend_from_slice(&worker.join().unwrap_or_else(|e| panic::resume_unwind(e)));
+            }
+        });
+        let (height, array, map) = build_from_leafs(tree, values.len(), &hasher, use_map, scheme);
+        MerkleTree {
+            array: array.into(),
+            height,
+            items_count: values.len(),
+            map,
+            hasher,
+            scheme,
+        }
+    }
+}
+
+fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
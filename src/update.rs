@@ -0,0 +1,78 @@
+use std::convert::AsRef;
+use std::mem;
+
+use error::Error;
+use hasher::MerkleHasher;
+use scheme::PaddingStrategy;
+use nodes::Nodes;
+use {build_from_leafs, calculate_relatives, calculate_vec_len, level_layout, MerkleTree};
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    // Same tree as a new one over values with this hasher, scheme and map setting. The node array
+    // and the map keep their allocations and grow only when the values need more room.
+    pub fn rebuild<T: AsRef<[u8]>>(&mut self, values: &[T]) {
+        let len = self.hasher.output_len();
+        let mut tree = mem::replace(&mut self.array, Nodes::Memory(Vec::new())).into_empty_vec();
+        tree.reserve_exact(calculate_vec_len(values.len(), &self.hasher, self.scheme));
+        for v in values {
+            tree.extend_from_slice(&self.scheme.hash_leaf(v.as_ref(), &self.hasher));
+        }
+        if let Some(ref mut map) = self.map {
+            map.rebuild(&tree, len);
+        }
+        let (height, array, _) = build_from_leafs(tree, values.len(), &self.hasher, false, self.scheme);
+        self.array = array.into();
+        self.height = height;
+        self.items_count = values.len();
+    }
+
+    // Empty tree as new builds it over no values, memory is kept for the next build
+    pub fn clear(&mut self) {
+        self.rebuild::<&[u8]>(&[]);
+    }
+
+    // Rehashes the leaf and its ancestors only
+    pub fn update_leaf<T: AsRef<[u8]>>(&mut self, index: usize, value: &T) -> Result<(), Error> {
+        if index >= self.items_count {
+            return Err(Error::IndexOutOfRange { index, len: self.items_count });
+        }
+        let len = self.hasher.output_len();
+        let hash = self.scheme.hash_leaf(value.as_ref(), &self.hasher);
+        let old = self.array[index * len..(index + 1) * len].to_vec();
+        self.array[index * len..(index + 1) * len].copy_from_slice(&hash);
+
+        let layout = level_layout(self.items_count, self.scheme);
+        self.refresh_padding(0, layout[0], index);
+        let mut node = index;
+        for level in 1..layout.len() {
+            let (child_start, child_len) = layout[level - 1];
+            let (sibling, parent) = calculate_relatives(node);
+            let left = (child_start + (node & !1)) * len;
+            let hash = if sibling < child_len {
+                self.scheme.hash_pair(&self.array[left..left + len], &self.array[left + len..left + 2 * len], &self.hasher)
+            } else {
+                self.scheme.hash_lone(&self.array[left..left + len], &self.hasher)
+            };
+            let begin = (layout[level].0 + parent) * len;
+            self.array[begin..begin + len].copy_from_slice(&hash);
+            self.refresh_padding(level, layout[level], parent);
+            node = parent;
+        }
+
+        if let Some(ref mut map) = self.map {
+            map.remove(&old, index);
+            map.insert(&hash, index);
+        }
+        Ok(())
+    }
+
+    // Copies the last node of an odd level into its padding slot, a zero padding never changes
+    fn refresh_padding(&mut self, level: usize, (start, level_len): (usize, usize), index: usize) {
+        if self.scheme.padding == PaddingStrategy::DuplicateLast && level_len & 1 == 1 && index == level_len - 1
+            && !self.scheme.is_root_level(level, level_len) {
+            let len = self.hasher.output_len();
+            let last = (start + index) * len;
+            self.array.copy_within(last..last + len, last + len);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// Known answer vectors for verifiers written in other languages. Values come from a seeded
+// generator, so a seed and a size always give the same vectors; the frozen ones pin the
+// hashing and layout of new.
+use ring::digest::Algorithm;
+use serde::{Deserialize, Serialize};
+use serde_json;
+
+use hasher::{NamedHasher, RingHasher};
+use hex;
+use MerkleTree;
+
+pub const FROZEN: [&str; 2] = [
+    include_str!("../vectors/sha256_seed1_size7.json"),
+    include_str!("../vectors/sha512_seed2_size16.json"),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestVectors {
+    pub algorithm: String,
+    pub seed: u64,
+    pub values: Vec<String>, // hex
+    pub root: String, // hex
+    pub proofs: Vec<String>, // hex proof of every value, in the format of Proof::as_bytes
+}
+
+impl TestVectors {
+    pub fn from_json(json: &str) -> Result<TestVectors, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("vectors are always serializable")
+    }
+}
+
+// Values are 1 to 64 bytes long, drawn from splitmix64 started at seed
+pub fn generate(seed: u64, size: usize, algo: &'static Algorithm) -> TestVectors {
+    let mut state = seed;
+    let values: Vec<Vec<u8>> = (0..size).map(|_| {
+        let len = 1 + (splitmix64(&mut state) % 64) as usize;
+        (0..len).map(|_| splitmix64(&mut state) as u8).collect()
+    }).collect();
+    let tree = MerkleTree::new(&values, algo);
+    TestVectors {
+        algorithm: RingHasher::new(algo).name().to_string(),
+        seed,
+        values: values.iter().map(|v| hex::encode(v)).collect(),
+        root: hex::encode(tree.get_root()),
+        proofs: (0..size).map(|i| tree.build_proof_by_index(i).expect("index is in the tree").to_hex()).collect(),
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,230 @@
+use std::slice::Chunks;
+
+#[cfg(feature = "ring")]
+use ring::digest::Algorithm;
+
+use error::{LoadError, ValidationError, ValidationTrace};
+use hasher::MerkleHasher;
+#[cfg(feature = "ring")]
+use hasher::RingHasher;
+use proof::{check_alignment, Position, Proof, ProofFormatError};
+#[cfg(feature = "ring")]
+use root::roots_equal;
+use scheme::Scheme;
+use {calculate_chain, calculate_height, calculate_relatives, calculate_root, calculate_vec_len, level_layout, proof_hashes};
+
+// Borrowed parts of a tree, the read-only code shared by MerkleTree, MerkleTreeView and
+// LazyMerkleTree. The node array is the leaf level followed by upper, which starts with
+// the padding of the leaf level when there is one.
+pub(crate) struct Parts<'a, 'h, H: 'h> {
+    pub(crate) leafs: &'a [u8],
+    pub(crate) upper: &'a [u8],
+    pub(crate) items_count: usize,
+    pub(crate) height: usize,
+    pub(crate) hasher: &'h H,
+    pub(crate) scheme: Scheme,
+}
+
+impl<'a, 'h, H: MerkleHasher> Parts<'a, 'h, H> {
+    // Over a whole node array, as MerkleTree and MerkleTreeView keep it
+    pub(crate) fn new(array: &'a [u8], items_count: usize, height: usize, hasher: &'h H, scheme: Scheme) -> Parts<'a, 'h, H> {
+        let (leafs, upper) = array.split_at(items_count * hasher.output_len());
+        Parts { leafs, upper, items_count, height, hasher, scheme }
+    }
+
+    pub(crate) fn get_root(&self) -> &'a [u8] {
+        let end = self.leafs.len() + self.upper.len();
+        self.bytes(end - end.min(self.hasher.output_len()), end) // Last item
+    }
+
+    pub(crate) fn leaves(&self) -> Chunks<'a, u8> {
+        self.leafs.chunks(self.hasher.output_len())
+    }
+
+    pub(crate) fn levels(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        let len = self.hasher.output_len();
+        let (leafs, upper) = (self.leafs, self.upper);
+        level_layout(self.items_count, self.scheme).into_iter()
+            .map(move |(start, level_len)| node_bytes(leafs, upper, start * len, (start + level_len) * len))
+    }
+
+    pub(crate) fn node(&self, level: usize, index: usize) -> Option<&'a [u8]> {
+        let len = self.hasher.output_len();
+        self.levels().nth(level).and_then(|nodes| nodes.get(index * len..(index + 1) * len))
+    }
+
+    pub(crate) fn build_proof_by_index(&self, index: usize) -> Option<Proof> {
+        if index >= self.items_count { // Padding leaf is not a real item
+            return None;
+        }
+        let len = self.hasher.output_len();
+        let mut vec = Vec::with_capacity(self.height * len);
+        vec.extend_from_slice(self.bytes(index * len, (index + 1) * len));
+        let mut positions = vec![];
+        if !self.scheme.is_root_level(0, self.items_count) {
+            self.add_levels(0, index, self.items_count, &mut vec, &mut positions);
+        }
+        if self.scheme.sorted_pairs {
+            Some(Proof::new(vec, len))
+        } else {
+            Some(Proof::with_positions_unchecked(vec, len, positions))
+        }
+    }
+
+    // Siblings of the node on its level and on every level above, the root excluded
+    pub(crate) fn add_levels(&self, mut start_index: usize, mut index: usize, mut level_len: usize, result: &mut Vec<u8>, positions: &mut Vec<Position>) {
+        let len = self.hasher.output_len();
+        loop {
+            let (sibling, parent) = calculate_relatives(index);
+            if sibling < level_len || self.scheme.pads() { // Promoted node has no sibling
+                result.extend_from_slice(self.bytes(start_index + sibling * len, start_index + (sibling + 1) * len)); //Add sibling to result
+                positions.push(if sibling < index { Position::Left } else { Position::Right });
+            }
+            let next_level_len = level_len.div_ceil(2);
+            if next_level_len == 1 { // Do not include root to proof
+                return;
+            }
+            start_index += self.scheme.stored_len(level_len) * len;
+            index = parent;
+            level_len = next_level_len;
+        }
+    }
+
+    pub(crate) fn try_validate(&self, proof: &Proof) -> Result<(), ValidationError> {
+        if self.leafs.is_empty() && self.upper.is_empty() {
+            return Err(ValidationError::EmptyTree);
+        }
+        let expected = self.hasher.output_len();
+        // Nothing is hashed before the proof is known to split into whole digests
+        check_alignment(proof.as_bytes().len(), expected).map_err(ValidationError::Format)?;
+        if proof.is_empty() { // Once zeroized
+            return Err(ValidationError::Format(ProofFormatError::TooShort { hashes: 0 }));
+        }
+        if proof.digest_len() != expected {
+            return Err(ValidationError::DigestMismatch { got: proof.digest_len(), expected });
+        }
+        // Promoted nodes have no sibling, so such proofs may be shorter but never longer
+        let hashes = proof_hashes(self.items_count, self.scheme);
+        if proof.len() > hashes || (self.scheme.pads() && proof.len() != hashes) {
+            return Err(ValidationError::HashCount { got: proof.len(), expected: hashes });
+        }
+        if !self.scheme.sorted_pairs && proof.positions().is_none() {
+            return Err(ValidationError::MissingPositions);
+        }
+        let computed = calculate_root(proof, self.hasher, self.scheme);
+        if !same_root(&computed, self.get_root()) {
+            return Err(ValidationError::RootMismatch { computed, trace: self.trace(proof) });
+        }
+        Ok(())
+    }
+
+    // Walks the proof again and looks every hash up on its levels, only done once it failed
+    fn trace(&self, proof: &Proof) -> ValidationTrace {
+        let len = self.hasher.output_len();
+        let chain = calculate_chain(proof, self.hasher, self.scheme);
+        let levels: Vec<&[u8]> = self.levels().collect();
+        let skipped = levels.len().saturating_sub(proof.len()); // Levels a promoted node went up unpaired
+        let mismatch = chain.chunks(len).enumerate().position(|(step, hash)| {
+            !levels[step.min(levels.len())..(step + skipped + 1).min(levels.len())].iter()
+                .any(|level| level.chunks(len).any(|node| node == hash))
+        });
+        ValidationTrace::new(chain, len, mismatch)
+    }
+
+    fn bytes(&self, start: usize, end: usize) -> &'a [u8] {
+        node_bytes(self.leafs, self.upper, start, end)
+    }
+}
+
+// Bytes start..end of the node array, a range never spans the end of the leaf level
+fn node_bytes<'a>(leafs: &'a [u8], upper: &'a [u8], start: usize, end: usize) -> &'a [u8] {
+    if end <= leafs.len() {
+        &leafs[start..end]
+    } else {
+        &upper[start - leafs.len()..end - leafs.len()]
+    }
+}
+
+// Constant time where ring is there to do it
+#[cfg(feature = "ring")]
+fn same_root(computed: &[u8], root: &[u8]) -> bool {
+    roots_equal(computed, root)
+}
+
+#[cfg(not(feature = "ring"))]
+fn same_root(computed: &[u8], root: &[u8]) -> bool {
+    computed == root
+}
+
+// Read-only tree over a node array owned by someone else, in the layout of MerkleTree::as_bytes.
+// Nothing is copied, the bytes are only checked to have the length of a tree over items_count leafs.
+#[cfg(feature = "ring")]
+pub struct MerkleTreeView<'a, H: MerkleHasher = RingHasher> {
+    array: &'a [u8],
+    items_count: usize,
+    hasher: H,
+    scheme: Scheme,
+}
+
+#[cfg(not(feature = "ring"))]
+pub struct MerkleTreeView<'a, H: MerkleHasher> {
+    array: &'a [u8],
+    items_count: usize,
+    hasher: H,
+    scheme: Scheme,
+}
+
+#[cfg(feature = "ring")]
+impl<'a> MerkleTreeView<'a, RingHasher> {
+    pub fn new<A: Into<&'static Algorithm>>(bytes: &'a [u8], items_count: usize, algo: A) -> Result<MerkleTreeView<'a>, LoadError> {
+        MerkleTreeView::with_hasher(bytes, items_count, RingHasher::new(algo.into()))
+    }
+}
+
+impl<'a, H: MerkleHasher> MerkleTreeView<'a, H> {
+    // Nodes of a tree built with the legacy scheme, as from_bytes_with_hasher takes them
+    pub fn with_hasher(bytes: &'a [u8], items_count: usize, hasher: H) -> Result<MerkleTreeView<'a, H>, LoadError> {
+        let scheme = Scheme::LEGACY;
+        // Checked before the length is computed, which would overflow for absurd counts
+        if items_count > bytes.len() / hasher.output_len() {
+            return Err(LoadError::BadLength { got: bytes.len(), expected: items_count.saturating_mul(hasher.output_len()) });
+        }
+        let expected = calculate_vec_len(items_count, &hasher, scheme);
+        if bytes.len() != expected {
+            return Err(LoadError::BadLength { got: bytes.len(), expected });
+        }
+        Ok(MerkleTreeView { array: bytes, items_count, hasher, scheme })
+    }
+
+    pub fn get_root(&self) -> &'a [u8] {
+        self.parts().get_root()
+    }
+
+    pub fn leafs_count(&self) -> usize {
+        self.items_count
+    }
+
+    pub fn leaves(&self) -> Chunks<'a, u8> {
+        self.parts().leaves()
+    }
+
+    pub fn node(&self, level: usize, index: usize) -> Option<&'a [u8]> {
+        self.parts().node(level, index)
+    }
+
+    pub fn build_proof_by_index(&self, index: usize) -> Option<Proof> {
+        self.parts().build_proof_by_index(index)
+    }
+
+    pub fn validate(&self, proof: &Proof) -> bool {
+        self.try_validate(proof).is_ok()
+    }
+
+    pub fn try_validate(&self, proof: &Proof) -> Result<(), ValidationError> {
+        self.parts().try_validate(proof)
+    }
+
+    fn parts(&self) -> Parts<'a, '_, H> {
+        Parts::new(self.array, self.items_count, calculate_height(self.items_count, self.scheme), &self.hasher, self.scheme)
+    }
+}
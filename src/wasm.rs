@@ -0,0 +1,45 @@
+use js_sys::Uint8Array;
+use ring::digest::Algorithm;
+use wasm_bindgen::prelude::*;
+
+use error::LoadError;
+use hasher::{NamedHasher, RingHasher};
+use {verify_proof, MerkleTree};
+
+// Tree for JavaScript, values, roots and proofs cross the boundary as Uint8Array
+#[wasm_bindgen]
+pub struct WasmMerkleTree {
+    tree: MerkleTree,
+}
+
+#[wasm_bindgen]
+impl WasmMerkleTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new(values: Vec<Uint8Array>, algo: &str) -> Result<WasmMerkleTree, JsValue> {
+        let algo = algorithm(algo)?;
+        let values: Vec<Vec<u8>> = values.iter().map(|v| v.to_vec()).collect();
+        Ok(WasmMerkleTree { tree: MerkleTree::new(&values, algo) })
+    }
+
+    pub fn root(&self) -> Uint8Array {
+        Uint8Array::from(self.tree.get_root())
+    }
+
+    // Proof in the layout of Proof::as_bytes
+    #[wasm_bindgen(js_name = buildProof)]
+    pub fn build_proof(&self, value: Uint8Array) -> Option<Uint8Array> {
+        self.tree.build_proof(&value.to_vec()).map(|proof| Uint8Array::from(proof.as_bytes()))
+    }
+}
+
+#[wasm_bindgen(js_name = verifyProof)]
+pub fn verify(root: Uint8Array, proof: Uint8Array, algo: &str) -> Result<bool, JsValue> {
+    Ok(verify_proof(&root.to_vec(), &proof.to_vec(), algorithm(algo)?))
+}
+
+// By the names trees are stored with, "sha256", "sha512" and so on
+fn algorithm(name: &str) -> Result<&'static Algorithm, JsValue> {
+    RingHasher::from_name(name)
+        .map(|hasher| hasher.algorithm())
+        .ok_or_else(|| JsValue::from_str(&LoadError::UnknownAlgorithm(name.to_string()).to_string()))
+}
@@ -0,0 +1,45 @@
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use hasher::MerkleHasher;
+use nodes::Nodes;
+use proof::Proof;
+use MerkleTree;
+
+// Trees over sensitive values: the in-memory node array is zeroed over its whole allocation
+// when the tree is dropped or zeroized. Copies left behind by a growing array are not reached,
+// build the tree at its final size. A mapped array is the file, it is unmapped and left as it is.
+// The leaf map keeps no hashes, only 64 bit keys of their first 8 bytes, random SipHash outputs
+// unless map_hasher is set. Its tables are emptied but not zeroed, HashMap gives no access to them.
+impl<H: MerkleHasher> Zeroize for MerkleTree<H> {
+    // Leaves the empty tree clear makes
+    fn zeroize(&mut self) {
+        self.clear();
+    }
+}
+
+impl<H: MerkleHasher> ZeroizeOnDrop for MerkleTree<H> {}
+
+impl Drop for Nodes {
+    fn drop(&mut self) {
+        match *self {
+            Nodes::Memory(ref mut array) => array.zeroize(),
+            #[cfg(feature = "mmap")]
+            Nodes::Mapped { .. } => {}
+        }
+    }
+}
+
+// Bytes are zeroed and dropped, positions only tell the index and are dropped as they are
+impl Zeroize for Proof {
+    fn zeroize(&mut self) {
+        self.wipe();
+    }
+}
+
+impl ZeroizeOnDrop for Proof {}
+
+impl Drop for Proof {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
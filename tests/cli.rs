@@ -0,0 +1,98 @@
+#![cfg(feature = "cli")]
+
+extern crate ring;
+extern crate vmt;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use ring::digest::SHA384;
+
+use vmt::{hex, MerkleTree};
+
+// Directory with one file per value, removed when dropped
+struct Files {
+    dir: PathBuf,
+    paths: Vec<String>,
+}
+
+impl Files {
+    fn new(name: &str, values: &[&str]) -> Files {
+        let dir = env::temp_dir().join(format!("vmt-cli-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths = values.iter().enumerate().map(|(i, value)| {
+            let path = dir.join(format!("{}.txt", i));
+            fs::write(&path, value).unwrap();
+            path.to_str().unwrap().to_string()
+        }).collect();
+        Files { dir, paths }
+    }
+}
+
+impl Drop for Files {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn vmt(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_vmt")).args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout.clone()).unwrap().trim().to_string()
+}
+
+#[test]
+fn test_root_proof_verify() {
+    let values = ["one", "two", "three"];
+    let files = Files::new("round-trip", &values);
+    let mut args = vec!["--algo", "sha384", "root"];
+    args.extend(files.paths.iter().map(|p| p.as_str()));
+    let root = stdout(&vmt(&args));
+    assert_eq!(hex::encode(MerkleTree::new(&values, &SHA384).get_root()), root);
+
+    args[2] = "proof";
+    args.extend(&["--target", &files.paths[1]]);
+    let proof = stdout(&vmt(&args));
+    assert_eq!(MerkleTree::new(&values, &SHA384).build_proof(&"two").unwrap().to_hex(), proof);
+
+    assert_eq!("valid", stdout(&vmt(&["verify", "--algo", "sha384", "--root", &root, "--proof", &proof])));
+    let saved = files.dir.join("proof.hex");
+    fs::write(&saved, format!("{}\n", proof)).unwrap();
+    assert_eq!("valid", stdout(&vmt(&["verify", "--algo", "sha384", "--root", &root, "--proof", saved.to_str().unwrap()])));
+
+    let output = vmt(&["verify", "--root", &root, "--proof", &proof]); // Default sha256
+    assert_eq!(Some(1), output.status.code());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("does not match"));
+}
+
+#[test]
+fn test_every_listed_algorithm() {
+    let files = Files::new("algorithms", &["one", "two"]);
+    for &(name, len) in &[("sha1", 20), ("sha256", 32), ("sha384", 48), ("sha512", 64), ("sha512_256", 32)] {
+        let root = stdout(&vmt(&["--algo", name, "root", &files.paths[0], &files.paths[1]]));
+        assert_eq!(2 * len, root.len());
+    }
+}
+
+#[test]
+fn test_errors() {
+    let files = Files::new("errors", &["one"]);
+    assert_eq!(Some(2), vmt(&[]).status.code());
+    let output = vmt(&["--algo", "md5", "root", &files.paths[0]]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("sha1|sha256|sha384|sha512|sha512_256"));
+    assert_eq!(Some(2), vmt(&["proof", &files.paths[0]]).status.code());
+    let output = vmt(&["root", "/nonexistent/file"]);
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("/nonexistent/file"));
+    let absent = files.dir.join("absent.txt");
+    fs::write(&absent, "two").unwrap();
+    assert_eq!(Some(1), vmt(&["proof", &files.paths[0], "--target", absent.to_str().unwrap()]).status.code());
+    assert_eq!(Some(1), vmt(&["verify", "--root", "zz", "--proof", "00"]).status.code());
+}
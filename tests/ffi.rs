@@ -0,0 +1,63 @@
+#![cfg(feature = "ffi")]
+
+extern crate ring;
+extern crate vmt;
+
+use std::ptr;
+
+use ring::digest::SHA256;
+
+use vmt::ffi::*;
+use vmt::MerkleTree;
+
+fn new_tree(values: &[&[u8]], algo_id: i32) -> *mut VmtTree {
+    let pointers: Vec<*const u8> = values.iter().map(|v| v.as_ptr()).collect();
+    let lens: Vec<usize> = values.iter().map(|v| v.len()).collect();
+    unsafe { vmt_tree_new(pointers.as_ptr(), lens.as_ptr(), values.len(), algo_id) }
+}
+
+#[test]
+fn test_proof_round_trip() {
+    let values: [&[u8]; 3] = [b"one", b"two", b"three"];
+    let tree = new_tree(&values, VMT_SHA256);
+    assert!(!tree.is_null());
+    let mut root = [0; 32];
+    let mut written = 0;
+    unsafe {
+        assert_eq!(VMT_OK, vmt_tree_root(tree, root.as_mut_ptr(), root.len(), &mut written));
+        assert_eq!(32, written);
+        assert_eq!(MerkleTree::new(&values, &SHA256).get_root(), &root[..]);
+
+        let mut proof = vec![0; 16];
+        let code = vmt_tree_build_proof(tree, b"two".as_ptr(), 3, proof.as_mut_ptr(), proof.len(), &mut written);
+        assert_eq!(VMT_BUFFER_TOO_SMALL, code);
+        proof.resize(written, 0);
+        assert_eq!(VMT_OK, vmt_tree_build_proof(tree, b"two".as_ptr(), 3, proof.as_mut_ptr(), proof.len(), &mut written));
+        assert_eq!(1, vmt_verify_proof(root.as_ptr(), root.len(), proof.as_ptr(), proof.len(), VMT_SHA256));
+        proof[0] ^= 1;
+        assert_eq!(0, vmt_verify_proof(root.as_ptr(), root.len(), proof.as_ptr(), proof.len(), VMT_SHA256));
+
+        let code = vmt_tree_build_proof(tree, b"four".as_ptr(), 4, proof.as_mut_ptr(), proof.len(), &mut written);
+        assert_eq!(VMT_NOT_FOUND, code);
+        vmt_tree_free(tree);
+    }
+}
+
+#[test]
+fn test_error_codes() {
+    assert!(new_tree(&[b"one"], 42).is_null());
+    unsafe {
+        assert!(vmt_tree_new(ptr::null(), ptr::null(), 2, VMT_SHA256).is_null());
+        let mut written = 0;
+        assert_eq!(VMT_NULL_POINTER, vmt_tree_root(ptr::null(), ptr::null_mut(), 0, &mut written));
+        assert_eq!(VMT_UNKNOWN_ALGORITHM, vmt_verify_proof(ptr::null(), 0, ptr::null(), 0, 0));
+        assert_eq!(VMT_NULL_POINTER, vmt_verify_proof(ptr::null(), 32, ptr::null(), 0, VMT_SHA256));
+        vmt_tree_free(ptr::null_mut());
+
+        let empty = vmt_tree_new(ptr::null(), ptr::null(), 0, VMT_SHA512);
+        assert!(!empty.is_null());
+        assert_eq!(VMT_OK, vmt_tree_root(empty, ptr::null_mut(), 0, &mut written));
+        assert_eq!(0, written);
+        vmt_tree_free(empty);
+    }
+}
@@ -1,11 +1,115 @@
+#![cfg(feature = "ring")]
+
+#[cfg(feature = "serde")]
+extern crate bincode;
 extern crate ring;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(feature = "keccak")]
+extern crate tiny_keccak;
+#[cfg(feature = "signing")]
+extern crate untrusted;
 extern crate vmt;
+#[cfg(feature = "zeroize")]
+extern crate zeroize;
+
+// Helpers shared by the test modules below
+mod helpers {
+    use std::hash::{BuildHasher, Hasher};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use vmt::{MerkleHasher, MerkleTree, RingHasher};
+
+    // 64 bit FNV-1a, enough to exercise a hasher ring knows nothing about
+    pub struct Fnv;
+
+    impl MerkleHasher for Fnv {
+        fn output_len(&self) -> usize {
+            8
+        }
+
+        fn hash_parts(&self, parts: &[&[u8]], out: &mut [u8]) {
+            let mut hash: u64 = 0xcbf29ce484222325;
+            for byte in parts.iter().flat_map(|p| p.iter()) {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+            out.copy_from_slice(&hash.to_be_bytes());
+        }
+    }
+
+    // Counts the pairs it hashes
+    pub struct Counting {
+        pub inner: RingHasher,
+        pub pairs: Arc<AtomicUsize>,
+    }
+
+    impl MerkleHasher for Counting {
+        fn output_len(&self) -> usize {
+            self.inner.output_len()
+        }
+
+        fn hash_parts(&self, parts: &[&[u8]], out: &mut [u8]) {
+            self.inner.hash_parts(parts, out)
+        }
+
+        fn hash_nodes(&self, left: &[u8], right: &[u8], out: &mut [u8]) {
+            self.pairs.fetch_add(1, Ordering::SeqCst);
+            self.inner.hash_nodes(left, right, out)
+        }
+    }
+
+    // Map hasher that puts every key on the same slot
+    pub struct Constant;
+
+    impl Hasher for Constant {
+        fn finish(&self) -> u64 {
+            7
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+
+    impl BuildHasher for Constant {
+        type Hasher = Constant;
+
+        fn build_hasher(&self) -> Constant {
+            Constant
+        }
+    }
+
+    // "value 0" to "value {count - 1}"
+    pub fn values(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("value {}", i)).collect()
+    }
+
+    // Values of random length and content from a fixed seed
+    #[cfg(feature = "rayon")]
+    pub fn random_values(count: usize, seed: u64) -> Vec<Vec<u8>> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 33) as u8
+        };
+        (0..count).map(|_| (0..next() % 64).map(|_| next()).collect()).collect()
+    }
+
+    // Tree in the write_to format, nodes and layout included, to compare two trees byte for byte
+    pub fn stored(tree: &MerkleTree) -> Vec<u8> {
+        let mut bytes = vec![];
+        tree.write_to(&mut bytes).unwrap();
+        bytes
+    }
+}
 
 macro_rules! test_tree {
     ($constructor:ident) => {
         use ring::digest::{Algorithm, Context, Digest, SHA512};
 
-        use vmt::MerkleTree;
+        use vmt::{MerkleTree, Proof};
 
         static ALGO: &'static Algorithm = &SHA512;
 
@@ -149,7 +253,7 @@ macro_rules! test_tree {
                 assert_eq!(true, proof.is_some());
                 let vec = proof.unwrap();
                 assert_eq!(3, vec.len());
-                tree.validate(&vec);
+                assert_eq!(true, tree.validate(&vec));
             }
 
             let absent = vec!["qqq", "www", "eee", "rrr"];
@@ -168,10 +272,219 @@ macro_rules! test_tree {
             assert_eq!(true, proof.is_some());
             let _d0: Digest = vmt::get_hash("five".as_ref(), ALGO);
             let proof_vec = proof.unwrap();
-            let vec = vec![proof_vec[0], proof_vec[1], _d0.as_ref()];
+            let mut bytes = proof_vec.as_bytes()[..2 * ALGO.output_len].to_vec();
+            bytes.extend_from_slice(_d0.as_ref());
+            let vec = Proof::from_bytes(&bytes, ALGO.output_len).unwrap();
             assert_eq!(false, tree.validate(&vec));
         }
 
+        #[test]
+        fn test_proof_accessors() {
+            let values = vec!["one", "two", "three", "four"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+            let proof = tree.build_proof(&"three").unwrap();
+
+            let _d2: Digest = vmt::get_hash(values[2].as_ref(), ALGO);
+            let _d3: Digest = vmt::get_hash(values[3].as_ref(), ALGO);
+            let _d01 = vmt::get_pair_hash(
+                vmt::get_hash(values[0].as_ref(), ALGO).as_ref(),
+                vmt::get_hash(values[1].as_ref(), ALGO).as_ref(),
+                ALGO);
+
+            assert_eq!(ALGO.output_len, proof.digest_len());
+            assert_eq!(_d2.as_ref(), proof.leaf_hash());
+            let siblings: Vec<&[u8]> = proof.siblings().collect();
+            assert_eq!(vec![_d3.as_ref(), _d01.as_ref()], siblings);
+            assert_eq!(3 * ALGO.output_len, proof.as_bytes().len());
+            assert_eq!(false, proof.is_empty());
+
+            let copy = Proof::from_bytes(proof.as_bytes(), ALGO.output_len).unwrap();
+            assert_eq!(proof, copy);
+            assert_eq!(true, tree.validate(&copy));
+        }
+
+        #[test]
+        fn test_verify_proof_against_root() {
+            let values = vec!["one", "two", "three", "four", "five"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+            let root = tree.get_root().to_vec();
+
+            for v in &values {
+                let proof = tree.build_proof(v).unwrap();
+                assert_eq!(true, vmt::verify_proof(&root, proof.as_bytes(), ALGO));
+            }
+            let proof = tree.build_proof(&"one").unwrap();
+            let others = ["six", "seven"];
+            let other = MerkleTree::$constructor(&others, ALGO);
+            assert_eq!(false, vmt::verify_proof(other.get_root(), proof.as_bytes(), ALGO));
+        }
+
+        #[test]
+        fn test_verify_proof_single_leaf() {
+            let values = vec!["one"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+            let proof = tree.build_proof(&"one").unwrap();
+
+            assert_eq!(2, proof.len());
+            assert_eq!(true, vmt::verify_proof(tree.get_root(), proof.as_bytes(), ALGO));
+        }
+
+        #[test]
+        fn test_verify_proof_malformed() {
+            let values = vec!["one", "two", "three"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+            let root = tree.get_root();
+            let proof = tree.build_proof(&"two").unwrap();
+            let bytes = proof.as_bytes();
+
+            assert_eq!(false, vmt::verify_proof(root, &[], ALGO));
+            assert_eq!(false, vmt::verify_proof(root, &bytes[..ALGO.output_len], ALGO));
+            assert_eq!(false, vmt::verify_proof(root, &bytes[..bytes.len() - 1], ALGO));
+            assert_eq!(false, vmt::verify_proof(&[], bytes, ALGO));
+        }
+
+        #[test]
+        fn test_proof_by_index() {
+            let values = vec!["one", "two", "three", "four", "five"];
+            for len in 4..6 {
+                let tree = MerkleTree::$constructor(&values[..len], ALGO);
+
+                assert_eq!(tree.build_proof(&values[0]), tree.build_proof_by_index(0));
+                assert_eq!(tree.build_proof(&values[len - 1]), tree.build_proof_by_index(len - 1));
+                assert_eq!(true, tree.validate(&tree.build_proof_by_index(len - 1).unwrap()));
+                assert_eq!(None, tree.build_proof_by_index(len)); // Padded or out of bounds
+                assert_eq!(None, tree.build_proof_by_index(len + 1));
+            }
+        }
+
+        #[test]
+        fn test_proof_by_index_duplicates() {
+            let values = vec!["one", "two", "one"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+            let first = tree.build_proof_by_index(0).unwrap();
+            let last = tree.build_proof_by_index(2).unwrap();
+
+            assert_eq!(first.leaf_hash(), last.leaf_hash());
+            assert_eq!(false, first == last);
+            assert_eq!(true, tree.validate(&first));
+            assert_eq!(true, tree.validate(&last));
+        }
+
+        #[test]
+        fn test_proof_by_hash() {
+            let values = vec!["one", "two", "three"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+
+            for v in &values {
+                let _d: Digest = vmt::get_hash(v.as_ref(), ALGO);
+                assert_eq!(tree.build_proof(v), tree.build_proof_by_hash(_d.as_ref()));
+            }
+            let _absent: Digest = vmt::get_hash("four".as_ref(), ALGO);
+            assert_eq!(None, tree.build_proof_by_hash(_absent.as_ref()));
+            let _d0: Digest = vmt::get_hash(values[0].as_ref(), ALGO);
+            assert_eq!(None, tree.build_proof_by_hash(&_d0.as_ref()[1..]));
+            assert_eq!(None, tree.build_proof_by_hash(&[]));
+        }
+
+        #[test]
+        fn test_contains() {
+            let values = vec!["one", "two", "one", "three", "one"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+
+            assert_eq!(true, tree.contains(&"one"));
+            assert_eq!(true, tree.contains(&"three"));
+            assert_eq!(false, tree.contains(&"four"));
+            assert_eq!(Some(0), tree.index_of(&"one"));
+            assert_eq!(Some(1), tree.index_of(&"two"));
+            assert_eq!(None, tree.index_of(&"four"));
+            assert_eq!(vec![0, 2, 4], tree.indices_of(&"one"));
+            assert_eq!(vec![3], tree.indices_of(&"three"));
+            assert_eq!(true, tree.indices_of(&"four").is_empty());
+        }
+
+        #[test]
+        fn test_contains_empty() {
+            let values: Vec<&str> = vec![];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+
+            assert_eq!(false, tree.contains(&"one"));
+            assert_eq!(None, tree.index_of(&"one"));
+            assert_eq!(true, tree.indices_of(&"one").is_empty());
+        }
+
+        #[test]
+        fn test_validate_value() {
+            let values = vec!["one", "two", "three", "four", "five"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+
+            for v in &values {
+                let proof = tree.build_proof(v).unwrap();
+                assert_eq!(true, tree.validate_value(v, &proof));
+                assert_eq!(true, vmt::verify_value(tree.get_root(), v, proof.as_bytes(), ALGO));
+            }
+        }
+
+        #[test]
+        fn test_validate_value_substitution() {
+            let values = vec!["one", "two", "three", "four"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+            let proof = tree.build_proof(&"two").unwrap();
+
+            assert_eq!(true, tree.validate(&proof)); // Chain alone is fine
+            assert_eq!(false, tree.validate_value(&"one", &proof));
+            assert_eq!(false, vmt::verify_value(tree.get_root(), &"one", proof.as_bytes(), ALGO));
+        }
+
+        #[test]
+        fn test_validate_value_single_leaf() {
+            let values = vec!["one"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+            let proof = tree.build_proof(&"one").unwrap();
+
+            assert_eq!(proof.leaf_hash(), proof.siblings().next().unwrap());
+            assert_eq!(true, tree.validate_value(&"one", &proof));
+            assert_eq!(true, vmt::verify_value(tree.get_root(), &"one", proof.as_bytes(), ALGO));
+        }
+
+        #[test]
+        fn test_verify_value_short_proof() {
+            let values = vec!["one", "two"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+            let _d0: Digest = vmt::get_hash(values[0].as_ref(), ALGO);
+
+            assert_eq!(false, vmt::verify_value(tree.get_root(), &"one", &[], ALGO));
+            assert_eq!(false, vmt::verify_value(tree.get_root(), &"one", _d0.as_ref(), ALGO));
+        }
+
+        #[test]
+        fn test_validate_bytes_malformed() {
+            let values = vec!["one", "two", "three", "four"];
+            let tree = MerkleTree::$constructor(&values, ALGO);
+            let proof = tree.build_proof(&"three").unwrap();
+            let bytes = proof.as_bytes();
+            let mut longer = bytes.to_vec();
+            longer.extend_from_slice(&bytes[..ALGO.output_len]);
+
+            assert_eq!(true, tree.validate_bytes(bytes));
+            assert_eq!(false, tree.validate_bytes(&[]));
+            assert_eq!(false, tree.validate_bytes(&bytes[..ALGO.output_len]));
+            assert_eq!(false, tree.validate_bytes(&bytes[..bytes.len() - 1]));
+            assert_eq!(false, tree.validate_bytes(&bytes[1..]));
+            assert_eq!(false, tree.validate_bytes(&longer));
+            longer.push(0);
+            assert_eq!(false, tree.validate_bytes(&longer));
+        }
+
+        #[test]
+        fn test_pair_hash_short_input() {
+            let _pair = vmt::get_pair_hash(&[1, 2], &[1, 2, 3], ALGO);
+            let _swapped = vmt::get_pair_hash(&[1, 2, 3], &[1, 2], ALGO);
+            let _empty = vmt::get_pair_hash(&[], &[], ALGO);
+
+            assert_eq!(ALGO.output_len, _pair.as_ref().len());
+            assert_eq!(ALGO.output_len, _empty.as_ref().len());
+        }
+
         fn hash_pair(x: &[u8], y: &[u8], algo: &'static Algorithm) -> Digest {
             let mut ctx = Context::new(algo);
             ctx.update(x);
@@ -185,6 +498,4443 @@ mod test {
     test_tree!(new);
 }
 
+mod test_leaf_hashes {
+    use ring::digest::{Digest, SHA256};
+
+    use vmt::{Error, MerkleTree};
+
+    #[test]
+    fn test_from_leaf_hashes_root() {
+        let values = ["one", "two", "three", "four", "five"];
+        let hashes: Vec<Digest> = values.iter().map(|v| vmt::get_hash(v.as_ref(), &SHA256)).collect();
+        for len in 0..values.len() + 1 {
+            let tree = MerkleTree::new(&values[..len], &SHA256);
+            let from_hashes = MerkleTree::from_leaf_hashes(&hashes[..len], &SHA256).unwrap();
+
+            assert_eq!(tree.get_root(), from_hashes.get_root());
+            assert_eq!(tree.height(), from_hashes.height());
+            assert_eq!(tree.nodes_count(), from_hashes.nodes_count());
+        }
+    }
+
+    #[test]
+    fn test_from_leaf_hashes_map() {
+        let values = vec!["one", "two", "three"];
+        let hashes: Vec<Digest> = values.iter().map(|v| vmt::get_hash(v.as_ref(), &SHA256)).collect();
+        let tree = MerkleTree::new(&values, &SHA256);
+        let from_hashes = MerkleTree::from_leaf_hashes_with_map(&hashes, &SHA256).unwrap();
+
+        for (v, h) in values.iter().zip(hashes.iter()) {
+            assert_eq!(tree.build_proof(v), from_hashes.build_proof_by_hash(h.as_ref()));
+        }
+    }
+
+    #[test]
+    fn test_from_leaf_hashes_bad_length() {
+        let hashes = vec![vec![0u8; 32], vec![1u8; 31]];
+        let result = MerkleTree::from_leaf_hashes(&hashes, &SHA256);
+
+        assert_eq!(Some(Error::BadHashLength { index: 1, len: 31, expected: 32 }), result.err());
+    }
+}
+
+mod test_validation_error {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{MerkleTree, Proof, ProofFormatError, ValidationError};
+
+    #[test]
+    fn test_try_validate_ok() {
+        let values = ["one", "two", "three"];
+        let tree = MerkleTree::new(&values, &SHA512);
+        let proof = tree.build_proof(&"two").unwrap();
+
+        assert_eq!(Ok(()), tree.try_validate(&proof));
+    }
+
+    #[test]
+    fn test_try_validate_empty_tree() {
+        let values: [&str; 0] = [];
+        let empty = MerkleTree::new(&values, &SHA512);
+        let tree = MerkleTree::new(&["one", "two"], &SHA512);
+        let proof = tree.build_proof(&"two").unwrap();
+
+        assert_eq!(Err(ValidationError::EmptyTree), empty.try_validate(&proof));
+    }
+
+    #[test]
+    fn test_try_validate_bad_length() {
+        let values = ["one", "two", "three"];
+        let tree = MerkleTree::new(&values, &SHA512);
+        let short = MerkleTree::new(&values, &SHA256);
+        let proof = short.build_proof(&"one").unwrap(); // 3 * 32 bytes
+
+        assert_eq!(Err(ValidationError::Format(ProofFormatError::NotAligned { len: 96, digest_len: 64 })), tree.try_validate(&proof));
+    }
+
+    #[test]
+    fn test_try_validate_digest_mismatch() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA512);
+        let proof = Proof::from_bytes(&[0; 128], 32).unwrap();
+
+        assert_eq!(Err(ValidationError::DigestMismatch { got: 32, expected: 64 }), tree.try_validate(&proof));
+    }
+
+    #[test]
+    fn test_try_validate_root_mismatch() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA512);
+        let other = MerkleTree::new(&["four", "five", "six"], &SHA512);
+        let proof = other.build_proof(&"four").unwrap();
+
+        match tree.try_validate(&proof) {
+            Err(ValidationError::RootMismatch { computed, .. }) => assert_eq!(other.get_root(), &computed[..]),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_error_display() {
+        let error: Box<dyn std::error::Error> = Box::new(ValidationError::Format(ProofFormatError::NotAligned { len: 5, digest_len: 64 }));
+        assert_eq!("proof length 5 is not a multiple of digest length 64", error.to_string());
+    }
+}
+
+mod test_multiproof {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, MultiProof};
+
+    static VALUES: [&str; 9] = ["a", "b", "c", "d", "e", "f", "g", "h", "i"];
+
+    #[test]
+    fn test_multiproof_single_matches_proof() {
+        for len in 2..VALUES.len() + 1 {
+            let tree = MerkleTree::new(&VALUES[..len], &SHA256);
+            for i in 0..len {
+                let proof = tree.build_proof_by_index(i).unwrap();
+                let multi = tree.build_multiproof(&[i]).unwrap();
+                let mut siblings = proof.siblings();
+                for hash in multi.hashes() { // Same path without padding duplicates
+                    assert!(siblings.any(|s| s == hash));
+                }
+                if len.is_power_of_two() {
+                    assert_eq!(proof.siblings().count(), multi.hashes().count());
+                }
+                assert_eq!(vec![proof.leaf_hash()], multi.leaf_hashes().collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn test_multiproof_siblings() {
+        let tree = MerkleTree::new(&VALUES[..4], &SHA256);
+        let multi = tree.build_multiproof(&[1, 0]).unwrap();
+        let proof = tree.build_proof_by_index(0).unwrap();
+
+        assert_eq!(&[0, 1], multi.indices());
+        assert_eq!(4, multi.leaf_count());
+        assert_eq!(vec![proof.siblings().nth(1).unwrap()], multi.hashes().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_multiproof_all_leaves() {
+        for len in 1..VALUES.len() + 1 {
+            let tree = MerkleTree::new(&VALUES[..len], &SHA256);
+            let indices: Vec<usize> = (0..len).collect();
+            let multi = tree.build_multiproof(&indices).unwrap();
+
+            assert_eq!(0, multi.hashes().count());
+            assert_eq!(len, multi.leaf_hashes().count());
+        }
+    }
+
+    #[test]
+    fn test_multiproof_padded_leaf() {
+        let tree = MerkleTree::new(&VALUES[..5], &SHA256);
+        let multi = tree.build_multiproof(&[4, 4]).unwrap();
+
+        assert_eq!(&[4], multi.indices());
+        assert_eq!(1, multi.hashes().count()); // Duplicates on the right edge are not sent
+    }
+
+    #[test]
+    fn test_multiproof_deduplicates_shared_nodes() {
+        let tree = MerkleTree::new(&VALUES, &SHA256);
+        let multi = tree.build_multiproof(&[0, 2, 3, 7]).unwrap();
+        let separate: usize = [0, 2, 3, 7].iter()
+            .map(|&i| tree.build_proof_by_index(i).unwrap().siblings().count())
+            .sum();
+
+        assert_eq!(4, multi.hashes().count());
+        assert!(multi.hashes().count() < separate);
+    }
+
+    fn flip_first_byte(proof: &MultiProof, in_leaves: bool) -> MultiProof {
+        let mut leaves: Vec<u8> = proof.leaf_hashes().flat_map(|h| h.to_vec()).collect();
+        let mut hashes: Vec<u8> = proof.hashes().flat_map(|h| h.to_vec()).collect();
+        if in_leaves { leaves[0] ^= 1 } else { hashes[0] ^= 1 }
+        MultiProof::new(proof.leaf_count(), proof.indices().to_vec(), leaves, hashes, proof.digest_len())
+    }
+
+    #[test]
+    fn test_multiproof_round_trip() {
+        let sets: [&[usize]; 6] = [&[0], &[0, 1], &[1, 2], &[0, 4], &[2, 3, 4], &[0, 5, 8]];
+        for len in 1..VALUES.len() + 1 {
+            let tree = MerkleTree::new(&VALUES[..len], &SHA256);
+            for set in sets.iter() {
+                let indices: Vec<usize> = set.iter().cloned().filter(|&i| i < len).collect();
+                if indices.is_empty() {
+                    continue;
+                }
+                let multi = tree.build_multiproof(&indices).unwrap();
+                assert!(tree.validate_multiproof(&multi), "len {} indices {:?}", len, indices);
+                assert!(vmt::verify_multiproof(tree.get_root(), &multi, &SHA256));
+
+                assert!(!tree.validate_multiproof(&flip_first_byte(&multi, true)));
+                if multi.hashes().count() > 0 {
+                    assert!(!tree.validate_multiproof(&flip_first_byte(&multi, false)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_multiproof_inconsistent() {
+        let tree = MerkleTree::new(&VALUES[..7], &SHA256);
+        let multi = tree.build_multiproof(&[1, 4]).unwrap();
+        let leaves: Vec<u8> = multi.leaf_hashes().flat_map(|h| h.to_vec()).collect();
+        let hashes: Vec<u8> = multi.hashes().flat_map(|h| h.to_vec()).collect();
+        let with = |count, indices: Vec<usize>, hashes: Vec<u8>| MultiProof::new(count, indices, leaves.clone(), hashes, 32);
+
+        assert!(tree.validate_multiproof(&with(7, vec![1, 4], hashes.clone())));
+        assert!(!tree.validate_multiproof(&with(8, vec![1, 4], hashes.clone())));
+        assert!(!tree.validate_multiproof(&with(7, vec![4, 1], hashes.clone())));
+        assert!(!tree.validate_multiproof(&with(7, vec![1, 7], hashes.clone())));
+        assert!(!tree.validate_multiproof(&with(7, vec![1], hashes.clone())));
+        assert!(!tree.validate_multiproof(&with(7, vec![1, 4], hashes[32..].to_vec())));
+        let mut extra = hashes.clone();
+        extra.extend_from_slice(&hashes[..32]);
+        assert!(!tree.validate_multiproof(&with(7, vec![1, 4], extra)));
+        assert!(!tree.validate_multiproof(&with(7, vec![1, 4], hashes[1..].to_vec())));
+    }
+
+    #[test]
+    fn test_multiproof_bad_indices() {
+        let tree = MerkleTree::new(&VALUES[..3], &SHA256);
+        let values: [&str; 0] = [];
+        let empty = MerkleTree::new(&values, &SHA256);
+
+        assert_eq!(None, tree.build_multiproof(&[]));
+        assert_eq!(None, tree.build_multiproof(&[0, 3]));
+        assert_eq!(None, empty.build_multiproof(&[0]));
+    }
+}
+
+mod test_consistency {
+    use ring::digest::SHA256;
+
+    use vmt::MerkleTree;
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_consistency_all_pairs() {
+        let values = values(17);
+        let trees: Vec<MerkleTree> = (0..values.len() + 1).map(|n| MerkleTree::new(&values[..n], &SHA256)).collect();
+        for new in 1..trees.len() {
+            for old in 1..new + 1 {
+                let proof = trees[new].build_consistency_proof(old).unwrap();
+                assert!(vmt::verify_consistency(trees[old].get_root(), old, trees[new].get_root(), new, &proof, &SHA256),
+                        "{} -> {}", old, new);
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistency_appended_in_steps() {
+        let mut values: Vec<String> = vec![];
+        let mut history: Vec<(usize, Vec<u8>)> = vec![];
+        for step in [3, 1, 4, 1, 5, 9].iter() {
+            for _ in 0..*step {
+                let next = format!("record {}", values.len());
+                values.push(next);
+            }
+            let tree = MerkleTree::new(&values, &SHA256);
+            for &(count, ref root) in &history {
+                let proof = tree.build_consistency_proof(count).unwrap();
+                assert!(vmt::verify_consistency(root, count, tree.get_root(), values.len(), &proof, &SHA256));
+            }
+            history.push((values.len(), tree.get_root().to_vec()));
+        }
+    }
+
+    #[test]
+    fn test_consistency_rejects_tampering() {
+        let values = values(11);
+        let old = MerkleTree::new(&values[..6], &SHA256);
+        let new = MerkleTree::new(&values, &SHA256);
+        let proof = new.build_consistency_proof(6).unwrap();
+        let other = MerkleTree::new(&values[1..7], &SHA256);
+
+        assert!(vmt::verify_consistency(old.get_root(), 6, new.get_root(), 11, &proof, &SHA256));
+        assert!(!vmt::verify_consistency(other.get_root(), 6, new.get_root(), 11, &proof, &SHA256));
+        assert!(!vmt::verify_consistency(old.get_root(), 5, new.get_root(), 11, &proof, &SHA256));
+        assert!(!vmt::verify_consistency(old.get_root(), 6, new.get_root(), 17, &proof, &SHA256));
+        assert!(!vmt::verify_consistency(old.get_root(), 6, new.get_root(), 11, &proof[32..], &SHA256));
+        assert!(!vmt::verify_consistency(old.get_root(), 6, new.get_root(), 11, &proof[..proof.len() - 1], &SHA256));
+        for i in 0..proof.len() / 32 {
+            let mut tampered = proof.clone();
+            tampered[i * 32] ^= 1;
+            assert!(!vmt::verify_consistency(old.get_root(), 6, new.get_root(), 11, &tampered, &SHA256));
+        }
+    }
+
+    #[test]
+    fn test_consistency_bad_counts() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+
+        assert_eq!(None, tree.build_consistency_proof(0));
+        assert_eq!(None, tree.build_consistency_proof(4));
+        assert!(!vmt::verify_consistency(&[], 0, tree.get_root(), 3, &[], &SHA256));
+    }
+}
+
+mod test_rfc6962 {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, Proof, ValidationError};
+
+    use super::helpers::values;
+
+    // Leafs and roots of the certificate transparency reference test vectors
+    fn leafs() -> Vec<Vec<u8>> {
+        vec![vec![], vec![0x00], vec![0x10], vec![0x20, 0x21], vec![0x30, 0x31],
+             vec![0x40, 0x41, 0x42, 0x43], (0x50..0x58).collect(), (0x60..0x70).collect()]
+    }
+
+    const ROOTS: [&str; 8] = [
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+        "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+        "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+        "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+        "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+        "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+        "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+    ];
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_rfc6962_roots() {
+        let leafs = leafs();
+        for n in 1..leafs.len() + 1 {
+            let tree = MerkleTree::new_rfc6962(&leafs[..n], &SHA256);
+            assert_eq!(ROOTS[n - 1], hex(tree.get_root()), "{} leafs", n);
+        }
+    }
+
+    #[test]
+    fn test_rfc6962_single_leaf() {
+        let tree = MerkleTree::new_rfc6962(&[b"only"], &SHA256);
+        assert_eq!(1, tree.height());
+        assert_eq!(1, tree.nodes_count());
+        let proof = tree.build_proof_by_index(0).unwrap();
+        assert_eq!(1, proof.len());
+        assert!(tree.validate(&proof));
+    }
+
+    #[test]
+    fn test_rfc6962_proofs() {
+        let leafs = leafs();
+        for n in 1..leafs.len() + 1 {
+            let tree = MerkleTree::new_rfc6962(&leafs[..n], &SHA256);
+            for (i, leaf) in leafs[..n].iter().enumerate() {
+                let proof = tree.build_proof_by_index(i).unwrap();
+                assert!(tree.validate(&proof), "leaf {} of {}", i, n);
+                assert!(tree.validate_value(leaf, &proof), "leaf {} of {}", i, n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rfc6962_proof_without_positions() {
+        let tree = MerkleTree::new_rfc6962(&leafs(), &SHA256);
+        let proof = tree.build_proof_by_index(2).unwrap();
+        let unpositioned = Proof::from_bytes(proof.as_bytes(), 32).unwrap();
+        assert_eq!(Err(ValidationError::MissingPositions), tree.try_validate(&unpositioned));
+    }
+
+    #[test]
+    fn test_rfc6962_differs_from_default() {
+        let leafs = leafs();
+        assert!(MerkleTree::new(&leafs, &SHA256).get_root() != MerkleTree::new_rfc6962(&leafs, &SHA256).get_root());
+    }
+
+    #[test]
+    fn test_rfc6962_multiproof() {
+        let values = values(11);
+        let tree = MerkleTree::new_rfc6962(&values, &SHA256);
+        for indices in [vec![0], vec![10], vec![1, 2, 9], vec![0, 5, 10], (0..11).collect()].iter() {
+            let proof = tree.build_multiproof(indices).unwrap();
+            assert!(tree.validate_multiproof(&proof), "{:?}", indices);
+        }
+    }
+
+    #[test]
+    fn test_rfc6962_consistency() {
+        let values = values(13);
+        let trees: Vec<MerkleTree> = (0..values.len() + 1).map(|n| MerkleTree::new_rfc6962(&values[..n], &SHA256)).collect();
+        for new in 1..trees.len() {
+            for old in 1..new + 1 {
+                let proof = trees[new].build_consistency_proof(old).unwrap();
+                assert!(trees[new].validate_consistency(trees[old].get_root(), old, &proof), "{} -> {}", old, new);
+            }
+        }
+    }
+}
+
+mod test_bitcoin {
+    use vmt::{Error, MerkleTree};
+
+    // Transactions of mainnet block 100000, hex as shown by block explorers
+    const TXIDS: [&str; 4] = [
+        "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+        "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+        "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+        "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+    ];
+    const ROOT: &str = "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766";
+
+    // Explorer hex to internal byte order
+    fn txid(hex: &str) -> Vec<u8> {
+        let mut bytes: Vec<u8> = (0..hex.len() / 2).map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap()).collect();
+        bytes.reverse();
+        bytes
+    }
+
+    fn display(bytes: &[u8]) -> String {
+        bytes.iter().rev().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_bitcoin_block_root() {
+        let txids: Vec<Vec<u8>> = TXIDS.iter().map(|h| txid(h)).collect();
+        let tree = MerkleTree::new_bitcoin(&txids).unwrap();
+        assert_eq!(ROOT, display(tree.get_root()));
+    }
+
+    #[test]
+    fn test_bitcoin_odd_count() {
+        let txids: Vec<Vec<u8>> = TXIDS[..3].iter().map(|h| txid(h)).collect();
+        let tree = MerkleTree::new_bitcoin(&txids).unwrap();
+        assert_eq!("fa435470825de273081dcc706b25514c936fa6dc80ab965ce6970d68ddd0b553", display(tree.get_root()));
+    }
+
+    #[test]
+    fn test_bitcoin_single_transaction() {
+        let txids = [txid(TXIDS[0])];
+        let tree = MerkleTree::new_bitcoin(&txids).unwrap();
+        assert_eq!(&txids[0][..], tree.get_root()); // Coinbase txid is the root
+        assert!(tree.validate(&tree.build_proof_by_index(0).unwrap()));
+    }
+
+    #[test]
+    fn test_bitcoin_proofs() {
+        let txids: Vec<Vec<u8>> = TXIDS.iter().map(|h| txid(h)).collect();
+        for n in 1..txids.len() + 1 {
+            let tree = MerkleTree::new_bitcoin(&txids[..n]).unwrap();
+            for t in &txids[..n] {
+                let proof = tree.build_proof_by_hash(t).unwrap();
+                assert!(proof.positions().is_some());
+                assert!(tree.validate(&proof));
+            }
+            let proof = tree.build_multiproof(&(0..n).collect::<Vec<usize>>()).unwrap();
+            assert!(tree.validate_multiproof(&proof));
+        }
+    }
+
+    #[test]
+    fn test_bitcoin_bad_txid() {
+        match MerkleTree::new_bitcoin(&[vec![0u8; 20]]) {
+            Err(Error::BadHashLength { index: 0, len: 20, expected: 32 }) => {}
+            _ => panic!("short txid accepted"),
+        }
+    }
+}
+
+#[cfg(feature = "keccak")]
+mod test_keccak {
+    use tiny_keccak::{Hasher, Keccak};
+
+    use vmt::{Keccak256Hasher, MerkleTree};
+
+    fn keccak256(parts: &[&[u8]]) -> [u8; 32] {
+        let mut keccak = Keccak::v256();
+        for part in parts {
+            keccak.update(part);
+        }
+        let mut result = [0; 32];
+        keccak.finalize(&mut result);
+        result
+    }
+
+    // Reimplementation of OpenZeppelin MerkleProof.verify
+    fn oz_verify(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+        let computed = proof.iter().fold(leaf, |hash, sibling| {
+            if hash < *sibling { keccak256(&[&hash, sibling]) } else { keccak256(&[sibling, &hash]) }
+        });
+        computed == root
+    }
+
+    fn root(tree: &MerkleTree<Keccak256Hasher>) -> [u8; 32] {
+        let mut root = [0; 32];
+        root.copy_from_slice(tree.get_root());
+        root
+    }
+
+    #[test]
+    fn test_keccak_openzeppelin_proofs() {
+        let values: Vec<String> = (0..9).map(|i| format!("account {}", i)).collect();
+        for n in 1..values.len() + 1 {
+            let tree = MerkleTree::new_keccak256(&values[..n]);
+            for (i, value) in values[..n].iter().enumerate() {
+                let proof = tree.build_proof_by_index(i).unwrap();
+                assert!(tree.validate(&proof));
+                let leaf = keccak256(&[value.as_bytes()]);
+                assert!(oz_verify(&proof.to_openzeppelin().unwrap(), root(&tree), leaf), "leaf {} of {}", i, n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_keccak_leaf_hashes() {
+        // StandardMerkleTree leafs are hashed twice
+        let leafs: Vec<[u8; 32]> = (0..5u8).map(|i| keccak256(&[&keccak256(&[&[i]])])).collect();
+        let tree = MerkleTree::from_keccak256_leaf_hashes(&leafs).unwrap();
+        for (i, leaf) in leafs.iter().enumerate() {
+            let proof = tree.build_proof_by_hash(leaf).unwrap().to_openzeppelin().unwrap();
+            assert!(oz_verify(&proof, root(&tree), *leaf), "leaf {}", i);
+            assert!(!oz_verify(&proof, root(&tree), keccak256(&[leaf])));
+        }
+    }
+}
+
+mod test_hasher {
+    use vmt::MerkleTree;
+
+    use super::helpers::{Fnv, values};
+
+    #[test]
+    fn test_custom_hasher_proofs() {
+        let values = values(7);
+        let tree = MerkleTree::new_with_hasher(&values, Fnv, true);
+        assert_eq!(8, tree.get_root().len());
+        assert_eq!(4, tree.height());
+        for value in &values {
+            let proof = tree.build_proof(value).unwrap();
+            assert_eq!(8, proof.digest_len());
+            assert!(tree.validate_value(value, &proof));
+        }
+        let proof = tree.build_multiproof(&[1, 4, 6]).unwrap();
+        assert!(tree.validate_multiproof(&proof));
+    }
+
+    #[test]
+    fn test_custom_hasher_rfc6962() {
+        let values = values(5);
+        let tree = MerkleTree::new_rfc6962_with_hasher(&values, Fnv);
+        let old = MerkleTree::new_rfc6962_with_hasher(&values[..3], Fnv);
+        for i in 0..values.len() {
+            assert!(tree.validate(&tree.build_proof_by_index(i).unwrap()));
+        }
+        let proof = tree.build_consistency_proof(3).unwrap();
+        assert!(tree.validate_consistency(old.get_root(), 3, &proof));
+    }
+}
+
+#[cfg(feature = "serde")]
+mod test_serde {
+    use ring::digest::{SHA256, SHA384};
+
+    use vmt::MerkleTree;
+
+    use super::helpers::values;
+
+    fn assert_same(tree: &MerkleTree, copy: &MerkleTree) {
+        assert_eq!(tree.get_root(), copy.get_root());
+        assert_eq!(tree.height(), copy.height());
+        assert_eq!(tree.leafs_count(), copy.leafs_count());
+        for i in 0..tree.leafs_count() {
+            let proof = tree.build_proof_by_index(i).unwrap();
+            assert_eq!(proof, copy.build_proof_by_index(i).unwrap());
+            assert!(copy.validate(&proof));
+        }
+    }
+
+    #[test]
+    fn test_serde_bincode_round_trip() {
+        let tree = MerkleTree::new_with_map(&values(11), &SHA384);
+        let bytes = bincode::serialize(&tree).unwrap();
+        let copy: MerkleTree = bincode::deserialize(&bytes).unwrap();
+        assert_same(&tree, &copy);
+        assert!(copy.contains(&"value 7"));
+    }
+
+    #[test]
+    fn test_serde_json_round_trip() {
+        for tree in [MerkleTree::new(&values(11), &SHA256), MerkleTree::new_rfc6962(&values(11), &SHA256)].iter() {
+            let json = serde_json::to_string(tree).unwrap();
+            let copy: MerkleTree = serde_json::from_str(&json).unwrap();
+            assert_same(tree, &copy);
+        }
+        let empty: Vec<String> = vec![];
+        let json = serde_json::to_string(&MerkleTree::new(&empty, &SHA256)).unwrap();
+        assert!(serde_json::from_str::<MerkleTree>(&json).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_serde_corrupted_length() {
+        let tree = MerkleTree::new(&values(11), &SHA256);
+        let mut json = serde_json::to_value(&tree).unwrap();
+        json["array"].as_array_mut().unwrap().pop();
+        let err = serde_json::from_value::<MerkleTree>(json).err().unwrap();
+        assert!(err.to_string().contains("node array has"), "{}", err);
+
+        let mut json = serde_json::to_value(&tree).unwrap();
+        json["items_count"] = serde_json::Value::from(13);
+        assert!(serde_json::from_value::<MerkleTree>(json).is_err());
+
+        let mut json = serde_json::to_value(&tree).unwrap();
+        json["height"] = serde_json::Value::from(3);
+        assert!(serde_json::from_value::<MerkleTree>(json).is_err());
+    }
+
+    #[test]
+    fn test_serde_unknown_algorithm() {
+        let tree = MerkleTree::new(&values(11), &SHA256);
+        let mut json = serde_json::to_value(&tree).unwrap();
+        assert_eq!("sha256", json["algorithm"]);
+        json["algorithm"] = serde_json::Value::from("md5");
+        let err = serde_json::from_value::<MerkleTree>(json).err().unwrap();
+        assert!(err.to_string().contains("unknown algorithm 'md5'"), "{}", err);
+    }
+}
+
+mod test_binary {
+    use ring::digest::SHA256;
+
+    use vmt::{LoadError, MerkleTree};
+
+    use super::helpers::values;
+
+    fn stored() -> (MerkleTree, Vec<u8>) {
+        let values = values(13);
+        let tree = MerkleTree::new_with_map(&values, &SHA256);
+        let mut bytes = vec![];
+        tree.write_to(&mut bytes).unwrap();
+        (tree, bytes)
+    }
+
+    fn load(bytes: &[u8], check_root: bool) -> Result<MerkleTree, LoadError> {
+        MerkleTree::read_from(bytes, check_root)
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let (tree, bytes) = stored();
+        let copy = load(&bytes, true).unwrap();
+        assert_eq!(tree.get_root(), copy.get_root());
+        assert_eq!(tree.height(), copy.height());
+        for i in 0..tree.leafs_count() {
+            assert_eq!(tree.build_proof_by_index(i), copy.build_proof_by_index(i));
+        }
+        assert_eq!(Some(12), copy.index_of(&"value 12"));
+
+        let rfc = MerkleTree::new_rfc6962(&["a", "b", "c"], &SHA256);
+        let mut bytes = vec![];
+        rfc.write_to(&mut bytes).unwrap();
+        assert_eq!(rfc.get_root(), load(&bytes, true).unwrap().get_root());
+    }
+
+    #[test]
+    fn test_binary_truncated() {
+        let (_, bytes) = stored();
+        for &len in &[0, 3, 6, 12, 20, 40, bytes.len() - 1] {
+            assert_eq!(LoadError::Truncated, load(&bytes[..len], false).err().unwrap(), "{} bytes", len);
+        }
+    }
+
+    #[test]
+    fn test_binary_bad_header() {
+        let (_, bytes) = stored();
+        let mut copy = bytes.clone();
+        copy[0] = b'X';
+        assert_eq!(LoadError::BadMagic, load(&copy, false).err().unwrap());
+
+        let mut copy = bytes.clone();
+        copy[4] = 9;
+        assert_eq!(LoadError::UnsupportedVersion(9), load(&copy, false).err().unwrap());
+
+        let mut copy = bytes.clone();
+        copy[6..12].copy_from_slice(b"sha999");
+        assert_eq!(LoadError::UnknownAlgorithm("sha999".to_string()), load(&copy, false).err().unwrap());
+
+        let mut copy = bytes.clone();
+        copy[14] += 4; // Leaf count
+        match load(&copy, false) {
+            Err(LoadError::BadLength { .. }) => {}
+            other => panic!("{:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_binary_corrupted_node() {
+        let (_, mut bytes) = stored();
+        let last = bytes.len() - 40; // Inside the root
+        bytes[last] ^= 1;
+        assert!(load(&bytes, false).is_ok());
+        assert_eq!(LoadError::RootMismatch, load(&bytes, true).err().unwrap());
+    }
+}
+
+#[cfg(feature = "serde")]
+mod test_json {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, NamedHasher, Proof, ProofJsonError};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_json_round_trip() {
+        for tree in [MerkleTree::new(&values(6), &SHA256), MerkleTree::new_rfc6962(&values(6), &SHA256)].iter() {
+            let name = tree.hasher().name();
+            for i in 0..tree.leafs_count() {
+                let proof = tree.build_proof_by_index(i).unwrap();
+                let json = proof.to_json(name);
+                let parsed = Proof::from_json(&json, name).unwrap();
+                assert_eq!(proof, parsed);
+                assert!(tree.validate(&parsed));
+            }
+        }
+    }
+
+    #[test]
+    fn test_json_layout() {
+        let tree = MerkleTree::new(&values(6), &SHA256);
+        let proof = tree.build_proof_by_index(0).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&proof.to_json("sha256")).unwrap();
+        assert_eq!("sha256", json["algorithm"]);
+        assert_eq!(64, json["leaf"].as_str().unwrap().len());
+        assert_eq!(proof.len() - 1, json["path"].as_array().unwrap().len());
+        assert!(json.get("positions").is_none());
+    }
+
+    #[test]
+    fn test_json_rejects_bad_hashes() {
+        let tree = MerkleTree::new(&values(6), &SHA256);
+        let json = tree.build_proof_by_index(0).unwrap().to_json("sha256");
+        let mut object: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        object["path"][1] = serde_json::Value::from("abcd");
+        assert_eq!(Err(ProofJsonError::BadPathHash { index: 1 }), Proof::from_json(&object.to_string(), "sha256"));
+        object["path"][1] = serde_json::Value::from("zz".repeat(32));
+        assert_eq!(Err(ProofJsonError::BadPathHash { index: 1 }), Proof::from_json(&object.to_string(), "sha256"));
+        object["leaf"] = serde_json::Value::from("x");
+        assert_eq!(Err(ProofJsonError::BadLeaf), Proof::from_json(&object.to_string(), "sha256"));
+
+        match Proof::from_json(&json, "sha512") {
+            Err(ProofJsonError::AlgorithmMismatch { .. }) => {}
+            other => panic!("{:?}", other),
+        }
+        match Proof::from_json("[1, 2]", "sha256") {
+            Err(ProofJsonError::Syntax(_)) => {}
+            other => panic!("{:?}", other),
+        }
+    }
+}
+
+mod test_append {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, RingHasher};
+
+    use super::helpers::{stored, values, Counting};
+
+    fn assert_same(tree: &MerkleTree, fresh: &MerkleTree) {
+        assert_eq!(fresh.get_root(), tree.get_root());
+        assert_eq!(fresh.height(), tree.height());
+        assert_eq!(stored(fresh), stored(tree)); // Whole node array
+        for i in 0..fresh.leafs_count() {
+            assert_eq!(fresh.build_proof_by_index(i), tree.build_proof_by_index(i));
+        }
+    }
+
+    #[test]
+    fn test_push_matches_fresh_tree() {
+        let values = values(20);
+        let empty: Vec<String> = vec![];
+        let mut tree = MerkleTree::new_with_map(&empty, &SHA256);
+        let mut rfc = MerkleTree::new_rfc6962(&empty, &SHA256);
+        for (n, value) in values.iter().enumerate() {
+            tree.push(value);
+            rfc.push(value);
+            assert_same(&tree, &MerkleTree::new_with_map(&values[..n + 1], &SHA256));
+            assert_same(&rfc, &MerkleTree::new_rfc6962(&values[..n + 1], &SHA256));
+            assert_eq!(Some(n), tree.index_of(value));
+        }
+    }
+
+    #[test]
+    fn test_extend_matches_fresh_tree() {
+        let values = values(40);
+        for &(from, to) in &[(0, 1), (1, 2), (3, 4), (4, 5), (7, 9), (8, 17), (5, 40), (16, 16)] {
+            let mut tree = MerkleTree::new_with_map(&values[..from], &SHA256);
+            tree.extend(&values[from..to]);
+            assert_same(&tree, &MerkleTree::new_with_map(&values[..to], &SHA256));
+            assert!(tree.contains(&values[to - 1]));
+
+            let mut rfc = MerkleTree::new_rfc6962(&values[..from], &SHA256);
+            rfc.extend(values[from..to].iter());
+            assert_same(&rfc, &MerkleTree::new_rfc6962(&values[..to], &SHA256));
+        }
+    }
+
+    // Every push hashes only the right edge and the node array is not copied to a new
+    // allocation each time
+    #[test]
+    fn test_push_cost() {
+        let pairs = Arc::new(AtomicUsize::new(0));
+        let hasher = Counting { inner: RingHasher::new(&SHA256), pairs: pairs.clone() };
+        let mut tree = MerkleTree::new_with_hasher(&Vec::<String>::new(), hasher, false);
+        let mut reallocations = 0;
+        for value in values(1000) {
+            let (before, capacity) = (pairs.load(Ordering::SeqCst), tree.capacity());
+            tree.push(&value);
+            assert!(pairs.load(Ordering::SeqCst) - before < tree.height());
+            reallocations += (tree.capacity() != capacity) as usize;
+        }
+        assert!(reallocations < 40);
+        assert_eq!(MerkleTree::new(&values(1000), &SHA256).get_root(), tree.get_root());
+        let capacity = tree.capacity();
+        tree.truncate(500);
+        tree.push(&"value 500");
+        assert_eq!(capacity, tree.capacity());
+    }
+
+    #[test]
+    fn test_truncate_matches_fresh_tree() {
+        let values = values(17);
+        for from in 1..values.len() + 1 {
+            for to in 0..from + 1 {
+                let mut tree = MerkleTree::new_with_map(&values[..from], &SHA256);
+                tree.truncate(to);
+                let fresh = MerkleTree::new_with_map(&values[..to], &SHA256);
+                assert_same(&tree, &fresh);
+                assert_eq!(fresh.nodes_count(), tree.nodes_count());
+                assert_eq!(fresh.data_size(), tree.data_size());
+                assert_eq!(to > 0, tree.contains(&values[0]));
+                assert!(!tree.contains(&values[from - 1]) || from == to);
+
+                let mut rfc = MerkleTree::new_rfc6962(&values[..from], &SHA256);
+                rfc.truncate(to);
+                assert_same(&rfc, &MerkleTree::new_rfc6962(&values[..to], &SHA256));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pop() {
+        let values = ["a", "b", "c", "d", "e"];
+        let mut tree = MerkleTree::new_with_map(&values, &SHA256);
+        assert_eq!(4, tree.height());
+        let last = tree.pop().unwrap();
+        assert_eq!(vmt::get_hash(b"e", &SHA256).as_ref(), &last[..]);
+        assert_eq!(3, tree.height()); // Padding level is gone
+        assert_same(&tree, &MerkleTree::new_with_map(&values[..4], &SHA256));
+        for _ in 0..4 {
+            assert!(tree.pop().is_some());
+        }
+        assert!(tree.is_empty());
+        assert_eq!(None, tree.pop());
+        tree.push(&"a");
+        assert_same(&tree, &MerkleTree::new_with_map(&values[..1], &SHA256));
+    }
+
+    #[test]
+    fn test_truncate_keeps_earlier_duplicate() {
+        let mut tree = MerkleTree::new_with_map(&["a", "b", "a"], &SHA256);
+        tree.truncate(2);
+        assert_eq!(Some(0), tree.index_of(&"a"));
+        let mut tree = MerkleTree::new_with_map(&["b", "a", "a"], &SHA256);
+        tree.truncate(1);
+        assert!(!tree.contains(&"a"));
+    }
+
+    #[test]
+    fn test_update_leaf_matches_fresh_tree() {
+        let values = values(9);
+        for n in 1..values.len() + 1 {
+            for index in 0..n {
+                let mut changed = values[..n].to_vec();
+                changed[index] = format!("changed {}", index);
+
+                let mut tree = MerkleTree::new_with_map(&values[..n], &SHA256);
+                tree.update_leaf(index, &changed[index]).unwrap();
+                assert_same(&tree, &MerkleTree::new_with_map(&changed, &SHA256));
+                assert_eq!(Some(index), tree.index_of(&changed[index]));
+                assert!(!tree.contains(&values[index]));
+
+                let mut rfc = MerkleTree::new_rfc6962(&values[..n], &SHA256);
+                rfc.update_leaf(index, &changed[index]).unwrap();
+                assert_same(&rfc, &MerkleTree::new_rfc6962(&changed, &SHA256));
+            }
+        }
+    }
+
+    #[test]
+    fn test_update_leaf_keeps_duplicates_in_map() {
+        let mut tree = MerkleTree::new_with_map(&["a", "b", "a", "c"], &SHA256);
+        tree.update_leaf(0, &"c").unwrap();
+        assert_eq!(Some(2), tree.index_of(&"a"));
+        assert_eq!(Some(0), tree.index_of(&"c"));
+        assert_same(&tree, &MerkleTree::new_with_map(&["c", "b", "a", "c"], &SHA256));
+    }
+
+    #[test]
+    fn test_update_leaf_out_of_range() {
+        let mut tree = MerkleTree::new(&["a", "b", "c"], &SHA256);
+        assert_eq!(Err(vmt::Error::IndexOutOfRange { index: 3, len: 3 }), tree.update_leaf(3, &"d"));
+    }
+
+    #[test]
+    fn test_push_duplicate_value() {
+        let mut tree = MerkleTree::new_with_map(&["a", "b", "c"], &SHA256);
+        tree.push(&"a");
+        assert_eq!(Some(0), tree.index_of(&"a"));
+        assert_eq!(vec![0, 3], tree.indices_of(&"a"));
+        assert_same(&tree, &MerkleTree::new_with_map(&["a", "b", "c", "a"], &SHA256));
+    }
+}
+
+mod test_builder {
+    use std::collections::hash_map::RandomState;
+
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{Error, MerkleTree, MerkleTreeBuilder, PaddingStrategy, RingHasher};
+
+    use super::helpers::{stored, values};
+
+    #[test]
+    fn test_builder_matches_constructors() {
+        let values = values(7);
+        let built = MerkleTreeBuilder::new().algorithm(&SHA512).build(&values).unwrap();
+        assert_eq!(stored(&MerkleTree::new(&values, &SHA512)), stored(&built));
+
+        let built = MerkleTreeBuilder::new().algorithm(&SHA256).use_map(true).build(&values).unwrap();
+        assert_eq!(stored(&MerkleTree::new_with_map(&values, &SHA256)), stored(&built));
+        assert_eq!(Some(3), built.index_of(&"value 3"));
+
+        let hashes: Vec<Vec<u8>> = values.iter().map(|v| vmt::get_hash(v.as_ref(), &SHA256).as_ref().to_vec()).collect();
+        let built = MerkleTreeBuilder::with_hasher(RingHasher::new(&SHA256)).build_from_leaf_hashes(&hashes).unwrap();
+        assert_eq!(stored(&MerkleTree::from_leaf_hashes(&hashes, &SHA256).unwrap()), stored(&built));
+    }
+
+    #[test]
+    fn test_builder_errors() {
+        let values = ["a", "b"];
+        assert_eq!(Err(Error::MissingHasher), MerkleTreeBuilder::new().use_map(true).build(&values).map(|_| ()));
+        match MerkleTreeBuilder::new().algorithm(&SHA256).build_from_leaf_hashes(&[[0u8; 16]]) {
+            Err(Error::BadHashLength { index: 0, len: 16, expected: 32 }) => {}
+            _ => panic!("short leaf hash accepted"),
+        }
+    }
+
+    #[test]
+    fn test_builder_rejects_promoted_single_leaf_padding() {
+        let built = MerkleTreeBuilder::new().algorithm(&SHA256).padding(PaddingStrategy::PromoteOdd).pad_single_leaf(true).build(&["a"]);
+        assert_eq!(Err(Error::IncompatibleOptions("PromoteOdd padding and pad_single_leaf")), built.map(|_| ()));
+        let promoted = MerkleTreeBuilder::new().algorithm(&SHA256).padding(PaddingStrategy::PromoteOdd).build(&["a"]).unwrap();
+        assert_eq!(1, promoted.height()); // The lone leaf is the root
+        assert_eq!(vmt::get_hash(b"a", &SHA256).as_ref(), promoted.get_root());
+        let padded = MerkleTreeBuilder::new().algorithm(&SHA256).pad_single_leaf(false).build(&["a"]).unwrap();
+        assert_eq!(promoted.get_root(), padded.get_root());
+    }
+
+    #[test]
+    fn test_builder_rejects_positions_with_sorted_pairs() {
+        let built = MerkleTreeBuilder::new().algorithm(&SHA256).proof_positions(true).build(&values(3));
+        assert_eq!(Err(Error::IncompatibleOptions("sorted pairs and proof positions")), built.map(|_| ()));
+        let tree = MerkleTreeBuilder::new().algorithm(&SHA256).proof_positions(false).build(&values(3)).unwrap();
+        assert!(tree.build_proof_by_index(0).unwrap().positions().is_none());
+    }
+
+    #[test]
+    fn test_builder_rejects_unsorted_pairs_without_positions() {
+        let built = MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false).proof_positions(false).build(&values(3));
+        assert_eq!(Err(Error::IncompatibleOptions("unsorted pairs and proofs without positions")), built.map(|_| ()));
+        let tree = MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false).proof_positions(true).build(&values(3)).unwrap();
+        assert!(tree.build_proof_by_index(0).unwrap().positions().is_some());
+    }
+
+    #[test]
+    fn test_builder_override_order() {
+        let values = values(9);
+        let dropped = MerkleTreeBuilder::new().algorithm(&SHA256).map_hasher(RandomState::new()).use_map(false).build(&values).unwrap();
+        assert!(!dropped.has_map());
+        let kept = MerkleTreeBuilder::new().algorithm(&SHA256).map_hasher(RandomState::new()).use_map(false).use_map(true)
+            .build(&values).unwrap();
+        assert!(kept.has_map());
+        assert_eq!(Some(4), kept.index_of(&"value 4"));
+        let replaced = MerkleTreeBuilder::new().algorithm(&SHA512).algorithm(&SHA256).build(&values).unwrap();
+        assert_eq!(stored(&MerkleTree::new(&values, &SHA256)), stored(&replaced));
+    }
+}
+
+mod test_positional {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, MerkleTreeBuilder, Position, Proof, ValidationError};
+
+    use super::helpers::values;
+
+    fn positional<T: AsRef<[u8]>>(values: &[T]) -> MerkleTree {
+        MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false).build(values).unwrap()
+    }
+
+    #[test]
+    fn test_positional_order_matters() {
+        assert!(positional(&["a", "b"]).get_root() != positional(&["b", "a"]).get_root());
+        assert_eq!(MerkleTree::new(&["a", "b"], &SHA256).get_root(), MerkleTree::new(&["b", "a"], &SHA256).get_root());
+        assert!(positional(&["a", "b"]).get_root() != MerkleTree::new(&["a", "b"], &SHA256).get_root());
+    }
+
+    #[test]
+    fn test_positional_proofs() {
+        let values = values(9);
+        for n in 1..values.len() + 1 {
+            let tree = positional(&values[..n]);
+            for (i, value) in values[..n].iter().enumerate() {
+                let proof = tree.build_proof(value).unwrap();
+                assert!(tree.validate(&proof), "leaf {} of {}", i, n);
+                // Directions spell the leaf index, lowest level first
+                let index = proof.positions().unwrap().iter().enumerate()
+                    .fold(0, |acc, (level, p)| if *p == Position::Left { acc | 1 << level } else { acc });
+                assert_eq!(i, index);
+            }
+        }
+    }
+
+    #[test]
+    fn test_positional_directions_are_checked() {
+        let values = ["a", "b", "c", "d"];
+        let tree = positional(&values);
+        let proof = tree.build_proof(&"b").unwrap();
+        let flipped: Vec<Position> = proof.positions().unwrap().iter()
+            .map(|p| if *p == Position::Left { Position::Right } else { Position::Left })
+            .collect();
+        let forged = Proof::with_positions(proof.as_bytes(), 32, flipped).unwrap();
+        assert!(!tree.validate(&forged));
+        assert!(!tree.validate_bytes(proof.as_bytes())); // Bytes alone carry no directions
+
+        let unpositioned = Proof::from_bytes(proof.as_bytes(), 32).unwrap();
+        assert_eq!(Err(ValidationError::MissingPositions), tree.try_validate(&unpositioned));
+    }
+}
+
+mod test_domain_separation {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, MerkleTreeBuilder, Proof, ValidationError};
+
+    use super::helpers::values;
+
+    const VALUES: [&str; 4] = ["a", "b", "c", "d"];
+
+    // Presents the first internal node as a leaf whose value is its children concatenated
+    fn forge(tree: &MerkleTree) -> (Vec<u8>, Proof) {
+        let real = tree.build_proof(&"c").unwrap();
+        let siblings: Vec<&[u8]> = real.siblings().collect();
+        let node = siblings[1]; // Parent of "a" and "b"
+        let (a, b) = (tree.build_proof(&"a").unwrap(), tree.build_proof(&"b").unwrap());
+        let (left, right) = if a.leaf_hash() < b.leaf_hash() { (&a, &b) } else { (&b, &a) };
+        let mut value = left.leaf_hash().to_vec();
+        value.extend_from_slice(right.leaf_hash());
+
+        let parent_cd = a.siblings().nth(1).unwrap().to_vec();
+        let mut bytes = node.to_vec();
+        bytes.extend_from_slice(&parent_cd);
+        (value, Proof::from_bytes(&bytes, 32).unwrap())
+    }
+
+    #[test]
+    fn test_internal_node_as_leaf_legacy() {
+        let tree = MerkleTree::new(&VALUES, &SHA256);
+        let (value, proof) = forge(&tree);
+        assert!(vmt::verify_value(tree.get_root(), &value, proof.as_bytes(), &SHA256)); // The attack works
+        assert_eq!(Err(ValidationError::HashCount { got: 2, expected: 3 }), tree.try_validate(&proof)); // Unless the height is known
+        assert!(!tree.validate_value(&value, &proof));
+    }
+
+    #[test]
+    fn test_internal_node_as_leaf_separated() {
+        let tree = MerkleTree::new_domain_separated(&VALUES, &SHA256);
+        let (value, proof) = forge(&tree);
+        assert_eq!(Err(ValidationError::HashCount { got: 2, expected: 3 }), tree.try_validate(&proof)); // A path from an internal node is short
+        assert!(!tree.validate_value(&value, &proof));
+        assert!(!tree.contains(&value));
+    }
+
+    #[test]
+    fn test_domain_separated_proofs() {
+        let values = values(9);
+        for n in 1..values.len() + 1 {
+            let tree = MerkleTreeBuilder::new().algorithm(&SHA256).domain_separation(true).build(&values[..n]).unwrap();
+            assert!(tree.get_root() != MerkleTree::new(&values[..n], &SHA256).get_root());
+            for value in &values[..n] {
+                assert!(tree.validate_value(value, &tree.build_proof(value).unwrap()));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+mod test_parallel {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{MerkleTree, MerkleTreeBuilder};
+
+    use super::helpers::{random_values, stored};
+
+    const SIZES: [usize; 12] = [0, 1, 2, 3, 5, 7, 13, 31, 64, 97, 257, 1009];
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        for &n in SIZES.iter() {
+            let values = random_values(n, n as u64);
+            let tree = MerkleTree::new_parallel(&values, &SHA512);
+            assert_eq!(stored(&MerkleTree::new(&values, &SHA512)), stored(&tree));
+        }
+    }
+
+    #[test]
+    fn test_parallel_matches_serial_schemes() {
+        for &n in SIZES.iter() {
+            let values = random_values(n, 7 * n as u64 + 1);
+            let builders = || vec![
+                MerkleTreeBuilder::new().algorithm(&SHA256).use_map(true),
+                MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false),
+                MerkleTreeBuilder::new().algorithm(&SHA256).domain_separation(true),
+            ];
+            for (serial, parallel) in builders().into_iter().zip(builders()) {
+                let tree = parallel.build_parallel(&values).unwrap();
+                assert_eq!(stored(&serial.build(&values).unwrap()), stored(&tree));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_map() {
+        let mut values = random_values(500, 3);
+        values.extend(random_values(100, 3)); // Duplicates keep their first index
+        let tree = MerkleTreeBuilder::new().algorithm(&SHA256).use_map(true).build_parallel(&values).unwrap();
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(values.iter().position(|v| v == value), tree.index_of(value));
+            assert!(tree.index_of(value).unwrap() <= i % 500);
+            assert!(tree.validate_value(value, &tree.build_proof(value).unwrap()));
+        }
+    }
+}
+
+mod test_threaded {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, MerkleTreeBuilder};
+
+    use super::helpers::{stored, values};
+
+    #[test]
+    fn test_threaded_matches_serial() {
+        let values: Vec<String> = (0..101).map(|i| format!("value {}", i).repeat(i % 7 + 1)).collect();
+        for &n in [0, 1, 2, 3, 7, 16, 37, 101].iter() {
+            let serial = stored(&MerkleTree::new(&values[..n], &SHA256));
+            for &threads in [None, Some(0), Some(1), Some(2), Some(3), Some(8), Some(200)].iter() {
+                assert_eq!(serial, stored(&MerkleTree::new_threaded(&values[..n], &SHA256, threads)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_threaded_builder() {
+        let values = values(29);
+        let serial = MerkleTreeBuilder::new().algorithm(&SHA256).use_map(true).domain_separation(true).build(&values).unwrap();
+        let tree = MerkleTreeBuilder::new().algorithm(&SHA256).use_map(true).domain_separation(true)
+            .build_threaded(&values, Some(4)).unwrap();
+        assert_eq!(stored(&serial), stored(&tree));
+        assert_eq!(Some(17), tree.index_of(&values[17]));
+    }
+}
+
+mod test_from_iter {
+    use std::collections::VecDeque;
+
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, MerkleTreeBuilder};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_from_slices_and_arrays() {
+        let values = values(11);
+        let tree = MerkleTree::new(&values, &SHA256);
+        assert_eq!(tree.get_root(), MerkleTree::new(&values[..], &SHA256).get_root());
+        let owned = vec!["one", "two", "three"];
+        assert_eq!(MerkleTree::new(&owned, &SHA256).get_root(), MerkleTree::new(&["one", "two", "three"], &SHA256).get_root());
+    }
+
+    #[test]
+    fn test_from_iterators() {
+        let values = values(11);
+        let tree = MerkleTree::new(&values, &SHA256);
+        assert_eq!(tree.get_root(), MerkleTree::new_from_iter(&values, &SHA256).get_root());
+        assert_eq!(tree.get_root(), MerkleTree::new_from_iter(values.iter().cloned().collect::<VecDeque<_>>(), &SHA256).get_root());
+
+        let mapped = MerkleTree::new_from_iter((0..11).map(|i| format!("value {}", i)), &SHA256);
+        assert_eq!(tree.get_root(), mapped.get_root());
+        assert_eq!(11, mapped.leafs_count());
+        let filtered = MerkleTree::new_from_iter((0..30).filter(|i| i % 3 == 0).map(|i| i.to_string()), &SHA256);
+        assert_eq!(10, filtered.leafs_count());
+        assert!(MerkleTree::new_from_iter(Vec::<String>::new(), &SHA256).is_empty());
+    }
+
+    #[test]
+    fn test_builder_from_iter() {
+        let values = values(11);
+        let tree = MerkleTreeBuilder::new().algorithm(&SHA256).use_map(true).build_from_iter(values.iter().rev()).unwrap();
+        let reversed: Vec<&String> = values.iter().rev().collect();
+        assert_eq!(MerkleTree::new(&reversed, &SHA256).get_root(), tree.get_root());
+        assert_eq!(Some(10), tree.index_of(&values[0]));
+    }
+
+    #[test]
+    fn test_streaming() {
+        let values = values(11);
+        for n in 0..values.len() {
+            let tree = MerkleTree::new(&values[..n], &SHA256);
+            let cursor = values[..n].iter().filter(|_| true); // No upper bound, lower bound is 0
+            let streamed = MerkleTree::from_iter_streaming(cursor, &SHA256, None);
+            assert_eq!(tree.get_root(), streamed.get_root());
+            assert_eq!(tree.height(), streamed.height());
+            assert_eq!(tree.nodes_count(), streamed.nodes_count());
+            for &hint in [Some(0), Some(n), Some(100)].iter() {
+                assert_eq!(tree.get_root(), MerkleTree::from_iter_streaming(&values[..n], &SHA256, hint).get_root());
+            }
+        }
+        let empty = MerkleTree::from_iter_streaming(Vec::<String>::new(), &SHA256, None);
+        assert!(empty.is_empty());
+        assert_eq!(0, empty.height());
+        assert!(empty.build_proof(&"value 0").is_none());
+    }
+}
+
+mod test_readers {
+    use std::io::{self, Cursor, Read};
+
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::MerkleTree;
+
+    // Hands out data in small pieces and fails once the limit is reached
+    struct Failing {
+        data: Vec<u8>,
+        limit: usize,
+    }
+
+    impl Read for Failing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.limit == 0 {
+                return Err(io::Error::other("disk is gone"));
+            }
+            let n = buf.len().min(self.data.len()).min(7);
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data.drain(..n);
+            self.limit -= 1;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_readers_match_values() {
+        let values: Vec<Vec<u8>> = (0..9usize).map(|i| (0..i * 40_000).map(|b| (b * i) as u8).collect()).collect();
+        for n in 0..values.len() {
+            let tree = MerkleTree::new(&values[..n], &SHA512);
+            let read = MerkleTree::from_readers(values[..n].iter().map(Cursor::new), &SHA512).unwrap();
+            assert_eq!(tree.get_root(), read.get_root());
+            assert_eq!(tree.height(), read.height());
+        }
+    }
+
+    #[test]
+    fn test_readers_error() {
+        let ok = Failing { data: b"some leaf".to_vec(), limit: 10 };
+        let tree = MerkleTree::from_readers(vec![ok], &SHA256).unwrap();
+        assert_eq!(MerkleTree::new(&["some leaf"], &SHA256).get_root(), tree.get_root());
+
+        let readers = vec![Failing { data: b"first".to_vec(), limit: 10 }, Failing { data: vec![1; 100], limit: 3 }];
+        let err = MerkleTree::from_readers(readers, &SHA256).err().unwrap();
+        assert_eq!(io::ErrorKind::Other, err.kind());
+    }
+}
+
+mod test_leaves {
+    use ring::digest::{digest, SHA256};
+
+    use vmt::MerkleTree;
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_leaves_are_value_hashes() {
+        let values = values(9);
+        for n in 0..values.len() + 1 {
+            let tree = MerkleTree::new(&values[..n], &SHA256);
+            let leaves: Vec<&[u8]> = tree.leaves().collect();
+            assert_eq!(n, leaves.len());
+            for (value, leaf) in values.iter().zip(leaves) {
+                assert_eq!(digest(&SHA256, value.as_bytes()).as_ref(), leaf);
+            }
+        }
+        assert_eq!(0, MerkleTree::new(&Vec::<String>::new(), &SHA256).leaves().count());
+    }
+
+    #[test]
+    fn test_leaves_after_update() {
+        let values = values(5);
+        let mut tree = MerkleTree::new(&values, &SHA256);
+        tree.push(&"value 5");
+        tree.update_leaf(1, &"one").unwrap();
+        let copy = MerkleTree::from_leaf_hashes(&tree.leaves().collect::<Vec<_>>(), &SHA256).unwrap();
+        assert_eq!(tree.get_root(), copy.get_root());
+        assert_eq!(digest(&SHA256, b"one").as_ref(), tree.leaves().nth(1).unwrap());
+    }
+
+    #[test]
+    fn test_get_leaf_hash() {
+        let values = values(8);
+        for n in 0..values.len() + 1 {
+            let tree = MerkleTree::new(&values[..n], &SHA256);
+            for (i, value) in values[..n].iter().enumerate() {
+                let hash = tree.get_leaf_hash(i).unwrap();
+                assert_eq!(digest(&SHA256, value.as_bytes()).as_ref(), hash);
+                assert_eq!(tree.build_proof(value), tree.build_proof_by_hash(hash));
+            }
+            assert_eq!(None, tree.get_leaf_hash(n)); // Padding slot for odd counts
+            assert_eq!(None, tree.get_leaf_hash(n + 1));
+        }
+    }
+}
+
+mod test_levels {
+    use ring::digest::SHA256;
+
+    use vmt::{get_pair_hash, MerkleTree, MerkleTreeBuilder};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_levels_rebuild() {
+        let values = values(23);
+        for n in 1..values.len() + 1 {
+            let tree = MerkleTree::new(&values[..n], &SHA256);
+            let levels: Vec<&[u8]> = tree.levels().collect();
+            assert_eq!(tree.height(), levels.len());
+            assert_eq!(tree.get_root(), levels[levels.len() - 1]);
+            assert_eq!(tree.leaves().collect::<Vec<_>>().concat(), levels[0]);
+            let mut level_len = n;
+            for i in 0..levels.len() - 1 {
+                assert_eq!(level_len * 32, levels[i].len());
+                let nodes: Vec<&[u8]> = levels[i].chunks(32).collect();
+                let parents: Vec<u8> = nodes.chunks(2)
+                    .flat_map(|pair| get_pair_hash(pair[0], pair[pair.len() - 1], &SHA256).as_ref().to_vec())
+                    .collect();
+                assert_eq!(&parents[..], levels[i + 1]);
+                level_len = level_len.div_ceil(2);
+            }
+            assert_eq!(Some(levels[1]), tree.level(1));
+            assert_eq!(None, tree.level(levels.len()));
+        }
+    }
+
+    #[test]
+    fn test_levels_promoted() {
+        let values = values(5);
+        let tree = MerkleTree::new_rfc6962(&values, &SHA256);
+        let lens: Vec<usize> = tree.levels().map(|l| l.len() / 32).collect();
+        assert_eq!(vec![5, 3, 2, 1], lens);
+        assert_eq!(&tree.level(0).unwrap()[128..], &tree.level(1).unwrap()[64..]); // Promoted leaf
+        let single = MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false).build(&["one"]).unwrap();
+        assert_eq!(2, single.levels().count());
+        assert_eq!(0, MerkleTree::new(&Vec::<String>::new(), &SHA256).levels().count());
+    }
+
+    #[test]
+    fn test_node() {
+        let values = values(19);
+        for &n in [1, 2, 3, 7, 8, 11, 19].iter() {
+            let tree = MerkleTree::new(&values[..n], &SHA256);
+            assert_eq!(Some(tree.get_root()), tree.node(tree.height() - 1, 0));
+            assert_eq!(None, tree.node(tree.height() - 1, 1));
+            assert_eq!(None, tree.node(tree.height(), 0));
+            for (i, leaf) in tree.leaves().enumerate() {
+                assert_eq!(Some(leaf), tree.node(0, i));
+            }
+            assert_eq!(None, tree.node(0, n)); // Padding duplicate is not a node of its own
+        }
+        let tree = MerkleTree::new(&values[..11], &SHA256);
+        let parent = get_pair_hash(tree.node(1, 4).unwrap(), tree.node(1, 5).unwrap(), &SHA256);
+        assert_eq!(Some(parent.as_ref()), tree.node(2, 2));
+    }
+}
+
+mod test_metadata {
+    use ring::digest::{SHA1, SHA256, SHA384, SHA512, SHA512_256};
+
+    use vmt::{MerkleHasher, MerkleTree};
+
+    #[test]
+    fn test_algorithm_metadata() {
+        let algorithms = [(&SHA1, "sha1", 20), (&SHA256, "sha256", 32), (&SHA384, "sha384", 48),
+            (&SHA512, "sha512", 64), (&SHA512_256, "sha512_256", 32)];
+        for &(algo, name, len) in algorithms.iter() {
+            let tree = MerkleTree::new(&["one", "two", "three"], algo);
+            assert!(tree.algorithm() == algo);
+            assert_eq!(name, tree.algorithm_name());
+            assert_eq!(len, tree.digest_len());
+            assert_eq!(len, tree.hasher().output_len());
+            assert_eq!(len, tree.get_root().len());
+            assert_eq!(len, tree.build_proof(&"two").unwrap().digest_len());
+        }
+        let empty = MerkleTree::new(&Vec::<String>::new(), &SHA384);
+        assert_eq!(48, empty.digest_len());
+        assert_eq!("sha384", empty.algorithm_name());
+    }
+}
+
+mod test_root {
+    use ring::digest::{SHA256, SHA512, SHA512_256};
+
+    use vmt::{MerkleTree, Root};
+
+    #[test]
+    fn test_root_equality() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        let root = tree.root();
+        assert_eq!(tree.get_root(), root.as_bytes());
+        assert_eq!(32, root.digest_len());
+        assert_eq!(root, MerkleTree::new(&["one", "two", "three"], &SHA256).root());
+        assert_eq!(root, Root::new(tree.get_root(), 32));
+    }
+
+    #[test]
+    fn test_root_inequality() {
+        let root = MerkleTree::new(&["one", "two", "three"], &SHA256).root();
+        assert!(root != MerkleTree::new(&["one", "two", "four"], &SHA256).root());
+        assert!(root != MerkleTree::new(&["one", "two", "three"], &SHA512_256).root()); // Same length
+        let empty = MerkleTree::new(&Vec::<String>::new(), &SHA256).root();
+        assert!(empty.is_empty());
+        assert!(root != empty);
+    }
+
+    #[test]
+    #[should_panic(expected = "different digest lengths")]
+    fn test_root_length_mismatch() {
+        let root = MerkleTree::new(&["one", "two", "three"], &SHA256).root();
+        let _ = root == MerkleTree::new(&["one", "two", "three"], &SHA512).root();
+    }
+}
+
+mod test_hex {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::hex::{self, HexError};
+    use vmt::{MerkleTree, ProofFormatError};
+
+    #[test]
+    fn test_encode_decode() {
+        assert_eq!("00ff10ab", hex::encode(&[0, 255, 16, 171]));
+        assert_eq!(vec![0, 255, 16, 171], hex::decode("00FF10ab").unwrap());
+        assert_eq!(Vec::<u8>::new(), hex::decode("").unwrap());
+        assert_eq!(Err(HexError::OddLength { len: 3 }), hex::decode("abc"));
+        assert_eq!(Err(HexError::BadChar { position: 5, ch: 'g' }), hex::decode("00ff1g"));
+        assert_eq!(Err(HexError::BadChar { position: 2, ch: 'é' }), hex::decode("00é0"));
+    }
+
+    #[test]
+    fn test_root_hex() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        assert_eq!(64, tree.root_hex().len());
+        assert_eq!(tree.get_root(), &hex::decode(&tree.root_hex()).unwrap()[..]);
+        assert_eq!("", MerkleTree::new(&Vec::<String>::new(), &SHA256).root_hex());
+    }
+
+    #[test]
+    fn test_proof_hex() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        let proof = tree.build_proof(&"two").unwrap();
+        let decoded = hex::decode_proof(&proof.to_hex(), &SHA256).unwrap();
+        assert_eq!(proof, decoded);
+        assert!(tree.validate(&decoded));
+
+        let err = hex::decode_proof(&proof.to_hex(), &SHA512).err().unwrap();
+        assert_eq!(HexError::Format(ProofFormatError::NotAligned { len: 96, digest_len: 64 }), err);
+        let err = hex::decode_proof(&proof.to_hex()[..64], &SHA256).err().unwrap();
+        assert_eq!(HexError::Format(ProofFormatError::TooShort { hashes: 1 }), err);
+    }
+}
+
+mod test_raw_bytes {
+    use ring::digest::{digest, SHA256};
+
+    use vmt::{get_pair_hash, LoadError, MerkleTree};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_layout() {
+        let values = ["one", "two", "three"];
+        let tree = MerkleTree::new(&values, &SHA256);
+        let bytes = tree.as_bytes();
+        let h: Vec<Vec<u8>> = values.iter().map(|v| digest(&SHA256, v.as_bytes()).as_ref().to_vec()).collect();
+        let h01 = get_pair_hash(&h[0], &h[1], &SHA256).as_ref().to_vec();
+        let h22 = get_pair_hash(&h[2], &h[2], &SHA256).as_ref().to_vec();
+        let root = get_pair_hash(&h01, &h22, &SHA256).as_ref().to_vec();
+        let layout = [&h[0], &h[1], &h[2], &h[2], &h01, &h22, &root];
+        assert_eq!(layout.iter().map(|h| h.to_vec()).collect::<Vec<_>>().concat(), bytes);
+        assert_eq!(tree.get_root(), &bytes[bytes.len() - 32..]);
+        assert_eq!(tree.data_size(), bytes.len());
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let values = values(11);
+        let tree = MerkleTree::new(&values, &SHA256);
+        assert_eq!(tree.leaves().collect::<Vec<_>>().concat(), &tree.as_bytes()[..11 * 32]);
+        let copy = MerkleTree::from_bytes(tree.as_bytes(), 11, &SHA256).unwrap();
+        assert_eq!(tree.as_bytes(), copy.as_bytes());
+        assert_eq!(tree.height(), copy.height());
+        assert!(copy.validate_value(&values[4], &copy.build_proof(&values[4]).unwrap()));
+
+        let err = MerkleTree::from_bytes(tree.as_bytes(), 13, &SHA256).err().unwrap();
+        assert_eq!(LoadError::BadLength { got: 800, expected: 928 }, err);
+        let err = MerkleTree::from_bytes(&tree.as_bytes()[1..], 11, &SHA256).err().unwrap();
+        assert_eq!(LoadError::BadLength { got: 799, expected: 800 }, err);
+        assert!(MerkleTree::from_bytes(&[], 0, &SHA256).unwrap().is_empty());
+    }
+}
+
+mod test_debug {
+    use ring::digest::SHA256;
+
+    use vmt::MerkleTree;
+
+    #[test]
+    fn test_debug_small_tree() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        let expected = "MerkleTree(sha256, leafs: 3, height: 3)\n  \
+            level 0: 7692c3ad3540bb80… 3fc4ccfe745870e2… 8b5b9db0c13db242…\n  \
+            level 1: 0c7c01f86ff8722e… bfa3cacad0ddc1d2…\n  \
+            level 2: 30454d28c7a28d75…";
+        assert_eq!(expected, format!("{:?}", tree));
+        assert_eq!("MerkleTree(sha256, leafs: 0, height: 0)", format!("{:?}", MerkleTree::new(&Vec::<String>::new(), &SHA256)));
+    }
+
+    #[test]
+    fn test_debug_large_tree() {
+        let values: Vec<String> = (0..100_000).map(|i| i.to_string()).collect();
+        let tree = MerkleTree::new(&values, &SHA256);
+        let output = format!("{:?}", tree);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(tree.height() + 1, lines.len());
+        assert!(lines[1].starts_with("  level 0: 5feceb66ffc86f38… "));
+        assert!(lines[1].contains(" … (99994 more) "));
+        assert!(output.len() < 3000);
+    }
+}
+
+mod test_dot {
+    use ring::digest::SHA256;
+
+    use vmt::MerkleTree;
+
+    use super::helpers::values;
+
+    fn count(dot: &str, pattern: &str) -> usize {
+        dot.lines().filter(|l| l.contains(pattern)).count()
+    }
+
+    #[test]
+    fn test_dot_structure() {
+        for &(n, nodes, padding) in [(1, 3, 1), (3, 7, 1), (4, 7, 0)].iter() {
+            let values = values(n);
+            let dot = MerkleTree::new(&values, &SHA256).to_dot();
+            assert!(dot.starts_with("digraph merkle {"));
+            assert_eq!(nodes, count(&dot, "[label="));
+            assert_eq!(nodes - 1, count(&dot, " -> ")); // Every node but the root has one parent
+            assert_eq!(padding, count(&dot, "style=dashed"));
+        }
+        let dot = MerkleTree::new_rfc6962(&["one", "two", "three"], &SHA256).to_dot();
+        assert_eq!(6, count(&dot, "[label="));
+        assert_eq!(5, count(&dot, " -> ")); // Promoted leaf has one child edge
+        assert!(dot.contains("n1_1 -> n0_2;"));
+    }
+
+    #[test]
+    fn test_dot_with_proof() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        let dot = tree.to_dot_with_proof(&tree.build_proof(&"three").unwrap());
+        assert_eq!(3, count(&dot, "fillcolor=lightblue"));
+        assert_eq!(2, count(&dot, "fillcolor=orange"));
+        assert!(dot.contains("n0_3 [label=\"0/3"));
+        assert_eq!(1, count(&dot, "style=\"filled,dashed\", fillcolor=orange")); // Padding copy is the sibling
+        assert_eq!(tree.to_dot(), tree.to_dot_with_proof(&MerkleTree::new(&["four", "five"], &SHA256).build_proof(&"four").unwrap()));
+    }
+
+    #[test]
+    fn test_dot_with_proof_of_duplicate() {
+        let tree = MerkleTree::new_rfc6962(&["one", "two", "one", "three"], &SHA256);
+        let filled = |dot: &str, node: &str, color: &str| dot.lines()
+            .any(|l| l.contains(&format!("{} [", node)) && l.contains(&format!("fillcolor={}", color)));
+        let dot = tree.to_dot_with_proof(&tree.build_proof_by_index(2).unwrap());
+        assert!(filled(&dot, "n0_2", "lightblue"));
+        assert!(filled(&dot, "n0_3", "orange"));
+        assert!(filled(&dot, "n1_1", "lightblue"));
+        assert!(!filled(&dot, "n0_0", "lightblue"));
+        let dot = tree.to_dot_with_proof(&tree.build_proof_by_index(0).unwrap());
+        assert!(filled(&dot, "n0_0", "lightblue"));
+        assert!(!filled(&dot, "n0_2", "lightblue"));
+    }
+}
+
+mod test_equality {
+    use std::collections::HashSet;
+
+    use ring::digest::{SHA256, SHA512_256};
+
+    use vmt::{MerkleTree, MerkleTreeBuilder};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_map_does_not_count() {
+        let values = values(7);
+        assert!(MerkleTree::new(&values, &SHA256) == MerkleTree::new_with_map(&values, &SHA256));
+        let mut set = HashSet::new();
+        set.insert(MerkleTree::new(&values, &SHA256));
+        assert!(!set.insert(MerkleTree::new_with_map(&values, &SHA256)));
+        assert!(set.insert(MerkleTree::new(&values[..6], &SHA256)));
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn test_unequal_trees() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        assert!(tree != MerkleTree::new(&["two", "one", "three"], &SHA256)); // Same root with sorted pairs
+        assert!(tree.get_root() == MerkleTree::new(&["two", "one", "three"], &SHA256).get_root());
+        assert!(tree != MerkleTree::new(&["one", "three", "two"], &SHA256));
+        assert!(tree != MerkleTree::new(&["one", "two", "three"], &SHA512_256));
+        let positional = MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false).build(&["one", "two", "three"]).unwrap();
+        assert!(tree != positional);
+        assert!(MerkleTree::new(&Vec::<String>::new(), &SHA256) == MerkleTree::new(&Vec::<String>::new(), &SHA256));
+    }
+}
+
+mod test_diff {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{DiffError, MerkleTree, MerkleTreeBuilder};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_diff_changed_leafs() {
+        let values = values(1000);
+        let tree = MerkleTree::new(&values, &SHA256);
+        assert_eq!(Vec::<usize>::new(), tree.diff(&MerkleTree::new(&values, &SHA256)).unwrap());
+
+        let changed = [0, 17, 18, 511, 512, 998, 999];
+        let mut other: Vec<String> = values.clone();
+        for &i in changed.iter() {
+            other[i] = format!("corrupted {}", i);
+        }
+        let other = MerkleTree::new(&other, &SHA256);
+        assert_eq!(changed.to_vec(), tree.diff(&other).unwrap());
+        assert_eq!(changed.to_vec(), other.diff(&tree).unwrap());
+    }
+
+    #[test]
+    fn test_diff_leaf_counts() {
+        let values = values(1000);
+        let tree = MerkleTree::new(&values, &SHA256);
+        for &n in [1, 2, 333, 512, 999].iter() {
+            let mut other: Vec<String> = values[..n].to_vec();
+            other[n / 2] = "corrupted".to_string();
+            let expected: Vec<usize> = Some(n / 2).into_iter().chain(n..1000).collect();
+            assert_eq!(expected, tree.diff(&MerkleTree::new(&other, &SHA256)).unwrap());
+            assert_eq!(expected, MerkleTree::new(&other, &SHA256).diff(&tree).unwrap());
+        }
+        let empty = MerkleTree::new(&Vec::<String>::new(), &SHA256);
+        assert_eq!((0..1000).collect::<Vec<_>>(), empty.diff(&tree).unwrap());
+    }
+
+    #[test]
+    fn test_diff_mismatch() {
+        let values = values(10);
+        let tree = MerkleTree::new(&values, &SHA256);
+        assert_eq!(Err(DiffError::AlgorithmMismatch), tree.diff(&MerkleTree::new(&values, &SHA512)));
+        let positional = MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false).build(&values).unwrap();
+        assert_eq!(Err(DiffError::SchemeMismatch), tree.diff(&positional));
+    }
+}
+
+mod test_merge {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{Error, MerkleTree, MerkleTreeBuilder};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_merge_matches_new() {
+        let values = values(40);
+        for &(left, right) in [(0, 5), (5, 0), (1, 1), (3, 5), (7, 9), (8, 8), (13, 27), (16, 3)].iter() {
+            let all = &values[..left + right];
+            let merged = MerkleTree::new(&all[..left], &SHA256).merge(MerkleTree::new(&all[left..], &SHA256)).unwrap();
+            let tree = MerkleTree::new(all, &SHA256);
+            assert!(tree == merged);
+            assert_eq!(tree.height(), merged.height());
+            for value in all {
+                assert!(merged.validate_value(value, &merged.build_proof(value).unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_maps() {
+        let values: Vec<String> = (0..10).map(|i| format!("value {}", i % 8)).collect(); // Last two repeat
+        let merged = MerkleTree::new(&values[..5], &SHA256).merge(MerkleTree::new_with_map(&values[5..], &SHA256)).unwrap();
+        assert_eq!(Some(0), merged.index_of(&values[8]));
+        assert_eq!(Some(7), merged.index_of(&values[7]));
+        let merged = MerkleTree::new_with_map(&values[..3], &SHA256).merge(MerkleTree::new_with_map(&values[3..], &SHA256)).unwrap();
+        assert_eq!(Some(6), merged.index_of(&values[6]));
+        assert_eq!(Some(1), merged.index_of(&values[9]));
+    }
+
+    #[test]
+    fn test_merge_mismatch() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        assert_eq!(Error::AlgorithmMismatch, tree.merge(MerkleTree::new(&["four"], &SHA512)).err().unwrap());
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        let positional = MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false).build(&["four"]).unwrap();
+        assert_eq!(Error::SchemeMismatch, tree.merge(positional).err().unwrap());
+    }
+
+    #[test]
+    fn test_subtree() {
+        let values = values(23);
+        let tree = MerkleTree::new_with_map(&values, &SHA256);
+        for &(start, end) in [(0, 23), (0, 1), (1, 2), (3, 10), (5, 21), (7, 23), (22, 23)].iter() {
+            let subtree = tree.subtree(start..end).unwrap();
+            assert!(MerkleTree::new(&values[start..end], &SHA256) == subtree);
+            assert_eq!(Some(0), subtree.index_of(&values[start]));
+            assert!(subtree.validate_value(&values[end - 1], &subtree.build_proof(&values[end - 1]).unwrap()));
+        }
+        let rfc = MerkleTree::new_rfc6962(&values, &SHA256);
+        assert_eq!(MerkleTree::new_rfc6962(&values[3..14], &SHA256).get_root(), rfc.subtree(3..14).unwrap().get_root());
+        assert!(tree.subtree(5..5).is_none());
+        assert!(tree.subtree(20..24).is_none());
+        assert!(tree.subtree(23..24).is_none());
+    }
+}
+
+mod test_range_proof {
+    use ring::digest::SHA256;
+
+    use vmt::{verify_range, MerkleTree, RangeProof};
+
+    use super::helpers::values;
+
+    fn check(values: &[String], start: usize, end: usize) -> RangeProof {
+        let tree = MerkleTree::new(values, &SHA256);
+        let proof = tree.build_range_proof(start..end).unwrap();
+        let leafs: Vec<&[u8]> = tree.leaves().skip(start).take(end - start).collect();
+        assert!(tree.validate_range(start..end, &leafs, &proof));
+        assert!(verify_range(tree.get_root(), start..end, &leafs, &proof, &SHA256));
+
+        let mut tampered: Vec<Vec<u8>> = leafs.iter().map(|l| l.to_vec()).collect();
+        tampered[0][0] ^= 1;
+        assert!(!verify_range(tree.get_root(), start..end, &tampered, &proof, &SHA256));
+        if end - start > 1 {
+            assert!(!verify_range(tree.get_root(), start + 1..end, &leafs[1..], &proof, &SHA256));
+        }
+        proof
+    }
+
+    #[test]
+    fn test_ranges() {
+        let values = values(13);
+        assert_eq!(0, check(&values, 0, 13).hashes().count()); // Full range
+        for &(start, end) in [(0, 1), (12, 13), (6, 7), (0, 4), (0, 7), (9, 13), (3, 11), (4, 8)].iter() {
+            check(&values, start, end);
+        }
+        assert_eq!(MerkleTree::new(&values, &SHA256).build_proof_by_index(5).unwrap().siblings().count(),
+                   check(&values, 5, 6).hashes().count());
+    }
+
+    #[test]
+    fn test_range_at_odd_end() {
+        let values = values(11);
+        let proof = check(&values, 8, 11); // Leaf 10 is paired with its padding copy
+        assert_eq!(1, proof.hashes().count());
+        check(&values, 10, 11);
+        check(&values, 0, 11);
+    }
+
+    #[test]
+    fn test_bad_ranges() {
+        let values = values(5);
+        let tree = MerkleTree::new(&values, &SHA256);
+        assert!(tree.build_range_proof(3..3).is_none());
+        assert!(tree.build_range_proof(3..6).is_none());
+        let proof = tree.build_range_proof(1..3).unwrap();
+        let leafs: Vec<&[u8]> = tree.leaves().skip(1).take(2).collect();
+        assert!(!tree.validate_range(1..4, &leafs, &proof));
+        assert!(!tree.validate_range(1..3, &leafs[..1], &proof));
+    }
+}
+
+mod test_subtree_proof {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, MerkleTreeBuilder};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_block_proof() {
+        let values = values(1000);
+        let tree = MerkleTree::new(&values, &SHA256);
+        let block = tree.node(8, 2).unwrap().to_vec(); // Leafs 512..768
+        assert_eq!(MerkleTree::new(&values[512..768], &SHA256).get_root(), &block[..]);
+        let proof = tree.build_subtree_proof(8, 2).unwrap();
+        assert_eq!(2 * 32, proof.len());
+        assert!(tree.validate_subtree(8, 2, &block, &proof));
+
+        let mut tampered = block.clone();
+        tampered[31] ^= 1;
+        assert!(!tree.validate_subtree(8, 2, &tampered, &proof));
+        assert!(!tree.validate_subtree(8, 2, &block, &proof[..32]));
+    }
+
+    #[test]
+    fn test_every_node() {
+        let values = values(13);
+        let trees = [MerkleTree::new(&values, &SHA256), MerkleTree::new_rfc6962(&values, &SHA256),
+            MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false).build(&values).unwrap()];
+        for tree in trees.iter() {
+            for level in 0..tree.height() - 1 {
+                for index in 0..tree.level(level).unwrap().len() / 32 {
+                    let proof = tree.build_subtree_proof(level, index).unwrap();
+                    assert!(tree.validate_subtree(level, index, tree.node(level, index).unwrap(), &proof));
+                }
+            }
+            let leaf_proof = tree.build_proof_by_index(5).unwrap();
+            assert_eq!(&leaf_proof.as_bytes()[32..], &tree.build_subtree_proof(0, 5).unwrap()[..]);
+            assert!(tree.build_subtree_proof(tree.height() - 1, 0).is_none()); // Root
+            assert!(tree.build_subtree_proof(1, 7).is_none());
+            assert!(tree.build_subtree_proof(tree.height(), 0).is_none());
+        }
+    }
+}
+
+mod test_sparse {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::sparse::{verify_sparse, SparseMerkleTree};
+
+    fn filled(keys: &[&str]) -> SparseMerkleTree {
+        let mut tree = SparseMerkleTree::new(&SHA256);
+        for key in keys {
+            tree.insert(key, &format!("value of {}", key));
+        }
+        tree
+    }
+
+    #[test]
+    fn test_insert_and_prove() {
+        let tree = filled(&["alice", "bob", "carol"]);
+        assert_eq!(3, tree.len());
+        assert_eq!(Some(&b"value of bob"[..]), tree.get(&"bob"));
+        for key in ["alice", "bob", "carol"].iter() {
+            let value = format!("value of {}", key);
+            let proof = tree.prove(key);
+            assert!(verify_sparse(tree.root(), key, Some(value.as_bytes()), &proof, &SHA256));
+            assert!(!verify_sparse(tree.root(), key, Some(b"other value"), &proof, &SHA256));
+            assert!(!verify_sparse(tree.root(), key, None, &proof, &SHA256));
+            assert!(!verify_sparse(tree.root(), &"dave", Some(value.as_bytes()), &proof, &SHA256));
+        }
+        assert!(tree.prove(&"alice").siblings_count() < 10); // Empty subtrees are left out
+    }
+
+    #[test]
+    fn test_missing_key() {
+        let tree = filled(&["alice", "bob", "carol"]);
+        assert_eq!(None, tree.get(&"dave"));
+        let proof = tree.prove(&"dave");
+        assert!(verify_sparse(tree.root(), &"dave", None, &proof, &SHA256));
+        assert!(!verify_sparse(tree.root(), &"dave", Some(b"value of dave"), &proof, &SHA256));
+        assert!(!verify_sparse(tree.root(), &"alice", None, &proof, &SHA256));
+
+        let empty = SparseMerkleTree::new(&SHA512);
+        assert!(empty.is_empty());
+        assert_eq!(512, empty.depth());
+        assert!(verify_sparse(empty.root(), &"alice", None, &empty.prove(&"alice"), &SHA512));
+    }
+
+    #[test]
+    fn test_overwrite() {
+        let mut tree = filled(&["alice", "bob"]);
+        let before = tree.root().to_vec();
+        tree.insert(&"bob", &"new value");
+        assert!(before != tree.root());
+        assert_eq!(Some(&b"new value"[..]), tree.get(&"bob"));
+        assert_eq!(2, tree.len());
+        assert!(verify_sparse(tree.root(), &"bob", Some(b"new value"), &tree.prove(&"bob"), &SHA256));
+        tree.insert(&"bob", &"value of bob");
+        assert_eq!(&before[..], tree.root());
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        let keys = ["one", "two", "three", "four", "five", "six"];
+        let tree = filled(&keys);
+        let mut reversed = keys;
+        reversed.reverse();
+        assert_eq!(tree.root(), filled(&reversed).root());
+        assert!(tree.root() != filled(&keys[..5]).root());
+        assert!(SparseMerkleTree::new(&SHA256).root() != filled(&keys[..1]).root());
+    }
+}
+
+mod test_mmr {
+    use ring::digest::{digest, Context, SHA256};
+
+    use vmt::mmr::{self, Mmr};
+
+    fn hash(value: &str) -> Vec<u8> {
+        digest(&SHA256, value.as_bytes()).as_ref().to_vec()
+    }
+
+    fn pair(left: &[u8], right: &[u8]) -> Vec<u8> {
+        let mut ctx = Context::new(&SHA256);
+        ctx.update(left);
+        ctx.update(right);
+        ctx.finish().as_ref().to_vec()
+    }
+
+    #[test]
+    fn test_positions() {
+        let mut mmr = Mmr::new(&SHA256);
+        assert!(mmr.is_empty());
+        assert_eq!(Vec::<u8>::new(), mmr.root());
+        let positions: Vec<usize> = (0..9).map(|i| mmr.append(&format!("value {}", i))).collect();
+        assert_eq!(vec![0, 1, 3, 4, 7, 8, 10, 11, 15], positions);
+        assert_eq!(16, mmr.size());
+        assert_eq!(9, mmr.leaf_count());
+    }
+
+    #[test]
+    fn test_bagging() {
+        let mut mmr = Mmr::new(&SHA256);
+        mmr.append(&"a");
+        assert_eq!(hash("a"), mmr.root()); // Single leaf is its own root
+        mmr.append(&"b");
+        let ab = pair(&hash("a"), &hash("b"));
+        assert_eq!(ab, mmr.root());
+        mmr.append(&"c");
+        assert_eq!(pair(&ab, &hash("c")), mmr.root());
+        mmr.append(&"d");
+        let abcd = pair(&ab, &pair(&hash("c"), &hash("d")));
+        assert_eq!(abcd, mmr.root());
+        mmr.append(&"e");
+        mmr.append(&"f");
+        mmr.append(&"g");
+        let ef = pair(&hash("e"), &hash("f"));
+        assert_eq!(pair(&abcd, &pair(&ef, &hash("g"))), mmr.root()); // Bagged right to left
+    }
+
+    #[test]
+    fn test_proofs() {
+        let mut mmr = Mmr::new(&SHA256);
+        let mut leafs = vec![];
+        for i in 0..21 {
+            let value = format!("value {}", i);
+            leafs.push((mmr.append(&value), value));
+            let root = mmr.root();
+            for &(position, ref value) in &leafs {
+                let proof = mmr.prove(position).unwrap();
+                assert!(mmr::verify(&root, position, value, &proof, &SHA256));
+                assert!(!mmr::verify(&root, position, &"other", &proof, &SHA256));
+            }
+        }
+        let root = mmr.root();
+        let proof = mmr.prove(0).unwrap();
+        assert!(!mmr::verify(&root, 1, &leafs[0].1, &proof, &SHA256)); // Positions are bound
+        assert!(mmr.prove(2).is_none()); // Inner node
+        assert!(mmr.prove(mmr.size()).is_none());
+        assert!(!mmr::verify(&root, 2, &leafs[0].1, &proof, &SHA256));
+    }
+
+    #[test]
+    fn test_history_is_kept() {
+        let mut mmr = Mmr::new(&SHA256);
+        for i in 0..6 {
+            mmr.append(&format!("value {}", i));
+        }
+        let old_root = mmr.root();
+        let proof = mmr.prove(4).unwrap();
+        mmr.append(&"value 6");
+        assert!(old_root != mmr.root());
+        assert!(mmr::verify(&old_root, 4, &"value 3", &proof, &SHA256));
+        assert!(!mmr::verify(&mmr.root(), 4, &"value 3", &proof, &SHA256));
+        assert!(mmr::verify(&mmr.root(), 4, &"value 3", &mmr.prove(4).unwrap(), &SHA256));
+    }
+}
+
+mod test_absence {
+    use ring::digest::{digest, SHA256};
+
+    use vmt::{verify_absence, SortedMerkleTree};
+
+    use super::helpers::values;
+
+    // Values ordered by their keys
+    fn by_key(values: &[String]) -> Vec<String> {
+        let mut sorted = values.to_vec();
+        sorted.sort_by_key(|v| digest(&SHA256, v.as_bytes()).as_ref().to_vec());
+        sorted
+    }
+
+    // Missing value whose key is below the first key or above the last one
+    fn outside(values: &[String], below: bool) -> String {
+        let sorted = by_key(values);
+        let key = |v: &String| digest(&SHA256, v.as_bytes()).as_ref().to_vec();
+        (0..).map(|i| format!("missing {}", i))
+            .find(|m| if below { key(m) < key(&sorted[0]) } else { key(m) > key(&sorted[sorted.len() - 1]) })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_absent_values() {
+        let values = values(11);
+        let tree = SortedMerkleTree::new(&values, &SHA256);
+        assert_eq!(11, tree.tree().leafs_count());
+        for i in 0..50 {
+            let missing = format!("missing {}", i);
+            let proof = tree.prove_absence(&missing).unwrap();
+            assert!(verify_absence(tree.get_root(), &missing, &proof, &SHA256));
+            assert!(!verify_absence(tree.get_root(), &values[0], &proof, &SHA256));
+        }
+    }
+
+    #[test]
+    fn test_absent_outside() {
+        let values = values(11);
+        let tree = SortedMerkleTree::new(&values, &SHA256);
+        let below = outside(&values, true);
+        let proof = tree.prove_absence(&below).unwrap();
+        assert!(proof.left.is_none());
+        assert!(verify_absence(tree.get_root(), &below, &proof, &SHA256));
+
+        let above = outside(&values, false);
+        let proof = tree.prove_absence(&above).unwrap();
+        assert!(proof.right.is_none());
+        assert!(verify_absence(tree.get_root(), &above, &proof, &SHA256));
+
+        let mut inner = tree.prove_absence(&"missing 1").unwrap();
+        inner.right = None; // Left neighbour is not the last leaf
+        assert!(!verify_absence(tree.get_root(), &"missing 1", &inner, &SHA256));
+    }
+
+    #[test]
+    fn test_present_values() {
+        let mut values = values(11);
+        values.push(values[3].clone());
+        let tree = SortedMerkleTree::new(&values, &SHA256);
+        assert_eq!(11, tree.tree().leafs_count()); // Deduplicated
+        let sorted = by_key(&values[..11]);
+        for (i, value) in sorted.iter().enumerate() {
+            assert!(tree.prove_absence(value).is_none());
+            let proof = tree.build_proof(value).unwrap();
+            assert_eq!(Some(proof.clone()), tree.tree().build_proof_by_index(i));
+            assert!(tree.validate_value(value, &proof));
+            assert!(tree.tree().validate(&proof));
+        }
+        assert!(tree.build_proof(&"missing 0").is_none());
+    }
+
+    #[test]
+    fn test_forged_neighbours() {
+        let values = values(11);
+        let tree = SortedMerkleTree::new(&values, &SHA256);
+        let sorted = by_key(&values);
+        let proof = tree.prove_absence(&"missing 2").unwrap();
+        // Leafs that are not neighbours do not prove anything about the keys between them
+        let far = tree.prove_absence(&outside(&values, true)).unwrap();
+        let mut forged = proof.clone();
+        forged.right = far.right;
+        assert!(!verify_absence(tree.get_root(), &"missing 2", &forged, &SHA256));
+        for value in &sorted {
+            assert!(!verify_absence(tree.get_root(), value, &proof, &SHA256));
+        }
+        let empty = SortedMerkleTree::new(&Vec::<String>::new(), &SHA256);
+        let proof = empty.prove_absence(&"anything").unwrap();
+        assert!(verify_absence(empty.get_root(), &"anything", &proof, &SHA256));
+    }
+}
+
+mod test_duplicates {
+    use std::hash::BuildHasherDefault;
+
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{MerkleHasher, MerkleTree, MerkleTreeBuilder, PrefixHasher};
+
+    use super::helpers::{Constant, Fnv, values};
+
+    // Every digest starts with the same 8 bytes, the Fnv digest follows
+    struct Colliding;
+
+    impl MerkleHasher for Colliding {
+        fn output_len(&self) -> usize {
+            8 + Fnv.output_len()
+        }
+
+        fn hash_parts(&self, parts: &[&[u8]], out: &mut [u8]) {
+            out[..8].copy_from_slice(&[0xaa; 8]);
+            Fnv.hash_parts(parts, &mut out[8..]);
+        }
+    }
+
+    #[test]
+    fn test_map_proves_first_occurrence() {
+        let values = vec!["one", "one", "two"];
+        let plain = MerkleTree::new(&values, &SHA256);
+        let mapped = MerkleTree::new_with_map(&values, &SHA256);
+        let proof = plain.build_proof(&"one").unwrap();
+        assert_eq!(plain.build_proof_by_index(0).unwrap().as_bytes(), proof.as_bytes());
+        assert_eq!(proof.as_bytes(), mapped.build_proof(&"one").unwrap().as_bytes());
+        assert_eq!(vec![0, 1], plain.indices_of(&"one"));
+        assert_eq!(vec![0, 1], mapped.indices_of(&"one"));
+        assert_eq!(Vec::<usize>::new(), mapped.indices_of(&"three"));
+    }
+
+    #[test]
+    fn test_map_follows_changes() {
+        let mut plain = MerkleTree::new(&["one", "one", "two"], &SHA256);
+        let mut mapped = MerkleTree::new_with_map(&["one", "one", "two"], &SHA256);
+        for tree in [&mut plain, &mut mapped].iter_mut() {
+            tree.update_leaf(0, &"two").unwrap();
+            tree.push(&"one");
+        }
+        assert_eq!(vec![1, 3], mapped.indices_of(&"one"));
+        assert_eq!(vec![0, 2], mapped.indices_of(&"two"));
+        for value in &["one", "two"] {
+            assert_eq!(plain.indices_of(value), mapped.indices_of(value));
+            assert_eq!(plain.build_proof(value), mapped.build_proof(value));
+        }
+        mapped.truncate(2);
+        assert_eq!(vec![1], mapped.indices_of(&"one"));
+        assert_eq!(vec![0], mapped.indices_of(&"two"));
+    }
+
+    #[test]
+    fn test_build_proof_all() {
+        let values = vec!["a", "b", "c", "a", "d", "e", "a", "f"];
+        for tree in &[MerkleTree::new(&values, &SHA256), MerkleTree::new_with_map(&values, &SHA256)] {
+            assert_eq!(vec![0, 3, 6], tree.indices_of(&"a"));
+            let proofs = tree.build_proof_all(&"a");
+            assert_eq!(3, proofs.len());
+            for (proof, &index) in proofs.iter().zip(&[0, 3, 6]) {
+                assert_eq!(tree.build_proof_by_index(index).as_ref(), Some(proof));
+                assert!(tree.validate(proof));
+            }
+            assert_ne!(proofs[0], proofs[1]);
+            assert_eq!(1, tree.build_proof_all(&"b").len());
+            assert!(tree.build_proof_all(&"z").is_empty());
+        }
+    }
+
+    #[test]
+    fn test_map_with_colliding_prefixes() {
+        let values = vec!["a", "b", "a", "c", "b", "d", "a"];
+        let plain = MerkleTree::new_with_hasher(&values, Colliding, false);
+        let mut mapped = MerkleTree::new_with_hasher(&values, Colliding, true);
+        for value in &["a", "b", "c", "d", "e"] {
+            assert_eq!(plain.index_of(value), mapped.index_of(value));
+            assert_eq!(plain.indices_of(value), mapped.indices_of(value));
+            assert_eq!(plain.build_proof(value), mapped.build_proof(value));
+        }
+        assert_eq!(vec![0, 2, 6], mapped.indices_of(&"a"));
+        mapped.update_leaf(0, &"d").unwrap();
+        mapped.truncate(6);
+        mapped.push(&"b");
+        assert_eq!(vec![2], mapped.indices_of(&"a"));
+        assert_eq!(vec![1, 4, 6], mapped.indices_of(&"b"));
+        assert_eq!(vec![0, 5], mapped.indices_of(&"d"));
+        assert_eq!(Some(3), mapped.index_of(&"c"));
+        assert!(!mapped.contains(&"e"));
+    }
+
+    #[test]
+    fn test_map_does_not_copy_hashes() {
+        let values = values(10_000);
+        let tree = MerkleTree::new_with_map(&values, &SHA512);
+        assert_eq!(None, MerkleTree::new(&values, &SHA512).map_size());
+        // Less than half of what the leaf hashes alone take
+        assert!(tree.map_size().unwrap() < values.len() * 64 / 2);
+        assert_eq!(Some(9_999), tree.index_of(&values[9_999]));
+    }
+
+    #[test]
+    fn test_custom_map_hasher() {
+        let values: Vec<String> = (0..20).map(|i| format!("value {}", i % 7)).collect();
+        let plain = MerkleTree::new(&values, &SHA256);
+        let prefix = MerkleTreeBuilder::new().algorithm(&SHA256).map_hasher(BuildHasherDefault::<PrefixHasher>::default()).build(&values).unwrap();
+        let constant = MerkleTreeBuilder::new().algorithm(&SHA256).map_hasher(Constant).build_from_iter(values.iter()).unwrap();
+        let disabled = MerkleTreeBuilder::new().algorithm(&SHA256).map_hasher(Constant).use_map(false).build(&values).unwrap();
+        assert!(prefix.map_size().is_some());
+        assert_eq!(None, disabled.map_size());
+        for tree in &[&prefix, &constant] {
+            for value in values.iter().map(|v| v.as_str()).chain(Some("absent")) {
+                assert_eq!(plain.index_of(&value), tree.index_of(&value));
+                assert_eq!(plain.indices_of(&value), tree.indices_of(&value));
+                assert_eq!(plain.build_proof(&value), tree.build_proof(&value));
+            }
+        }
+        let mut merged = MerkleTree::new(&values[..5], &SHA256).merge(constant).unwrap();
+        merged.update_leaf(0, &"value 6").unwrap();
+        assert_eq!(vec![0, 11, 18], merged.indices_of(&"value 6"));
+        assert_eq!(merged.build_proof_by_index(0), merged.build_proof(&"value 6"));
+    }
+
+    #[test]
+    fn test_enable_and_disable_map() {
+        let values = vec!["one", "one", "two", "three", "two"];
+        let mut tree = MerkleTree::new(&values, &SHA256);
+        let built = MerkleTree::new_with_map(&values, &SHA256);
+        assert!(!tree.has_map());
+        let before: Vec<_> = values.iter().map(|v| tree.build_proof(v)).collect();
+        tree.enable_map();
+        tree.enable_map();
+        assert!(tree.has_map());
+        assert!(tree == built);
+        for (value, proof) in values.iter().zip(&before) {
+            assert_eq!(proof, &tree.build_proof(value));
+            assert_eq!(built.index_of(value), tree.index_of(value));
+            assert_eq!(built.indices_of(value), tree.indices_of(value));
+        }
+        tree.push(&"one");
+        assert_eq!(vec![0, 1, 5], tree.indices_of(&"one"));
+        tree.disable_map();
+        assert!(!tree.has_map());
+        assert_eq!(None, tree.map_size());
+        assert_eq!(vec![0, 1, 5], tree.indices_of(&"one"));
+        assert_eq!(Some(2), tree.index_of(&"two"));
+    }
+}
+
+mod test_proof_error {
+    use std::error::Error;
+
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, ProofError};
+
+    #[test]
+    fn test_try_build_proof() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        let proof = tree.try_build_proof(&"two").unwrap();
+        assert_eq!(tree.build_proof(&"two"), Some(proof));
+        assert_eq!(Err(ProofError::NotFound), tree.try_build_proof(&"four"));
+        let empty = MerkleTree::new(&Vec::<String>::new(), &SHA256);
+        assert_eq!(Err(ProofError::EmptyTree), empty.try_build_proof(&"one"));
+        assert_eq!(None, empty.build_proof(&"one"));
+    }
+
+    #[test]
+    fn test_try_build_proof_by_hash() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        let hash = vmt::get_hash(b"one", &SHA256);
+        assert_eq!(tree.build_proof(&"one"), tree.try_build_proof_by_hash(hash.as_ref()).ok());
+        let error = tree.try_build_proof_by_hash(&hash.as_ref()[1..]).unwrap_err();
+        assert_eq!(ProofError::BadHashLength { len: 31, expected: 32 }, error);
+        let error: Box<dyn Error> = Box::new(error);
+        assert_eq!("hash has length 31, expected 32", error.to_string());
+        assert_eq!(Err(ProofError::NotFound), tree.try_build_proof_by_hash(&[0; 32]));
+    }
+}
+
+#[cfg(feature = "mmap")]
+mod test_mmap {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use ring::digest::SHA256;
+
+    use vmt::{LoadError, MerkleTree};
+
+    use super::helpers::values;
+
+    fn path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("vmt-{}-{}.bin", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_build_file_matches_memory() {
+        for count in 0..12 {
+            let values = values(count);
+            let file = path(&format!("build-{}", count));
+            MerkleTree::build_file(&values, &SHA256, &file).unwrap();
+            let tree = MerkleTree::new(&values, &SHA256);
+            let mut stored = vec![];
+            tree.write_to(&mut stored).unwrap();
+            assert_eq!(stored, fs::read(&file).unwrap());
+
+            let mapped: MerkleTree = MerkleTree::open_mapped(&file, true).unwrap();
+            assert!(mapped.is_mapped());
+            assert!(mapped == tree);
+            assert_eq!(tree.get_root(), mapped.get_root());
+            for value in &values {
+                let proof = mapped.build_proof(value).unwrap();
+                assert_eq!(tree.build_proof(value), Some(proof.clone()));
+                assert!(mapped.validate(&proof));
+            }
+            fs::remove_file(&file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_mapped_tree_changes_in_memory() {
+        let values = vec!["one", "two", "three"];
+        let file = path("change");
+        MerkleTree::new_with_map(&values, &SHA256).write_to(fs::File::create(&file).unwrap()).unwrap();
+        let stored = fs::read(&file).unwrap();
+        let mut tree: MerkleTree = MerkleTree::open_mapped(&file, false).unwrap();
+        assert_eq!(Some(1), tree.index_of(&"two"));
+        tree.update_leaf(1, &"four").unwrap();
+        assert!(!tree.is_mapped());
+        tree.push(&"five");
+        assert!(tree == MerkleTree::new(&["one", "four", "three", "five"], &SHA256));
+        assert_eq!(stored, fs::read(&file).unwrap());
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_open_damaged_file() {
+        let values = vec!["one", "two", "three"];
+        let file = path("damaged");
+        MerkleTree::build_file(&values, &SHA256, &file).unwrap();
+        let mut stored = fs::read(&file).unwrap();
+        let last = stored.len() - 1;
+        stored[last] ^= 1;
+        fs::write(&file, &stored).unwrap();
+        assert!(MerkleTree::<vmt::RingHasher>::open_mapped(&file, false).is_ok());
+        assert_eq!(Some(LoadError::RootMismatch), MerkleTree::<vmt::RingHasher>::open_mapped(&file, true).err());
+        fs::write(&file, &stored[..last]).unwrap();
+        assert_eq!(Some(LoadError::Truncated), MerkleTree::<vmt::RingHasher>::open_mapped(&file, false).err());
+        fs::write(&file, &stored[..10]).unwrap();
+        assert_eq!(Some(LoadError::Truncated), MerkleTree::<vmt::RingHasher>::open_mapped(&file, false).err());
+        fs::remove_file(&file).unwrap();
+    }
+}
+
+mod test_save {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use ring::digest::SHA256;
+
+    use vmt::{LoadError, MerkleTree, RingHasher};
+
+    use super::helpers::values;
+
+    fn path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("vmt-save-{}-{}.bin", name, std::process::id()))
+    }
+
+    fn load(path: &PathBuf) -> Result<MerkleTree, LoadError> {
+        MerkleTree::<RingHasher>::load(path, false)
+    }
+
+    #[test]
+    fn test_save_load() {
+        let values = values(7);
+        let tree = MerkleTree::new(&values, &SHA256);
+        let file = path("round-trip");
+        tree.save(&file).unwrap();
+        assert!(!file.with_extension("bin.tmp").exists());
+        let loaded = load(&file).unwrap();
+        assert!(loaded == tree);
+        assert!(!loaded.has_map());
+        let mapped = MerkleTree::<RingHasher>::load(&file, true).unwrap();
+        assert!(mapped.has_map());
+        assert_eq!(tree.build_proof(&values[3]), mapped.build_proof(&values[3]));
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_load_corrupted() {
+        let file = path("corrupted");
+        MerkleTree::new(&["one", "two", "three"], &SHA256).save(&file).unwrap();
+        let stored = fs::read(&file).unwrap();
+        for &i in &[50, stored.len() - 40, stored.len() - 1] { // Leaf, root and checksum
+            let mut flipped = stored.clone();
+            flipped[i] ^= 1;
+            fs::write(&file, &flipped).unwrap();
+            assert_eq!(Some(LoadError::ChecksumMismatch), load(&file).err());
+        }
+        fs::write(&file, &stored[..stored.len() - 1]).unwrap();
+        assert_eq!(Some(LoadError::Truncated), load(&file).err());
+        fs::write(&file, &stored[..20]).unwrap();
+        assert_eq!(Some(LoadError::Truncated), load(&file).err());
+        fs::remove_file(&file).unwrap();
+        assert_eq!(Some(LoadError::Io(std::io::ErrorKind::NotFound)), load(&file).err());
+    }
+}
+
+mod test_view {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{LoadError, MerkleTree, MerkleTreeView};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_view_matches_tree() {
+        for count in 0..12 {
+            let values = values(count);
+            let tree = MerkleTree::new(&values, &SHA256);
+            let view = MerkleTreeView::new(tree.as_bytes(), count, &SHA256).unwrap();
+            assert_eq!(tree.get_root(), view.get_root());
+            assert_eq!(count, view.leafs_count());
+            assert!(tree.leaves().eq(view.leaves()));
+            for level in 0..tree.height() + 1 {
+                for index in 0..count + 1 {
+                    assert_eq!(tree.node(level, index), view.node(level, index));
+                }
+            }
+            for i in 0..count + 1 {
+                let proof = view.build_proof_by_index(i);
+                assert_eq!(tree.build_proof_by_index(i), proof);
+                if let Some(proof) = proof {
+                    assert!(view.validate(&proof));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_view_checks_length() {
+        let tree = MerkleTree::new(&["one", "two", "three"], &SHA256);
+        let bytes = tree.as_bytes();
+        assert_eq!(Some(LoadError::BadLength { got: bytes.len() - 1, expected: bytes.len() }),
+                   MerkleTreeView::new(&bytes[..bytes.len() - 1], 3, &SHA256).err());
+        assert!(MerkleTreeView::new(bytes, 5, &SHA256).is_err());
+        assert!(MerkleTreeView::new(bytes, usize::MAX, &SHA256).is_err());
+        let other = MerkleTree::new(&["one", "two"], &SHA512);
+        let view = MerkleTreeView::new(bytes, 3, &SHA256).unwrap();
+        assert!(!view.validate(&other.build_proof_by_index(0).unwrap()));
+    }
+}
+
+mod test_shared {
+    use std::thread;
+
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, SharedMerkleTree};
+
+    use super::helpers::values;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_shared_is_send_sync() {
+        assert_send_sync::<MerkleTree>();
+        assert_send_sync::<SharedMerkleTree>();
+    }
+
+    #[test]
+    fn test_shared_clone() {
+        let values = values(10);
+        let tree = MerkleTree::new_with_map(&values, &SHA256);
+        let expected = MerkleTree::new_with_map(&values, &SHA256);
+        let shared = tree.into_shared();
+        let clone = shared.clone();
+        assert!(shared.ptr_eq(&clone));
+        assert_eq!(expected, *clone);
+        assert!(clone.has_map());
+        assert_eq!(expected.build_proof(&"value 3"), clone.build_proof(&"value 3"));
+        let shared = shared.try_unwrap().unwrap_err();
+        drop(clone);
+        assert_eq!(expected, shared.try_unwrap().ok().unwrap());
+    }
+
+    #[test]
+    fn test_shared_threads() {
+        let values = values(64);
+        let shared: SharedMerkleTree = MerkleTree::new_with_map(&values, &SHA256).into();
+        let root = shared.get_root().to_vec();
+        let workers: Vec<_> = (0..8).map(|t| {
+            let tree = shared.clone();
+            let values = values.clone();
+            thread::spawn(move || {
+                (t * 8..(t + 1) * 8).map(|i| tree.build_proof(&values[i]).unwrap()).collect::<Vec<_>>()
+            })
+        }).collect();
+        for (t, worker) in workers.into_iter().enumerate() {
+            for (i, proof) in worker.join().unwrap().iter().enumerate() {
+                assert_eq!(Some(proof.clone()), shared.build_proof_by_index(t * 8 + i));
+                assert!(shared.validate(proof));
+                assert!(vmt::verify_proof(&root, proof.as_bytes(), &SHA256));
+            }
+        }
+    }
+}
+
+mod test_lazy {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use ring::digest::SHA256;
+
+    use vmt::{LazyMerkleTree, MerkleTree, RingHasher};
+
+    use super::helpers::{values, Counting};
+
+    #[test]
+    fn test_lazy_matches_eager() {
+        for count in 0..12 {
+            let values = values(count);
+            let tree = MerkleTree::new(&values, &SHA256);
+            let lazy = MerkleTree::new_lazy(&values, &SHA256);
+            assert!(tree.leaves().eq(lazy.leaves()));
+            assert!(!lazy.is_computed());
+            assert_eq!(tree.get_root(), lazy.get_root());
+            for i in 0..count + 1 {
+                assert_eq!(tree.build_proof_by_index(i), lazy.build_proof_by_index(i));
+            }
+            for v in &values {
+                let proof = lazy.build_proof(v).unwrap();
+                assert!(lazy.validate(&proof));
+                assert_eq!(tree.build_proof(v), Some(proof));
+            }
+            for level in 0..tree.height() + 1 {
+                for index in 0..count + 1 {
+                    assert_eq!(tree.node(level, index), lazy.node(level, index));
+                }
+            }
+            assert_eq!(tree, lazy.into_tree());
+            assert_eq!(tree, MerkleTree::new_lazy(&values, &SHA256).into_tree());
+        }
+    }
+
+    #[test]
+    fn test_lazy_leaf_reads_hash_nothing() {
+        let pairs = Arc::new(AtomicUsize::new(0));
+        let hasher = Counting { inner: RingHasher::new(&SHA256), pairs: pairs.clone() };
+        let lazy = LazyMerkleTree::new_with_hasher(&values(100), hasher);
+        for i in 0..100 {
+            assert_eq!(lazy.leaves().nth(i), lazy.node(0, i));
+        }
+        assert!(lazy.node(0, 100).is_none());
+        assert_eq!(0, pairs.load(Ordering::SeqCst));
+        assert!(!lazy.is_computed());
+        assert!(lazy.node(1, 0).is_some());
+        assert!(lazy.is_computed());
+    }
+
+    #[test]
+    fn test_lazy_hashes_levels_once() {
+        let pairs = Arc::new(AtomicUsize::new(0));
+        let hasher = Counting { inner: RingHasher::new(&SHA256), pairs: pairs.clone() };
+        let values = values(100);
+        let lazy = LazyMerkleTree::new_with_hasher(&values, hasher);
+        assert_eq!(100, lazy.leaves().count());
+        assert!(lazy.build_proof_by_index(100).is_none());
+        assert_eq!(0, pairs.load(Ordering::SeqCst));
+
+        let root = lazy.get_root().to_vec();
+        let hashed = pairs.load(Ordering::SeqCst);
+        assert!(hashed > 0);
+        assert_eq!(MerkleTree::new(&values, &SHA256).get_root(), &root[..]);
+        assert_eq!(root, lazy.get_root());
+        assert!(lazy.build_proof(&"value 42").is_some());
+        assert!(lazy.node(1, 0).is_some());
+        assert_eq!(hashed, pairs.load(Ordering::SeqCst));
+    }
+}
+
+mod test_rebuild {
+    use ring::digest::SHA256;
+
+    use vmt::MerkleTree;
+
+    fn values(count: usize, tag: &str) -> Vec<String> {
+        (0..count).map(|i| format!("{} {}", tag, i)).collect()
+    }
+
+    #[test]
+    fn test_rebuild_matches_new() {
+        let mut tree = MerkleTree::new_with_map(&values(10, "old"), &SHA256);
+        for &count in &[10, 3, 17, 0, 1, 64, 5] {
+            let values = values(count, "new");
+            tree.rebuild(&values);
+            let expected = MerkleTree::new_with_map(&values, &SHA256);
+            assert_eq!(expected, tree);
+            assert_eq!(expected.height(), tree.height());
+            assert!(tree.has_map());
+            for v in &values {
+                assert_eq!(expected.build_proof(v), tree.build_proof(v));
+            }
+            assert!(tree.build_proof(&"old 1").is_none());
+        }
+    }
+
+    #[test]
+    fn test_rebuild_keeps_capacity() {
+        let mut tree = MerkleTree::new_with_map(&values(100, "big"), &SHA256);
+        let capacity = tree.capacity();
+        let map_size = tree.map_size();
+        tree.rebuild(&values(20, "small"));
+        assert_eq!(capacity, tree.capacity());
+        assert_eq!(map_size, tree.map_size());
+        assert!(tree.capacity() > MerkleTree::new(&values(20, "small"), &SHA256).capacity());
+        tree.rebuild(&values(100, "again"));
+        assert_eq!(capacity, tree.capacity());
+
+        tree.rebuild(&values(300, "larger"));
+        assert!(tree.capacity() > capacity);
+        assert_eq!(MerkleTree::new(&values(300, "larger"), &SHA256), tree);
+    }
+
+    #[test]
+    fn test_clear() {
+        let empty: Vec<String> = vec![];
+        let mut tree = MerkleTree::new_with_map(&values(50, "secret"), &SHA256);
+        let capacity = tree.capacity();
+        tree.clear();
+        assert!(tree.is_empty());
+        assert_eq!(0, tree.leafs_count());
+        assert_eq!(0, tree.data_size());
+        assert_eq!(&[] as &[u8], tree.get_root());
+        assert_eq!(capacity, tree.capacity());
+        assert!(tree.build_proof(&"secret 1").is_none());
+        assert_eq!(MerkleTree::new(&empty, &SHA256), tree);
+        assert_eq!(MerkleTree::new(&empty, &SHA256).height(), tree.height());
+
+        tree.rebuild(&values(30, "next"));
+        assert_eq!(MerkleTree::new(&values(30, "next"), &SHA256), tree);
+        assert_eq!(capacity, tree.capacity());
+
+        tree.clear();
+        tree.push(&"first");
+        assert_eq!(MerkleTree::new(&["first"], &SHA256), tree);
+        tree.extend(values(9, "more"));
+        let mut expected = vec!["first".to_string()];
+        expected.extend(values(9, "more"));
+        assert_eq!(MerkleTree::new(&expected, &SHA256).get_root(), tree.get_root());
+        assert!(tree.build_proof(&"more 4").is_some());
+    }
+}
+
+mod test_memory {
+    use ring::digest::SHA256;
+
+    use vmt::{MemoryUsage, MerkleTree};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_memory_usage() {
+        let values = values(100);
+        let tree = MerkleTree::new(&values, &SHA256);
+        let usage = tree.memory_usage();
+        assert_eq!(tree.data_size(), usage.nodes);
+        assert!(usage.node_capacity >= usage.nodes);
+        assert_eq!(0, usage.map);
+        assert_eq!(usage.node_capacity, usage.total());
+        assert!(MerkleTree::new_with_map(&values, &SHA256).memory_usage().map > 0);
+        let empty: Vec<String> = vec![];
+        assert_eq!(MemoryUsage { nodes: 0, node_capacity: 0, map: 0 }, MerkleTree::new(&empty, &SHA256).memory_usage());
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let values = values(100);
+        let mut tree = MerkleTree::new_with_map(&values, &SHA256);
+        tree.rebuild(&values[..10]);
+        let before = tree.memory_usage();
+        assert!(before.node_capacity > before.nodes);
+        tree.shrink_to_fit();
+        let after = tree.memory_usage();
+        assert_eq!(after.nodes, after.node_capacity);
+        assert!(after.map > 0 && after.map < before.map);
+        assert_eq!(MerkleTree::new(&values[..10], &SHA256), tree);
+        assert!(tree.build_proof(&"value 7").is_some());
+    }
+}
+
+mod test_capacity {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{CapacityError, MerkleTree};
+
+    use super::helpers::values;
+
+    // Largest leaf count whose node array fits in usize
+    fn boundary(algo: &'static ring::digest::Algorithm) -> usize {
+        let (mut low, mut high) = (0, usize::MAX);
+        while low < high {
+            let middle = low + (high - low).div_ceil(2);
+            if MerkleTree::required_size(middle, algo).is_ok() {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+        low
+    }
+
+    #[test]
+    fn test_required_size() {
+        for count in 0..40 {
+            let values = values(count);
+            assert_eq!(Ok(MerkleTree::new(&values, &SHA256).data_size()), MerkleTree::required_size(count, &SHA256));
+        }
+    }
+
+    #[test]
+    fn test_required_size_overflow() {
+        for &algo in &[&SHA256, &SHA512] {
+            let last = boundary(algo);
+            let size = MerkleTree::required_size(last, algo).unwrap();
+            assert!(size > usize::MAX - 2 * 64 * algo.output_len);
+            assert_eq!(Err(CapacityError { leafs: last + 1, digest_len: algo.output_len }),
+                       MerkleTree::required_size(last + 1, algo));
+            assert!(MerkleTree::required_size(usize::MAX, algo).is_err());
+        }
+        assert!(boundary(&SHA256) > usize::MAX / 128);
+        let error = MerkleTree::required_size(usize::MAX, &SHA256).unwrap_err();
+        assert_eq!(format!("tree over {} leafs of 32 byte digests needs more than {} bytes", usize::MAX, usize::MAX),
+                   error.to_string());
+    }
+
+    #[test]
+    fn test_try_new() {
+        let values = ["one", "two", "three"];
+        assert_eq!(MerkleTree::new(&values, &SHA256), MerkleTree::try_new(&values, &SHA256).unwrap());
+    }
+}
+
+mod test_layout_golden {
+    use ring::digest::{Context, SHA256};
+
+    use vmt::{hex, MerkleTree};
+
+    use super::helpers::values;
+
+    // Digest of the node arrays and every proof of trees over 0 to 33 leafs
+    fn digest(build: &dyn Fn(&[String]) -> MerkleTree) -> String {
+        let mut ctx = Context::new(&SHA256);
+        for count in 0..34 {
+            let values = values(count);
+            let tree = build(&values);
+            ctx.update(tree.as_bytes());
+            for i in 0..count {
+                let proof = tree.build_proof_by_index(i).unwrap();
+                ctx.update(proof.as_bytes());
+                for position in proof.positions().unwrap_or(&[]) {
+                    ctx.update(format!("{:?}", position).as_bytes());
+                }
+            }
+        }
+        hex::encode(ctx.finish().as_ref())
+    }
+
+    #[test]
+    fn test_layout_golden() {
+        assert_eq!("5484bbf8c56bd754eaee51b3b152c0317deeeeeffb32fb395f9b8162842f716b",
+                   digest(&|v| MerkleTree::new(v, &SHA256)));
+        assert_eq!("00abf1535d9365e7dc6efe04ee6479984e0fadb15110cf98f82d6a1176ce5125",
+                   digest(&|v| MerkleTree::new_domain_separated(v, &SHA256)));
+        assert_eq!("76c6197afff284a82a7a8e714fc38844f1f5722351243e658bc1ed5940d27524",
+                   digest(&|v| MerkleTree::new_rfc6962(v, &SHA256)));
+        assert_eq!("1d49e81b30a8785e647cd08bb29510ad48062158defb5fa16b942fafc10c3e1a",
+                   digest(&|v| MerkleTree::new_bitcoin(&MerkleTree::new(v, &SHA256).leaves().collect::<Vec<_>>()).unwrap()));
+    }
+    // Node arrays of trees whose levels have an odd number of nodes, duplicated or promoted
+    #[test]
+    fn test_odd_levels_golden() {
+        let digest = |tree: MerkleTree| hex::encode(ring::digest::digest(&SHA256, tree.as_bytes()).as_ref());
+        let expected = [
+            (1, "a8e7f4e418318fd25aa499a9e3a9d5d049a3f8e8b18f6152478494bd5f3d808a", "123c880df69da87059405bc3342448a287ceeae52cccd117870b540941045eed"),
+            (3, "8d5a2f63a8efcbec39b5943f3b508af972f9074a5dcf05b732b99451d2ca3cfe", "900ca6ec52afed92d137a94655c177fba0465b306c131086d7d75800db3ae936"),
+            (5, "01a08d26b5d90fd94848e96d6f7d46212dad254f32249fc692d56dc74353469f", "5293e530bb433e9282e21ebcf160250f7e0a99169c34464b3d7717cefb40610b"),
+            (7, "49e27a85c2bd182681ae8d80227668094183fc173bd0e019bd013a0d27a6c313", "697031470dd3115499e43e7eb40a3e7fb00c8c4ab9fff1c3fba76eb5a3c1a595"),
+            (9, "9b16a60c57f6b69041d2a6cb9d7cb0cdff497d5630eac5ca73f83ac9ed5f9b37", "9eca3bde8e0911fddcf38b286f70c33f0b8fcf0747529ccae6242eb85b0780dc"),
+        ];
+        for &(count, legacy, rfc6962) in &expected {
+            let values = values(count);
+            assert_eq!(legacy, digest(MerkleTree::new(&values, &SHA256)));
+            assert_eq!(rfc6962, digest(MerkleTree::new_rfc6962(&values, &SHA256)));
+        }
+    }
+}
+
+mod test_padding {
+    use ring::digest::SHA256;
+
+    use vmt::{hex, MerkleTree, MerkleTreeBuilder, PaddingStrategy};
+
+    use super::helpers::values;
+
+    const STRATEGIES: [PaddingStrategy; 3] = [PaddingStrategy::DuplicateLast, PaddingStrategy::ZeroHash, PaddingStrategy::PromoteOdd];
+
+    fn build(padding: PaddingStrategy, sorted_pairs: bool, values: &[String]) -> MerkleTree {
+        MerkleTreeBuilder::new().algorithm(&SHA256).padding(padding).sorted_pairs(sorted_pairs).build(values).unwrap()
+    }
+
+    #[test]
+    fn test_padding_roots() {
+        let expected = [
+            (PaddingStrategy::DuplicateLast, "f54a9d0e8aa11ce3d72d65e79e1262da64c728b96408dfe7c7cfc5ea9a45b3df",
+             "096652ba5bc3ccfbf255fc9ad3ad95a9815c18daab382b1c53beb4880c04dbe8"),
+            (PaddingStrategy::ZeroHash, "eeb99ac03458d411f6d8bf56e641f27c93d2072630ee5c73a465a6bf8dedd17c",
+             "2bd0f00013af70447db578b0e60e8979746775990c8698ed0c589fedf7364f61"),
+            (PaddingStrategy::PromoteOdd, "4dd4c4cbdf596d0cbc91fe166267c664f512c7d070689bc1f556531112818eb2",
+             "c0206692d4edcf381b7e7e20bbda35ccee8a22183da261b8f1dcd7290be14a35"),
+        ];
+        for &(padding, three, five) in &expected {
+            assert_eq!(three, hex::encode(build(padding, true, &values(3)).get_root()));
+            assert_eq!(five, hex::encode(build(padding, true, &values(5)).get_root()));
+        }
+        assert_eq!(MerkleTree::new(&values(5), &SHA256), build(PaddingStrategy::DuplicateLast, true, &values(5)));
+    }
+
+    #[test]
+    fn test_padding_proofs() {
+        for &padding in &STRATEGIES {
+            for &sorted_pairs in &[true, false] {
+                for count in 1..20 {
+                    let values = values(count);
+                    let tree = build(padding, sorted_pairs, &values);
+                    for v in &values {
+                        let proof = tree.build_proof(v).unwrap();
+                        assert!(tree.validate(&proof), "{:?} {} {}", padding, count, v);
+                    }
+                    let indices: Vec<usize> = (0..count).step_by(3).collect();
+                    assert!(tree.validate_multiproof(&tree.build_multiproof(&indices).unwrap()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_promote_odd_shorter_paths() {
+        let tree = build(PaddingStrategy::PromoteOdd, true, &values(5));
+        assert_eq!(4, tree.build_proof_by_index(0).unwrap().as_bytes().len() / 32);
+        assert_eq!(2, tree.build_proof_by_index(4).unwrap().as_bytes().len() / 32);
+        let tree = build(PaddingStrategy::ZeroHash, true, &values(5));
+        assert_eq!(4, tree.build_proof_by_index(4).unwrap().as_bytes().len() / 32);
+        assert_eq!(Some(&[0u8; 32][..]), tree.build_proof_by_index(4).unwrap().siblings().next());
+    }
+
+    #[test]
+    fn test_padding_updates() {
+        for &padding in &STRATEGIES {
+            let mut tree = build(padding, true, &values(4));
+            for count in 5..12 {
+                tree.push(&format!("value {}", count - 1));
+                assert_eq!(build(padding, true, &values(count)), tree);
+            }
+            tree.update_leaf(10, &"changed").unwrap();
+            let mut changed = values(11);
+            changed[10] = "changed".to_string();
+            assert_eq!(build(padding, true, &changed), tree);
+
+            let mut bytes = vec![];
+            tree.write_to(&mut bytes).unwrap();
+            assert_eq!(tree, MerkleTree::read_from(&bytes[..], true).unwrap());
+        }
+    }
+}
+
+mod test_salted {
+    use ring::digest::SHA256;
+    use ring::rand::SystemRandom;
+
+    use vmt::{LoadError, MerkleTree, SaltedMerkleTree, Salts, SALT_LEN};
+
+    const VALUES: [&str; 5] = ["alice", "bob", "carol", "dave", "eve"];
+
+    #[test]
+    fn test_salted_roots_differ() {
+        let rng = SystemRandom::new();
+        let first = MerkleTree::new_salted(&VALUES, &SHA256, &rng).unwrap();
+        let second = MerkleTree::new_salted(&VALUES, &SHA256, &rng).unwrap();
+        assert_ne!(first.get_root(), second.get_root());
+        assert_ne!(MerkleTree::new(&VALUES, &SHA256).get_root(), first.get_root());
+        assert_ne!(first.salt(0), second.salt(0));
+        assert_eq!(SALT_LEN, first.salt(4).unwrap().len());
+        assert_eq!(None, first.salt(5));
+    }
+
+    #[test]
+    fn test_salted_proofs() {
+        let rng = SystemRandom::new();
+        let tree = MerkleTree::new_salted(&VALUES, &SHA256, &rng).unwrap();
+        let root = tree.get_root();
+        for (i, value) in VALUES.iter().enumerate() {
+            let (salt, proof) = tree.build_proof_with_salt(i).unwrap();
+            assert!(vmt::verify_salted_value(root, value, salt, proof.as_bytes(), &SHA256));
+            assert!(!vmt::verify_salted_value(root, &"mallory", salt, proof.as_bytes(), &SHA256));
+            let other = tree.salt((i + 1) % VALUES.len()).unwrap();
+            assert!(!vmt::verify_salted_value(root, value, other, proof.as_bytes(), &SHA256));
+            assert!(!vmt::verify_value(root, value, proof.as_bytes(), &SHA256));
+        }
+        assert!(tree.build_proof_with_salt(5).is_none());
+    }
+
+    #[test]
+    fn test_salts_stored_apart() {
+        let rng = SystemRandom::new();
+        let (tree, salts) = MerkleTree::new_salted(&VALUES, &SHA256, &rng).unwrap().into_parts();
+        let bytes = salts.as_bytes().to_vec();
+        assert_eq!(VALUES.len() * SALT_LEN, bytes.len());
+        let salts = Salts::from_bytes(&bytes).unwrap();
+        assert!(Salts::from_bytes(&bytes[1..]).is_err());
+        assert!(matches!(SaltedMerkleTree::from_parts(MerkleTree::new(&VALUES[..4], &SHA256), salts.clone()),
+                         Err(LoadError::BadLength { .. })));
+        let tree = SaltedMerkleTree::from_parts(tree, salts).unwrap();
+        let (salt, proof) = tree.build_proof_with_salt(2).unwrap();
+        assert!(vmt::verify_salted_value(tree.get_root(), &"carol", salt, proof.as_bytes(), &SHA256));
+    }
+
+    #[test]
+    fn test_malformed_salt_length() {
+        for &len in &[1, SALT_LEN - 1, SALT_LEN + 1, 3 * SALT_LEN - 5] {
+            assert_eq!(Err(LoadError::NotMultiple { got: len, multiple: SALT_LEN }), Salts::from_bytes(&vec![0; len]).map(|_| ()));
+        }
+        assert_eq!(0, Salts::from_bytes(&[]).unwrap().len());
+        assert_eq!(2, Salts::from_bytes(&[0; 2 * SALT_LEN]).unwrap().len());
+    }
+}
+
+mod test_double_hash_leaves {
+    use ring::digest::SHA256;
+
+    use vmt::{hex, MerkleTree, MerkleTreeBuilder};
+
+    use super::helpers::values;
+
+    fn build<T: AsRef<[u8]>>(values: &[T], use_map: bool) -> MerkleTree {
+        MerkleTreeBuilder::new().algorithm(&SHA256).double_hash_leaves(true).use_map(use_map).build(values).unwrap()
+    }
+
+    #[test]
+    fn test_double_hash_roots() {
+        assert_eq!("3334bf169bd4337da65ca7ed1b63c09fa0b77886bedf7cd0cc4b9353dd07dd59",
+                   hex::encode(build(&["a", "b", "c"], false).get_root()));
+        assert_eq!("fe6d1a83ed5b116f4e61ac59d42668258e169e5998e3986e189a0fc72cc40487",
+                   hex::encode(build(&["a", "b", "c", "d", "e"], false).get_root()));
+        assert_eq!("ea54cb990986e8cd5e4aa21149b7170065730980102a64b4b2a9af1135191bbe",
+                   hex::encode(build(&["one"], false).get_root()));
+        let hashes: Vec<_> = ["a", "b", "c"].iter().map(|v| vmt::get_hash(v.as_bytes(), &SHA256)).collect();
+        assert_eq!(MerkleTree::new(&hashes, &SHA256).get_root(), build(&["a", "b", "c"], false).get_root());
+    }
+
+    #[test]
+    fn test_double_hash_proofs() {
+        let values = values(9);
+        for &use_map in &[false, true] {
+            let mut tree = build(&values, use_map);
+            for (i, v) in values.iter().enumerate() {
+                let proof = tree.build_proof(v).unwrap();
+                assert_eq!(tree.build_proof_by_index(i), Some(proof.clone()));
+                assert!(tree.validate(&proof));
+                assert_eq!(vec![i], tree.indices_of(v));
+            }
+            assert!(tree.build_proof(&vmt::get_hash(values[0].as_bytes(), &SHA256).as_ref().to_vec()).is_none());
+            tree.push(&"pushed");
+            assert!(tree.build_proof(&"pushed").is_some());
+
+            let mut bytes = vec![];
+            tree.write_to(&mut bytes).unwrap();
+            let read: MerkleTree = MerkleTree::read_from(&bytes[..], true).unwrap();
+            assert!(read.validate(&read.build_proof(&"value 3").unwrap()));
+        }
+    }
+}
+
+mod test_leaf_hasher {
+    use ring::digest::{Context, SHA256};
+
+    use vmt::MerkleTree;
+
+    struct Record {
+        name: String,
+        balance: u64,
+    }
+
+    // Version byte, then the length prefixed name and the balance
+    fn encode(record: &Record, ctx: &mut Context) {
+        ctx.update(&[1]);
+        ctx.update(&(record.name.len() as u32).to_be_bytes());
+        ctx.update(record.name.as_bytes());
+        ctx.update(&record.balance.to_be_bytes());
+    }
+
+    fn records() -> Vec<Record> {
+        (0..7).map(|i| Record { name: format!("account {}", i), balance: i * 100 }).collect()
+    }
+
+    #[test]
+    fn test_leaf_hasher_proofs() {
+        let records = records();
+        let tree = MerkleTree::new_with_leaf_hasher(&records, &SHA256, encode);
+        for (i, record) in records.iter().enumerate() {
+            let proof = tree.build_proof_with_leaf_hasher(record, encode).unwrap();
+            assert_eq!(tree.build_proof_by_index(i), Some(proof.clone()));
+            assert!(tree.validate(&proof));
+            assert!(vmt::verify_proof(tree.get_root(), proof.as_bytes(), &SHA256));
+        }
+        let missing = Record { name: "account 1".to_string(), balance: 101 };
+        assert!(tree.build_proof_with_leaf_hasher(&missing, encode).is_none());
+    }
+
+    #[test]
+    fn test_leaf_hasher_leafs() {
+        let records = records();
+        let tree = MerkleTree::new_with_leaf_hasher(&records, &SHA256, encode);
+        let mut bytes = vec![1];
+        bytes.extend_from_slice(&9u32.to_be_bytes());
+        bytes.extend_from_slice(b"account 2");
+        bytes.extend_from_slice(&200u64.to_be_bytes());
+        assert_eq!(vmt::get_hash(&bytes, &SHA256).as_ref(), tree.leaves().nth(2).unwrap());
+        let plain: Vec<_> = records.iter().map(|r| r.name.clone()).collect();
+        assert_ne!(MerkleTree::new(&plain, &SHA256).get_root(), tree.get_root());
+    }
+}
+
+#[cfg(feature = "serde")]
+mod test_from_serialize {
+    use std::collections::HashMap;
+
+    use ring::digest::SHA256;
+    use serde::Serialize;
+    use serde_json;
+
+    use vmt::{hex, MerkleTree};
+
+    #[derive(Serialize)]
+    struct Account {
+        name: String,
+        balance: u64,
+        tags: HashMap<String, serde_json::Value>,
+        history: Vec<u64>,
+    }
+
+    fn accounts() -> Vec<Account> {
+        (0..3).map(|i| {
+            let mut tags = HashMap::new();
+            tags.insert("zone".to_string(), serde_json::Value::from("eu"));
+            tags.insert("active".to_string(), serde_json::Value::from(i % 2 == 0));
+            Account { name: format!("account {}", i), balance: i * 100, tags, history: vec![i, i + 1] }
+        }).collect()
+    }
+
+    // Pinned: a change of the canonical encoding changes every root built with it
+    #[test]
+    fn test_from_serialize_root() {
+        let tree = MerkleTree::from_serialize(&accounts(), &SHA256).unwrap();
+        assert_eq!("0bf2362dd9e431039c0c9e4cbf8cb7f0145f16a1a053d40f847489bee12168ef", hex::encode(tree.get_root()));
+        let first = br#"{"balance":0,"history":[0,1],"name":"account 0","tags":{"active":true,"zone":"eu"}}"#;
+        assert_eq!(vmt::get_hash(first, &SHA256).as_ref(), tree.leaves().next().unwrap());
+    }
+
+    #[test]
+    fn test_build_proof_serialized() {
+        let mut values = accounts();
+        let tree = MerkleTree::from_serialize(&values, &SHA256).unwrap();
+        for (i, account) in values.iter().enumerate() {
+            let proof = tree.build_proof_serialized(account).unwrap();
+            assert_eq!(tree.build_proof_by_index(i), Some(proof.clone()));
+            assert!(tree.validate(&proof));
+        }
+        values[1].balance += 1;
+        assert!(tree.build_proof_serialized(&values[1]).is_none());
+    }
+
+    #[test]
+    fn test_from_serialize_error() {
+        let mut bad = HashMap::new();
+        bad.insert(vec![1u8], 1);
+        let values = vec![HashMap::new(), bad];
+        let error = MerkleTree::from_serialize(&values, &SHA256).unwrap_err();
+        assert_eq!(1, error.index);
+        assert!(MerkleTree::new(&["x"], &SHA256).build_proof_serialized(&values[1]).is_none());
+    }
+}
+
+mod test_entries {
+    use ring::digest::SHA256;
+
+    use vmt::MerkleTree;
+
+    #[test]
+    fn test_entries_are_unambiguous() {
+        let first = MerkleTree::from_entries(&[("ab", "c")], &SHA256);
+        let second = MerkleTree::from_entries(&[("a", "bc")], &SHA256);
+        assert_ne!(first.get_root(), second.get_root());
+        assert_ne!(MerkleTree::new(&["abc"], &SHA256).get_root(), first.get_root());
+        assert!(first.build_proof_for_key("ab", "c").is_some());
+        assert!(first.build_proof_for_key("a", "bc").is_none());
+    }
+
+    #[test]
+    fn test_entries_leaf() {
+        let tree = MerkleTree::from_entries(&[("key", "value")], &SHA256);
+        let mut bytes = 3u64.to_le_bytes().to_vec();
+        bytes.extend_from_slice(b"keyvalue");
+        assert_eq!(vmt::get_hash(&bytes, &SHA256).as_ref(), tree.leaves().next().unwrap());
+    }
+
+    #[test]
+    fn test_entries_proofs() {
+        let entries: Vec<(String, Vec<u8>)> = (0..9).map(|i| (format!("key {}", i), vec![i; i as usize])).collect();
+        let tree = MerkleTree::from_entries(&entries, &SHA256);
+        for (i, (key, value)) in entries.iter().enumerate() {
+            let proof = tree.build_proof_for_key(key, value).unwrap();
+            assert_eq!(tree.build_proof_by_index(i), Some(proof.clone()));
+            assert!(vmt::verify_proof(tree.get_root(), proof.as_bytes(), &SHA256));
+        }
+        assert!(tree.build_proof_for_key("key 1", [2]).is_none());
+    }
+}
+
+#[cfg(feature = "serde")]
+mod test_vectors {
+    use vmt::vectors::{self, TestVectors};
+    use vmt::{hex, NamedHasher, RingHasher};
+
+    #[test]
+    fn test_frozen_vectors() {
+        for json in &vectors::FROZEN {
+            let frozen = TestVectors::from_json(json).unwrap();
+            let algo = RingHasher::from_name(&frozen.algorithm).unwrap().algorithm();
+            assert_eq!(frozen, vectors::generate(frozen.seed, frozen.values.len(), algo));
+            let root = hex::decode(&frozen.root).unwrap();
+            for (value, proof) in frozen.values.iter().zip(&frozen.proofs) {
+                let value = hex::decode(value).unwrap();
+                assert!(vmt::verify_value(&root, &value, &hex::decode(proof).unwrap(), algo));
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_is_deterministic() {
+        let algo = &ring::digest::SHA256;
+        assert_eq!(vectors::generate(5, 10, algo), vectors::generate(5, 10, algo));
+        assert_ne!(vectors::generate(5, 10, algo).values, vectors::generate(6, 10, algo).values);
+        assert_eq!(vectors::generate(5, 3, algo).values[..], vectors::generate(5, 10, algo).values[..3]);
+        let generated = vectors::generate(5, 10, algo);
+        assert_eq!(generated, TestVectors::from_json(&generated.to_json()).unwrap());
+    }
+}
+
+mod test_roots_equal {
+    use ring::digest::SHA256;
+
+    use vmt::MerkleTree;
+
+    #[test]
+    fn test_roots_equal() {
+        let root = MerkleTree::new(&["a", "b", "c"], &SHA256).get_root().to_vec();
+        assert!(vmt::roots_equal(&root, &root.clone()));
+        let mut other = root.clone();
+        other[31] ^= 1;
+        assert!(!vmt::roots_equal(&root, &other));
+        assert!(!vmt::roots_equal(&root, &root[..31]));
+        assert!(!vmt::roots_equal(&root[..0], &root));
+        assert!(vmt::roots_equal(&[], &[]));
+    }
+}
+
+#[cfg(feature = "zeroize")]
+mod test_zeroize {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, Proof};
+    use zeroize::{Zeroize, ZeroizeOnDrop};
+
+    use super::helpers::values;
+
+    fn wiped_on_drop<T: Zeroize + ZeroizeOnDrop>() {}
+
+    #[test]
+    fn test_traits() {
+        wiped_on_drop::<MerkleTree>();
+        wiped_on_drop::<Proof>();
+    }
+
+    #[test]
+    fn test_zeroize_tree() {
+        let values: Vec<String> = (0..10).map(|i| format!("secret {}", i)).collect();
+        let mut tree = MerkleTree::new_with_map(&values, &SHA256);
+        let capacity = tree.capacity();
+        tree.zeroize();
+        assert!(tree.is_empty());
+        assert_eq!(MerkleTree::new(&Vec::<String>::new(), &SHA256).as_bytes(), tree.as_bytes());
+        assert_eq!(capacity, tree.capacity()); // Zeroed in place, kept for the next build
+        assert!(tree.build_proof(&"secret 3").is_none());
+
+        tree.rebuild(&values);
+        assert_eq!(MerkleTree::new(&values, &SHA256), tree);
+        assert!(tree.build_proof(&"secret 3").is_some());
+    }
+
+    #[test]
+    fn test_zeroize_proof() {
+        let values = vec!["a", "b", "c", "d", "e"];
+        let tree = MerkleTree::new(&values, &SHA256);
+        let mut proof = tree.build_proof(&"c").unwrap();
+        let bytes = proof.as_bytes().to_vec();
+        assert!(tree.validate(&proof));
+        assert_eq!(bytes, proof.clone().into_bytes());
+        proof.zeroize();
+        assert!(proof.as_bytes().is_empty());
+        assert!(proof.positions().is_none());
+        assert!(!tree.validate(&proof));
+    }
+
+    #[test]
+    fn test_unaffected() {
+        let values = values(33);
+        let tree = MerkleTree::new(&values, &SHA256);
+        for (i, value) in values.iter().enumerate() {
+            let proof = tree.build_proof(value).unwrap();
+            assert!(tree.validate(&proof), "leaf {}", i);
+        }
+        let root = tree.get_root().to_vec();
+        drop(tree);
+        assert_eq!(&root[..], MerkleTree::new(&values, &SHA256).get_root());
+    }
+}
+
+mod test_proof_path {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, MerkleTreeBuilder, Proof, ProofStep, StepPosition};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_sorted_path() {
+        let tree = MerkleTree::new(&values(9), &SHA256);
+        let proof = tree.build_proof(&"value 4").unwrap();
+        let steps: Vec<ProofStep> = proof.path().collect();
+        assert_eq!(4, steps.len());
+        for (step, sibling) in steps.iter().zip(proof.siblings()) {
+            assert_eq!(sibling, step.sibling);
+            assert_eq!(StepPosition::Unordered, step.position);
+        }
+        assert_eq!(tree.node(0, 5).unwrap(), steps[0].sibling);
+        assert_eq!(tree.node(1, 3).unwrap(), steps[1].sibling);
+        assert_eq!(tree.node(2, 0).unwrap(), steps[2].sibling);
+        assert_eq!(tree.node(3, 1).unwrap(), steps[3].sibling);
+    }
+
+    #[test]
+    fn test_ordered_path() {
+        let tree = MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false).build(&values(9)).unwrap();
+        let proof = tree.build_proof_by_index(4).unwrap();
+        let path: Vec<(&[u8], StepPosition)> = proof.path().map(|step| (step.sibling, step.position)).collect();
+        assert_eq!(vec![
+            (tree.node(0, 5).unwrap(), StepPosition::Right),
+            (tree.node(1, 3).unwrap(), StepPosition::Right),
+            (tree.node(2, 0).unwrap(), StepPosition::Left),
+            (tree.node(3, 1).unwrap(), StepPosition::Right),
+        ], path);
+
+        // The last leaf is paired with its own copy
+        let proof = tree.build_proof_by_index(8).unwrap();
+        let first = proof.path().next().unwrap();
+        assert_eq!(proof.leaf_hash(), first.sibling);
+        assert_eq!(StepPosition::Right, first.position);
+    }
+
+    #[test]
+    fn test_single_leaf_path() {
+        let proof = Proof::with_positions(&[7; 32], 32, vec![]).unwrap();
+        assert_eq!(0, proof.path().count());
+    }
+}
+
+mod test_proof_len {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{MerkleTree, MerkleTreeBuilder, PaddingStrategy};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_expected_proof_len() {
+        for count in 1..34 {
+            let values = values(count);
+            for algo in [&SHA256, &SHA512] {
+                let tree = MerkleTree::new(&values, algo);
+                assert_eq!(vmt::proof_len_for(count, algo), tree.expected_proof_len(), "{} leafs", count);
+                for index in 0..count {
+                    let proof = tree.build_proof_by_index(index).unwrap();
+                    assert_eq!(tree.expected_proof_len(), proof.as_bytes().len(), "{} of {} leafs", index, count);
+                    assert_eq!(proof.hash_count() * algo.output_len, proof.as_bytes().len());
+                }
+            }
+        }
+        assert_eq!(64, vmt::proof_len_for(1, &SHA256)); // Leaf and its copy
+        assert_eq!(0, vmt::proof_len_for(0, &SHA256));
+    }
+
+    #[test]
+    fn test_promoted_proof_len() {
+        for count in 1..34 {
+            let values = values(count);
+            let tree = MerkleTreeBuilder::new().algorithm(&SHA256).padding(PaddingStrategy::PromoteOdd).build(&values).unwrap();
+            let longest = (0..count).map(|i| tree.build_proof_by_index(i).unwrap().as_bytes().len()).max();
+            assert_eq!(Some(tree.expected_proof_len()), longest, "{} leafs", count);
+        }
+    }
+}
+
+mod test_hash_count {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, MerkleTreeBuilder, PaddingStrategy, Proof, ValidationError};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_one_too_few() {
+        for count in 3..10 { // Proofs of smaller trees have no hash to spare
+            let tree = MerkleTree::new(&values(count), &SHA256);
+            let proof = tree.build_proof_by_index(count - 1).unwrap();
+            let bytes = proof.as_bytes();
+            let short = Proof::from_bytes(&bytes[..bytes.len() - 32], 32).unwrap();
+            let expected = proof.hash_count();
+            assert_eq!(Err(ValidationError::HashCount { got: expected - 1, expected }), tree.try_validate(&short), "{} leafs", count);
+            assert!(!tree.validate(&short));
+        }
+    }
+
+    #[test]
+    fn test_one_too_many() {
+        for count in 1..10 {
+            let tree = MerkleTree::new(&values(count), &SHA256);
+            let proof = tree.build_proof_by_index(0).unwrap();
+            let mut bytes = proof.as_bytes().to_vec();
+            bytes.extend_from_slice(tree.get_root());
+            let long = Proof::from_bytes(&bytes, 32).unwrap();
+            let expected = proof.hash_count();
+            assert_eq!(Err(ValidationError::HashCount { got: expected + 1, expected }), tree.try_validate(&long), "{} leafs", count);
+            assert!(tree.validate(&proof));
+        }
+    }
+
+    #[test]
+    fn test_promoted_shorter_proofs() {
+        let tree = MerkleTreeBuilder::new().algorithm(&SHA256).padding(PaddingStrategy::PromoteOdd).sorted_pairs(false).build(&values(5)).unwrap();
+        let promoted = tree.build_proof_by_index(4).unwrap();
+        assert!(promoted.hash_count() < tree.build_proof_by_index(0).unwrap().hash_count());
+        assert!(tree.validate(&promoted));
+        let proof = tree.build_proof_by_index(0).unwrap();
+        let mut positions = proof.positions().unwrap().to_vec();
+        positions.push(positions[0]);
+        let mut bytes = proof.as_bytes().to_vec();
+        bytes.extend_from_slice(tree.get_root());
+        let long = Proof::with_positions(&bytes, 32, positions).unwrap();
+        assert_eq!(Err(ValidationError::HashCount { got: 5, expected: 4 }), tree.try_validate(&long));
+    }
+}
+
+mod test_alignment {
+    use ring::digest::SHA512;
+
+    use vmt::{hex, MerkleTree, Proof, ProofFormatError, ValidationError};
+
+    // Every length up to four digests, bytes from a fixed xorshift stream
+    fn inputs() -> Vec<Vec<u8>> {
+        let mut state: u32 = 0x9e37_79b9;
+        (0..4 * 64).map(|len| (0..len).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        }).collect()).collect()
+    }
+
+    fn classify(len: usize) -> Result<(), ProofFormatError> {
+        match len {
+            _ if !len.is_multiple_of(64) => Err(ProofFormatError::NotAligned { len, digest_len: 64 }),
+            0 | 64 => Err(ProofFormatError::TooShort { hashes: len / 64 }),
+            _ => Ok(()),
+        }
+    }
+
+    #[test]
+    fn test_parsers_agree() {
+        let tree = MerkleTree::new(&["a", "b", "c", "d"], &SHA512);
+        for bytes in inputs() {
+            let expected = classify(bytes.len());
+            assert_eq!(expected, Proof::from_bytes(&bytes, 64).map(|_| ()), "{} bytes", bytes.len());
+            assert_eq!(expected, hex::decode_proof(&hex::encode(&bytes), &SHA512).map(|_| ()).map_err(|e| match e {
+                hex::HexError::Format(e) => e,
+                e => panic!("unexpected {:?}", e),
+            }));
+            assert!(!tree.validate_bytes(&bytes));
+            assert!(!vmt::verify_proof(tree.get_root(), &bytes, &SHA512));
+        }
+    }
+
+    #[test]
+    fn test_try_validate_not_aligned() {
+        let tree = MerkleTree::new(&["a", "b", "c", "d"], &SHA512);
+        for bytes in inputs() {
+            // Proofs of other digest lengths are the only way to hand the tree misaligned bytes
+            for digest_len in [1, 16, 24, 32, 48] {
+                let proof = match Proof::from_bytes(&bytes, digest_len) {
+                    Ok(proof) => proof,
+                    Err(_) => continue,
+                };
+                let result = tree.try_validate(&proof);
+                if !bytes.len().is_multiple_of(64) {
+                    assert_eq!(Err(ValidationError::Format(ProofFormatError::NotAligned { len: bytes.len(), digest_len: 64 })), result);
+                } else {
+                    assert_eq!(Err(ValidationError::DigestMismatch { got: digest_len, expected: 64 }), result);
+                }
+                assert!(!tree.validate(&proof));
+            }
+        }
+    }
+}
+
+mod test_validate_batch {
+    use ring::digest::SHA256;
+
+    use vmt::MerkleTree;
+
+    use super::helpers::values;
+
+    // Every third proof has a flipped bit, every fifth is cut short
+    fn proofs(tree: &MerkleTree) -> (Vec<Vec<u8>>, Vec<bool>) {
+        (0..tree.leafs_count()).map(|i| {
+            let mut bytes = tree.build_proof_by_index(i).unwrap().into_bytes();
+            if i % 5 == 0 {
+                bytes.truncate(bytes.len() - 1);
+            } else if i % 3 == 0 {
+                bytes[40] ^= 1;
+            }
+            let valid = i % 5 != 0 && i % 3 != 0;
+            (bytes, valid)
+        }).unzip()
+    }
+
+    #[test]
+    fn test_validate_batch() {
+        let tree = MerkleTree::new(&values(100), &SHA256);
+        let (proofs, expected) = proofs(&tree);
+        assert_eq!(expected, tree.validate_batch(&proofs));
+        let serial: Vec<bool> = proofs.iter().map(|p| tree.validate_bytes(p)).collect();
+        assert_eq!(serial, tree.validate_batch(&proofs));
+        assert!(tree.validate_batch::<Vec<u8>>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_validate_values_batch() {
+        let values = values(100);
+        let tree = MerkleTree::new(&values, &SHA256);
+        let (proofs, mut expected) = proofs(&tree);
+        let mut pairs: Vec<(&str, Vec<u8>)> = values.iter().map(|v| v.as_str()).zip(proofs).collect();
+        pairs[1].0 = &values[2]; // Valid proofs of other values
+        pairs[2].0 = &values[1];
+        expected[1] = false;
+        expected[2] = false;
+        assert_eq!(expected, tree.validate_values_batch(&pairs));
+    }
+}
+
+mod test_integrity {
+    use ring::digest::SHA256;
+
+    use vmt::{IntegrityError, MerkleTree, MerkleTreeBuilder, PaddingStrategy};
+
+    use super::helpers::values;
+
+    // Nine leafs: levels of 9, 5, 3, 2 and 1 nodes start at nodes 0, 10, 16, 20 and 22
+    const STARTS: [usize; 5] = [0, 10, 16, 20, 22];
+
+    fn corrupted(level: usize, index: usize) -> MerkleTree {
+        let mut bytes = MerkleTree::new(&values(9), &SHA256).as_bytes().to_vec();
+        bytes[(STARTS[level] + index) * 32 + 5] ^= 0x40;
+        MerkleTree::from_bytes(&bytes, 9, &SHA256).unwrap()
+    }
+
+    #[test]
+    fn test_intact() {
+        for count in 0..34 {
+            assert_eq!(Ok(()), MerkleTree::new(&values(count), &SHA256).verify_integrity(), "{} leafs", count);
+            for padding in [PaddingStrategy::ZeroHash, PaddingStrategy::PromoteOdd] {
+                let tree = MerkleTreeBuilder::new().algorithm(&SHA256).padding(padding).build(&values(count)).unwrap();
+                assert_eq!(Ok(()), tree.verify_integrity());
+            }
+        }
+    }
+
+    #[test]
+    fn test_corrupted_node() {
+        for &(level, index) in [(1, 2), (1, 4), (2, 0), (2, 2), (3, 1), (4, 0)].iter() {
+            assert_eq!(Err(IntegrityError { level, index }), corrupted(level, index).verify_integrity());
+        }
+    }
+
+    #[test]
+    fn test_corrupted_padding() {
+        for &(level, index) in [(0, 9), (1, 5), (2, 3)].iter() {
+            assert_eq!(Err(IntegrityError { level, index }), corrupted(level, index).verify_integrity());
+        }
+        let tree = MerkleTreeBuilder::new().algorithm(&SHA256).padding(PaddingStrategy::ZeroHash).build(&values(3)).unwrap();
+        let mut bytes = tree.as_bytes().to_vec();
+        bytes[3 * 32] = 1; // Zero padding of the leafs
+        let mut stored = vec![];
+        tree.write_to(&mut stored).unwrap();
+        let offset = stored.len() - bytes.len();
+        stored[offset..].copy_from_slice(&bytes);
+        let tree: MerkleTree = MerkleTree::read_from(&stored[..], false).unwrap();
+        assert_eq!(Err(IntegrityError { level: 0, index: 3 }), tree.verify_integrity());
+    }
+
+    #[test]
+    fn test_corrupted_leaf() {
+        // A leaf is not checked on its own, its parent no longer matches it
+        assert_eq!(Err(IntegrityError { level: 1, index: 1 }), corrupted(0, 3).verify_integrity());
+        // Or its padding copy, which is checked before the level above
+        assert_eq!(Err(IntegrityError { level: 0, index: 9 }), corrupted(0, 8).verify_integrity());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("node 3 of level 2 differs from the recomputed one", IntegrityError { level: 2, index: 3 }.to_string());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel() {
+        let tree = MerkleTree::new(&values(1000), &SHA256);
+        assert_eq!(Ok(()), tree.verify_integrity_parallel());
+        for level in 0..5 {
+            for index in 0..[10, 6, 4, 2, 1][level] {
+                let tree = corrupted(level, index);
+                assert_eq!(tree.verify_integrity(), tree.verify_integrity_parallel());
+            }
+        }
+    }
+}
+
+mod test_validation_trace {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, MerkleTreeBuilder, PaddingStrategy, Proof, ValidationError, ValidationTrace};
+
+    use super::helpers::values;
+
+    fn trace(tree: &MerkleTree, proof: &Proof) -> ValidationTrace {
+        match tree.try_validate(proof) {
+            Err(ValidationError::RootMismatch { trace, .. }) => trace,
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    // Proof of leaf 4 of 9 with one byte flipped: leaf hash and four siblings
+    fn tampered(tree: &MerkleTree, byte: usize) -> Proof {
+        let proof = tree.build_proof_by_index(4).unwrap();
+        let mut bytes = proof.as_bytes().to_vec();
+        bytes[byte] ^= 1;
+        match proof.positions() {
+            Some(positions) => Proof::with_positions(&bytes, 32, positions.to_vec()).unwrap(),
+            None => Proof::from_bytes(&bytes, 32).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_wrong_leaf() {
+        let tree = MerkleTree::new(&values(9), &SHA256);
+        let trace = trace(&tree, &tampered(&tree, 0));
+        assert_eq!(Some(0), trace.first_mismatch());
+        assert_eq!(5, trace.len());
+    }
+
+    #[test]
+    fn test_wrong_middle_sibling() {
+        let tree = MerkleTree::new(&values(9), &SHA256);
+        let trace = trace(&tree, &tampered(&tree, 2 * 32 + 7)); // Second sibling
+        assert_eq!(Some(2), trace.first_mismatch());
+        assert_eq!(tree.node(0, 4), trace.step(0));
+        assert_eq!(tree.node(1, 2), trace.step(1));
+        assert_ne!(tree.node(2, 1), trace.step(2));
+    }
+
+    #[test]
+    fn test_wrong_last_sibling() {
+        let tree = MerkleTree::new(&values(9), &SHA256);
+        let proof = tampered(&tree, 4 * 32 + 31);
+        let trace = trace(&tree, &proof);
+        assert_eq!(Some(4), trace.first_mismatch()); // Only the root differs
+        let good: Vec<&[u8]> = (0..4).map(|level| tree.node(level, 4 >> level).unwrap()).collect();
+        assert_eq!(good, trace.hashes().take(4).collect::<Vec<_>>());
+        match tree.try_validate(&proof) {
+            Err(ValidationError::RootMismatch { computed, .. }) => assert_eq!(trace.step(4), Some(&computed[..])),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_ordered_and_promoted() {
+        let tree = MerkleTreeBuilder::new().algorithm(&SHA256).sorted_pairs(false).build(&values(9)).unwrap();
+        assert_eq!(Some(3), trace(&tree, &tampered(&tree, 3 * 32)).first_mismatch());
+
+        let tree = MerkleTreeBuilder::new().algorithm(&SHA256).padding(PaddingStrategy::PromoteOdd).build(&values(9)).unwrap();
+        let proof = tree.build_proof_by_index(8).unwrap(); // Promoted up to the level below the root
+        let mut bytes = proof.as_bytes().to_vec();
+        bytes[32] ^= 1;
+        let trace = trace(&tree, &Proof::from_bytes(&bytes, 32).unwrap());
+        assert_eq!(2, trace.len());
+        assert_eq!(Some(1), trace.first_mismatch());
+    }
+}
+
+#[cfg(feature = "signing")]
+mod test_signed_root {
+    use ring::digest::SHA256;
+    use ring::signature::Ed25519KeyPair;
+    use untrusted::Input;
+
+    use vmt::{hex, MerkleTree, SignedRoot};
+
+    fn key_pair(seed: u8) -> Ed25519KeyPair {
+        Ed25519KeyPair::from_seed_unchecked(Input::from(&[seed; 32])).unwrap()
+    }
+
+    fn signed() -> (SignedRoot, Vec<u8>) {
+        let key = key_pair(1);
+        let tree = MerkleTree::new(&["a", "b", "c"], &SHA256);
+        (tree.sign_root(&key, 1_700_000_000), key.public_key_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let (signed, public_key) = signed();
+        assert_eq!("sha256", signed.algorithm);
+        assert_eq!(3, signed.leaf_count);
+        assert_eq!(64, signed.signature.len());
+        assert!(signed.verify(&public_key));
+        assert!(!signed.verify(key_pair(2).public_key_bytes()));
+        assert!(!signed.verify(&[]));
+    }
+
+    #[test]
+    fn test_signed_bytes() {
+        let (signed, _) = signed();
+        let expected = format!("{}01{:02x}{}{}{}20{}", hex::encode(b"VMT-ROOT"), signed.algorithm.len(), hex::encode(signed.algorithm.as_bytes()),
+                               "0300000000000000", "00f1536500000000", hex::encode(&signed.root));
+        assert_eq!(expected, hex::encode(&signed.signed_bytes()));
+    }
+
+    #[test]
+    fn test_tampered_fields() {
+        let (signed, public_key) = signed();
+        let mut tampered = vec![signed.clone(); 5];
+        tampered[0].algorithm = "sha512".to_string();
+        tampered[1].leaf_count += 1;
+        tampered[2].timestamp += 1;
+        tampered[3].root[0] ^= 1;
+        tampered[4].signature[10] ^= 1;
+        for (i, t) in tampered.iter().enumerate() {
+            assert!(!t.verify(&public_key), "field {}", i);
+        }
+        let mut long = signed.clone();
+        long.root = vec![0; 256];
+        assert!(!long.verify(&public_key));
+    }
+}
+
+mod test_root_log {
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, RootLog};
+
+    use super::helpers::values;
+
+    // Entries 0 to 2 grow the tree by appending, entry 3 is a rebuild over other values
+    fn history() -> (RootLog, MerkleTree) {
+        let mut log = RootLog::new();
+        let mut tree = MerkleTree::new(&values(5), &SHA256);
+        assert_eq!(0, log.record(&tree));
+        tree.push(&"value 5");
+        assert_eq!(1, log.record(&tree));
+        tree.extend(values(13).split_off(6));
+        assert_eq!(2, log.record(&tree));
+        (log, tree)
+    }
+
+    #[test]
+    fn test_record_and_query() {
+        let (mut log, mut tree) = history();
+        tree.rebuild(&values(103)[100..]);
+        assert_eq!(3, log.record(&tree));
+
+        assert_eq!(4, log.len());
+        assert_eq!(3, log.latest().unwrap().sequence);
+        assert_eq!(tree.get_root(), &log.latest().unwrap().root[..]);
+        assert_eq!(6, log.get(1).unwrap().leaf_count);
+        assert_eq!(MerkleTree::new(&values(6), &SHA256).get_root(), &log.get(1).unwrap().root[..]);
+        assert!(log.get(4).is_none());
+        let sequences: Vec<u64> = log.iter().map(|e| e.sequence).collect();
+        assert_eq!(vec![0, 1, 2, 3], sequences);
+        assert_eq!(4, (&log).into_iter().count());
+        assert!(RootLog::new().latest().is_none());
+    }
+
+    #[test]
+    fn test_prove_consistent() {
+        let (mut log, mut tree) = history();
+        for &a in &[0, 1, 2] {
+            let proof = log.prove_consistent(a, 2, &tree).unwrap();
+            let old = log.get(a).unwrap();
+            assert!(vmt::verify_consistency(&old.root, old.leaf_count, tree.get_root(), 13, &proof, &SHA256));
+        }
+        assert!(log.prove_consistent(2, 1, &tree).is_none());
+        assert!(log.prove_consistent(0, 1, &tree).is_none()); // The tree is past entry 1
+        assert!(log.prove_consistent(0, 5, &tree).is_none());
+
+        tree.rebuild(&values(120)[100..]);
+        log.record(&tree);
+        assert!(log.prove_consistent(2, 3, &tree).is_none()); // Not appended to
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let (log, _) = history();
+        let json = ::serde_json::to_string(&log).unwrap();
+        assert_eq!(log, ::serde_json::from_str::<RootLog>(&json).unwrap());
+    }
+}
+
+mod test_collect {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{MerkleTree, MerkleTreeCollector};
+
+    fn encode(i: usize) -> Vec<u8> {
+        format!("value {}", i).into_bytes()
+    }
+
+    #[test]
+    fn test_collect() {
+        for count in [0, 1, 2, 7, 100] {
+            let values: Vec<Vec<u8>> = (0..count).map(encode).collect();
+            let tree: MerkleTree = (0..count).map(encode).collect();
+            assert_eq!(MerkleTree::new(&values, &SHA256), tree);
+            assert_eq!(&SHA256, tree.algorithm());
+        }
+        let tree = ["a", "b", "c"].iter().collect::<MerkleTree>();
+        assert_eq!(MerkleTree::new(&["a", "b", "c"], &SHA256), tree);
+    }
+
+    #[test]
+    fn test_collector() {
+        let values: Vec<Vec<u8>> = (0..33).map(encode).collect();
+        let tree = MerkleTreeCollector(&SHA512).collect((0..33).map(encode));
+        assert_eq!(MerkleTree::new(&values, &SHA512), tree);
+        assert!(tree.build_proof(&encode(20)).is_some());
+        // Values of any iterator, filtered ones too
+        let odd = MerkleTreeCollector(&SHA512).collect(values.iter().skip(1).step_by(2));
+        assert_eq!(16, odd.leafs_count());
+    }
+}
+
+mod test_extend_trait {
+    use ring::digest::SHA256;
+
+    use vmt::MerkleTree;
+
+    fn append_all<C: Extend<Vec<u8>>>(collection: &mut C, count: usize) {
+        collection.extend((0..count).map(|i| format!("value {}", i).into_bytes()));
+    }
+
+    #[test]
+    fn test_extend_generic() {
+        for count in [0, 1, 2, 5, 64] {
+            let mut expected = vec![b"first".to_vec()];
+            append_all(&mut expected, count);
+            let mut tree = MerkleTree::new(&["first"], &SHA256);
+            append_all(&mut tree, count);
+            assert_eq!(MerkleTree::new(&expected, &SHA256), tree);
+        }
+    }
+
+    #[test]
+    fn test_extend_empty_tree() {
+        let mut tree = MerkleTree::new(&Vec::<Vec<u8>>::new(), &SHA256);
+        Extend::extend(&mut tree, vec!["a", "b", "c"]);
+        assert_eq!(MerkleTree::new(&["a", "b", "c"], &SHA256), tree);
+    }
+}
+
+mod test_index {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::MerkleTree;
+
+    const VALUES: [&str; 5] = ["a", "b", "c", "d", "e"];
+
+    #[test]
+    fn test_index_leafs() {
+        for algo in [&SHA256, &SHA512] {
+            let tree = MerkleTree::new(&VALUES, algo);
+            for (i, value) in VALUES.iter().enumerate() {
+                assert_eq!(algo.output_len, tree[i].len());
+                assert_eq!(tree.get_leaf_hash(i).unwrap(), &tree[i]);
+                assert_eq!(tree.build_proof(value).unwrap().leaf_hash(), &tree[i]);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "leaf index 5 is out of range for a tree of 5 leafs")]
+    fn test_index_padding() {
+        let tree = MerkleTree::new(&VALUES, &SHA256);
+        let _ = &tree[5]; // The duplicate of the last leaf
+    }
+
+    #[test]
+    #[should_panic(expected = "leaf index 0 is out of range for a tree of 0 leafs")]
+    fn test_index_empty() {
+        let tree = MerkleTree::new(&Vec::<&str>::new(), &SHA256);
+        let _ = &tree[0];
+    }
+}
+
+mod test_into_iter {
+    use ring::digest::SHA256;
+
+    use vmt::MerkleTree;
+
+    use super::helpers::values;
+
+    fn expected(values: &[String]) -> Vec<Vec<u8>> {
+        values.iter().map(|v| vmt::get_hash(v.as_bytes(), &SHA256).as_ref().to_vec()).collect()
+    }
+
+    #[test]
+    fn test_borrowed() {
+        for count in [0, 1, 5, 8] {
+            let values = values(count);
+            let tree = MerkleTree::new(&values, &SHA256);
+            let mut leafs = vec![];
+            for leaf in &tree {
+                leafs.push(leaf.to_vec());
+            }
+            assert_eq!(expected(&values), leafs);
+        }
+    }
+
+    #[test]
+    fn test_owned() {
+        for count in [0, 1, 5, 8] {
+            let values = values(count);
+            let leafs = MerkleTree::new(&values, &SHA256).into_iter();
+            assert_eq!(count, leafs.len());
+            assert_eq!(expected(&values), leafs.collect::<Vec<_>>());
+        }
+    }
+}
+
+mod test_level_sizes {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{LevelInfo, MerkleTree, MerkleTreeBuilder, PaddingStrategy};
+
+    use super::helpers::values;
+
+    fn tree(count: usize) -> MerkleTree {
+        let values = values(count);
+        MerkleTree::new(&values, &SHA256)
+    }
+
+    // (logical, physical) nodes per level
+    const TABLES: [&[(usize, usize)]; 9] = [
+        &[(1, 2), (1, 1)],
+        &[(2, 2), (1, 1)],
+        &[(3, 4), (2, 2), (1, 1)],
+        &[(4, 4), (2, 2), (1, 1)],
+        &[(5, 6), (3, 4), (2, 2), (1, 1)],
+        &[(6, 6), (3, 4), (2, 2), (1, 1)],
+        &[(7, 8), (4, 4), (2, 2), (1, 1)],
+        &[(8, 8), (4, 4), (2, 2), (1, 1)],
+        &[(9, 10), (5, 6), (3, 4), (2, 2), (1, 1)],
+    ];
+
+    #[test]
+    fn test_tables() {
+        for (i, table) in TABLES.iter().enumerate() {
+            let sizes: Vec<(usize, usize)> = tree(i + 1).level_sizes().iter().map(|l| (l.logical_nodes, l.physical_nodes)).collect();
+            assert_eq!(table.to_vec(), sizes, "{} leafs", i + 1);
+        }
+        assert!(tree(0).level_sizes().is_empty());
+        assert_eq!(LevelInfo { logical_nodes: 3, physical_nodes: 4, offset_bytes: 16 * 32 }, tree(9).level_sizes()[2]);
+        assert!(tree(9).level_sizes()[2].is_padded());
+        assert!(!tree(9).level_sizes()[3].is_padded());
+    }
+
+    #[test]
+    fn test_tiling() {
+        for count in 0..34 {
+            let values = values(count);
+            let promoted = MerkleTreeBuilder::new().algorithm(&SHA256).padding(PaddingStrategy::PromoteOdd).build(&values).unwrap();
+            for tree in [MerkleTree::new(&values, &SHA512), promoted] {
+                let len = tree.get_root().len();
+                let mut offset = 0;
+                for (level, info) in tree.level_sizes().iter().enumerate() {
+                    assert_eq!(offset, info.offset_bytes);
+                    assert_eq!(tree.level(level).unwrap(), &tree.as_bytes()[offset..offset + info.logical_nodes * len]);
+                    offset += info.physical_nodes * len;
+                }
+                assert_eq!(tree.as_bytes().len(), offset);
+                assert_eq!(tree.data_size(), offset);
+                assert_eq!(tree.nodes_count() * len, offset);
+            }
+        }
+    }
+}
+
+mod test_size_helpers {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{CapacityError, MerkleTree};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_match_trees() {
+        for count in 0..=64 {
+            let values = values(count);
+            for algo in [&SHA256, &SHA512] {
+                let tree = MerkleTree::new(&values, algo);
+                assert_eq!(tree.height(), vmt::height_for(count), "{} leafs", count);
+                assert_eq!(tree.nodes_count(), vmt::nodes_for(count), "{} leafs", count);
+                assert_eq!(Ok(tree.data_size()), vmt::data_size_for(count, algo), "{} leafs", count);
+            }
+        }
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(Err(CapacityError { leafs: usize::MAX / 8, digest_len: 32 }), vmt::data_size_for(usize::MAX / 8, &SHA256));
+        assert_eq!(MerkleTree::required_size(1000, &SHA512), vmt::data_size_for(1000, &SHA512));
+        assert!(vmt::height_for(usize::MAX) > 60);
+        assert!(vmt::nodes_for(usize::MAX / 4) > usize::MAX / 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "take more than")]
+    fn test_nodes_overflow() {
+        vmt::nodes_for(usize::MAX);
+    }
+}
+
+mod test_digest_alg {
+    use ring::digest::{SHA1, SHA256, SHA384, SHA512, SHA512_256};
+
+    use vmt::{DigestAlg, MerkleTree, MerkleTreeBuilder, MerkleTreeView, NamedHasher, Proof, UnknownAlgorithm};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Ok(DigestAlg::Sha384), "sha384".parse());
+        assert_eq!(Ok(DigestAlg::Sha512_256), "SHA512_256".parse());
+        for name in ["sha1", "sha256", "sha384", "sha512", "sha512_256"] {
+            assert_eq!(name, name.parse::<DigestAlg>().unwrap().name());
+        }
+        assert_eq!(SHA384.output_len, DigestAlg::Sha384.algorithm().output_len);
+    }
+
+    #[test]
+    fn test_unknown_name() {
+        let err = "md5".parse::<DigestAlg>().unwrap_err();
+        assert_eq!(UnknownAlgorithm { name: "md5".to_string() }, err);
+        assert_eq!("unknown digest algorithm 'md5'", err.to_string());
+        assert!("".parse::<DigestAlg>().is_err());
+        assert!("sha-256".parse::<DigestAlg>().is_err());
+    }
+
+    #[test]
+    fn test_same_trees() {
+        let values = vec!["one", "two", "three", "four", "five"];
+        let pairs = [(DigestAlg::Sha1, &SHA1), (DigestAlg::Sha256, &SHA256), (DigestAlg::Sha384, &SHA384),
+            (DigestAlg::Sha512, &SHA512), (DigestAlg::Sha512_256, &SHA512_256)];
+        for pair in pairs.iter() {
+            let (alg, algo) = *pair;
+            let tree = MerkleTree::new(&values, alg);
+            let expected = MerkleTree::new(&values, algo);
+            assert_eq!(expected.get_root(), tree.get_root());
+            assert_eq!(alg.name(), tree.hasher().name());
+            let built = MerkleTreeBuilder::new().algorithm(alg).build(&values).unwrap();
+            assert_eq!(expected.get_root(), built.get_root());
+        }
+    }
+
+    #[test]
+    fn test_from_string() {
+        let values = vec!["one", "two", "three"];
+        let alg: DigestAlg = "sha384".parse().unwrap();
+        let tree = MerkleTree::new_with_map(&values, alg);
+        assert_eq!(48, tree.get_root().len());
+        let proof = tree.build_proof(&"two").unwrap();
+        assert!(tree.validate(&proof));
+        assert!(vmt::verify_value(tree.get_root(), &"two", proof.as_bytes(), alg));
+        assert!(vmt::verify_proof(tree.get_root(), proof.as_bytes(), alg));
+        assert_eq!(Ok(proof.clone()), vmt::hex::decode_proof(&proof.to_hex(), alg));
+        assert_eq!(Ok(proof.clone()), Proof::from_compact_bytes(&proof.to_compact_bytes(), alg));
+        assert_eq!(Ok(tree.data_size()), vmt::data_size_for(values.len(), alg));
+        assert_eq!(proof.as_bytes().len(), vmt::proof_len_for(values.len(), alg));
+
+        let loaded = MerkleTree::from_bytes(tree.as_bytes(), values.len(), alg).unwrap();
+        assert_eq!(tree.get_root(), loaded.get_root());
+        let view = MerkleTreeView::new(tree.as_bytes(), values.len(), alg).unwrap();
+        assert_eq!(tree.get_root(), view.get_root());
+    }
+}
+
+mod test_digest_arrays {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::MerkleTree;
+
+    #[test]
+    fn test_sha256() {
+        let values = vec!["one", "two", "three"];
+        let tree = MerkleTree::new(&values, &SHA256);
+        let root: [u8; 32] = tree.root_array().unwrap();
+        assert_eq!(tree.get_root(), &root[..]);
+        assert_eq!(None, tree.root_array::<64>());
+        assert_eq!(None, tree.root_array::<0>());
+
+        let leaf = tree.get_leaf_hash_array::<32>(1).unwrap();
+        assert_eq!(tree.get_leaf_hash(1).unwrap(), &leaf[..]);
+        assert_eq!(None, tree.get_leaf_hash_array::<32>(3));
+        assert_eq!(None, tree.get_leaf_hash_array::<64>(1));
+
+        let proof = tree.build_proof(&"two").unwrap();
+        let hashes = proof.hashes_array::<32>().unwrap();
+        assert_eq!(proof.len(), hashes.len());
+        assert_eq!(proof.leaf_hash(), &hashes[0][..]);
+        assert_eq!(proof.as_bytes(), &hashes.concat()[..]);
+        assert_eq!(None, proof.hashes_array::<64>());
+    }
+
+    #[test]
+    fn test_sha512() {
+        let values = vec!["one", "two", "three", "four", "five"];
+        let tree = MerkleTree::new(&values, &SHA512);
+        assert_eq!(tree.get_root(), &tree.root_array::<64>().unwrap()[..]);
+        assert_eq!(None, tree.root_array::<32>());
+        assert_eq!(tree.get_leaf_hash(4).unwrap(), &tree.get_leaf_hash_array::<64>(4).unwrap()[..]);
+        assert_eq!(None, tree.get_leaf_hash_array::<32>(4));
+
+        let proof = tree.build_proof(&"four").unwrap();
+        let hashes = proof.hashes_array::<64>().unwrap();
+        assert_eq!(proof.as_bytes(), &hashes.concat()[..]);
+        assert_eq!(None, proof.hashes_array::<32>());
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = MerkleTree::new(&Vec::<String>::new(), &SHA256);
+        assert_eq!(None, tree.root_array::<32>());
+        assert_eq!(None, tree.get_leaf_hash_array::<32>(0));
+    }
+}
+
+mod test_root_keys {
+    use std::collections::{BTreeMap, HashMap};
+
+    use ring::digest::SHA256;
+
+    use vmt::{MerkleTree, Root};
+
+    use super::helpers::values;
+
+    fn roots() -> Vec<Root> {
+        let values = values(10);
+        (1..=values.len()).map(|count| MerkleTree::new(&values[..count], &SHA256).root()).collect()
+    }
+
+    #[test]
+    fn test_hash_map() {
+        let roots = roots();
+        let map: HashMap<Root, usize> = roots.iter().cloned().zip(1..).collect();
+        assert_eq!(roots.len(), map.len());
+        for (count, root) in (1..).zip(roots.iter()) {
+            assert_eq!(Some(&count), map.get(root.as_bytes()));
+            assert_eq!(Some(&count), map.get(root));
+        }
+        assert_eq!(None, map.get(&[0; 32][..]));
+        assert_eq!(None, map.get(&roots[0].as_bytes()[..16]));
+    }
+
+    #[test]
+    fn test_btree_map() {
+        let roots = roots();
+        let map: BTreeMap<Root, usize> = roots.iter().cloned().zip(1..).collect();
+        assert_eq!(roots.len(), map.len());
+        for (count, root) in (1..).zip(roots.iter()) {
+            assert_eq!(Some(&count), map.get(root.as_bytes()));
+        }
+        let keys: Vec<&[u8]> = map.keys().map(|root| root.as_bytes()).collect();
+        let mut sorted: Vec<&[u8]> = roots.iter().map(|root| root.as_bytes()).collect();
+        sorted.sort();
+        assert_eq!(sorted, keys);
+        assert_eq!(None, map.get(&[0; 32][..]));
+    }
+
+    #[test]
+    fn test_order() {
+        let low = Root::new(&[1; 32], 32);
+        let high = Root::new(&[2; 32], 32);
+        assert!(low < high);
+        assert_eq!(low.clone(), low.clone().max(Root::new(&[1; 32], 32)));
+        assert_eq!(high.clone(), low.max(high));
+    }
+
+    #[test]
+    #[should_panic(expected = "different digest lengths")]
+    fn test_order_of_different_lengths() {
+        let _ = Root::new(&[1; 32], 32) < Root::new(&[1; 64], 64);
+    }
+}
+
+mod test_prefix_range {
+    use ring::digest::{digest, SHA256};
+
+    use vmt::SortedMerkleTree;
+
+    use super::helpers::values;
+
+    fn keys(values: &[String]) -> Vec<Vec<u8>> {
+        let mut keys: Vec<Vec<u8>> = values.iter().map(|v| digest(&SHA256, v.as_bytes()).as_ref().to_vec()).collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_ranges() {
+        let values = values(1000);
+        let keys = keys(&values);
+        let tree = SortedMerkleTree::new(&values, &SHA256);
+        for byte in 0..=255u8 {
+            let range = tree.range(&[byte]);
+            let expected: Vec<usize> = (0..keys.len()).filter(|&i| keys[i][0] == byte).collect();
+            assert_eq!(expected, range.clone().collect::<Vec<_>>(), "prefix {}", byte);
+            assert_eq!(keys.iter().filter(|k| k[0] < byte).count(), range.start);
+        }
+        assert_eq!(0..1000, tree.range(&[]));
+        for i in [0, 1, 500, 999] {
+            assert_eq!(i..i + 1, tree.range(&keys[i]));
+        }
+    }
+
+    #[test]
+    fn test_proofs() {
+        let values = values(1000);
+        let keys = keys(&values);
+        let tree = SortedMerkleTree::new(&values, &SHA256);
+        let byte = (0..=255u8).find(|&b| keys.iter().filter(|k| k[0] == b).count() > 3).unwrap();
+        for prefix in [&[byte][..], &keys[10][..2], &keys[10][..], &keys[999][..]] {
+            let proof = tree.prove_range_by_prefix(prefix).unwrap();
+            assert_eq!(tree.range(prefix), proof.range);
+            assert!(proof.keys.iter().all(|key| key.starts_with(prefix)));
+            assert!(tree.validate_prefix_range(prefix, &proof));
+
+            let mut tampered = proof.clone();
+            tampered.keys[0][31] ^= 1;
+            assert!(!tree.validate_prefix_range(prefix, &tampered));
+            let mut shifted = proof.clone();
+            shifted.range = proof.range.start + 1..proof.range.end + 1;
+            assert!(!tree.validate_prefix_range(prefix, &shifted));
+        }
+        let proof = tree.prove_range_by_prefix(&[byte]).unwrap();
+        assert!(proof.keys.len() > 3);
+        assert!(!tree.validate_prefix_range(&[byte.wrapping_add(1)], &proof));
+        let full = tree.prove_range_by_prefix(&[]).unwrap();
+        assert_eq!(keys, full.keys);
+        assert!(tree.validate_prefix_range(&[], &full));
+    }
+
+    #[test]
+    fn test_no_match() {
+        let values = values(30);
+        let keys = keys(&values);
+        let tree = SortedMerkleTree::new(&values, &SHA256);
+        let byte = (0..=255u8).find(|&b| keys.iter().all(|k| k[0] != b)).unwrap();
+        let range = tree.range(&[byte]);
+        assert!(range.is_empty());
+        assert_eq!(keys.iter().filter(|k| k[0] < byte).count(), range.start);
+        assert_eq!(None, tree.prove_range_by_prefix(&[byte]));
+
+        let mut long = keys[5].clone();
+        long.push(0);
+        assert_eq!(6..6, tree.range(&long));
+        assert_eq!(None, tree.prove_range_by_prefix(&long));
+
+        let empty = SortedMerkleTree::new(&Vec::<String>::new(), &SHA256);
+        assert_eq!(0..0, empty.range(&[]));
+        assert_eq!(None, empty.prove_range_by_prefix(&[]));
+    }
+}
+
+mod test_leaf_stats {
+    use std::collections::BTreeMap;
+
+    use ring::digest::SHA256;
+
+    use vmt::{LeafStats, MerkleTree, MerkleTreeBuilder};
+
+    use super::helpers::Constant;
+
+    // a once, b twice, c and d three times, e once, f five times
+    fn values() -> Vec<&'static str> {
+        vec!["c", "a", "b", "f", "d", "c", "f", "b", "d", "f", "e", "c", "f", "d", "f"]
+    }
+
+    fn expected() -> LeafStats {
+        let histogram: BTreeMap<usize, usize> = vec![(1, 2), (2, 1), (3, 2), (5, 1)].into_iter().collect();
+        LeafStats { leafs: 15, distinct: 6, max_multiplicity: 5, histogram }
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let values = values();
+        let plain = MerkleTree::new(&values, &SHA256);
+        let mapped = MerkleTree::new_with_map(&values, &SHA256);
+        // Every hash has the same key, so the map groups all leafs together
+        let colliding = MerkleTreeBuilder::new().algorithm(&SHA256).map_hasher(Constant).build(&values).unwrap();
+        for tree in [&plain, &mapped, &colliding] {
+            let stats = tree.stats();
+            assert_eq!(expected(), stats);
+            assert!(stats.has_duplicates());
+        }
+    }
+
+    #[test]
+    fn test_distinct() {
+        let values: Vec<String> = (0..9).map(|i| format!("value {}", i)).collect();
+        for tree in [MerkleTree::new(&values, &SHA256), MerkleTree::new_with_map(&values, &SHA256)] {
+            let stats = tree.stats();
+            assert_eq!(9, stats.leafs);
+            assert_eq!(9, stats.distinct);
+            assert_eq!(1, stats.max_multiplicity);
+            assert_eq!(vec![(1, 9)], stats.histogram.into_iter().collect::<Vec<_>>());
+        }
+        let single = MerkleTree::new(&["one"], &SHA256).stats();
+        assert_eq!((1, 1, 1), (single.leafs, single.distinct, single.max_multiplicity));
+        assert!(!single.has_duplicates());
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let values: Vec<String> = vec![];
+        for tree in [MerkleTree::new(&values, &SHA256), MerkleTree::new_with_map(&values, &SHA256)] {
+            let stats = tree.stats();
+            assert_eq!(LeafStats::default(), stats);
+            assert_eq!(0, stats.max_multiplicity);
+            assert!(stats.histogram.is_empty());
+            assert!(!stats.has_duplicates());
+        }
+    }
+}
+
+mod test_proof_format {
+    use ring::digest::SHA256;
+
+    use vmt::{Proof, ProofFormatError};
+
+    #[test]
+    fn test_from_bytes_malformed() {
+        let len = SHA256.output_len;
+        assert_eq!(Err(ProofFormatError::TooShort { hashes: 0 }), Proof::from_bytes(&[], len));
+        assert_eq!(Err(ProofFormatError::TooShort { hashes: 1 }), Proof::from_bytes(&vec![0; len], len));
+        assert_eq!(Err(ProofFormatError::NotAligned { len: 2 * len + 1, digest_len: len }),
+                   Proof::from_bytes(&vec![0; 2 * len + 1], len));
+        assert_eq!(Err(ProofFormatError::ZeroDigestLen), Proof::from_bytes(&[1, 2, 3], 0));
+        assert!(Proof::from_bytes(&vec![0; 2 * len], len).is_ok());
+    }
+
+    #[test]
+    fn test_from_bytes_accessors() {
+        let mut bytes = vec![1u8; 32];
+        bytes.extend_from_slice(&[2u8; 32]);
+        bytes.extend_from_slice(&[3u8; 32]);
+        let proof = Proof::from_bytes(&bytes, 32).unwrap();
+
+        assert_eq!(3, proof.len());
+        assert_eq!(&[1u8; 32][..], proof.leaf_hash());
+        let siblings: Vec<&[u8]> = proof.siblings().collect();
+        assert_eq!(vec![&[2u8; 32][..], &[3u8; 32][..]], siblings);
+        assert_eq!(bytes, proof.into_bytes());
+    }
+
+    #[test]
+    fn test_to_openzeppelin() {
+        let mut bytes = vec![1u8; 32];
+        bytes.extend_from_slice(&[2u8; 32]);
+        bytes.extend_from_slice(&[3u8; 32]);
+        let proof = Proof::from_bytes(&bytes, 32).unwrap();
+        assert_eq!(Some(vec![[2u8; 32], [3u8; 32]]), proof.to_openzeppelin());
+        assert_eq!(None, Proof::from_bytes(&[0u8; 128], 64).unwrap().to_openzeppelin());
+    }
+}
+
+mod test_compact_proof {
+    use ring::digest::{SHA256, SHA512};
+
+    use vmt::{CompactError, MerkleTree, Position, Proof, ProofFormatError};
+
+    use super::helpers::values;
+
+    #[test]
+    fn test_round_trip() {
+        for count in 1..40 {
+            let values = values(count);
+            for tree in [MerkleTree::new(&values, &SHA256), MerkleTree::new_rfc6962(&values, &SHA256)] {
+                for index in 0..count {
+                    let proof = tree.build_proof_by_index(index).unwrap();
+                    let bytes = proof.to_compact_bytes();
+                    let decoded = Proof::from_compact_bytes(&bytes, &SHA256).unwrap();
+                    assert_eq!(proof, decoded, "{} of {}", index, count);
+                    assert!(tree.validate(&decoded));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_layout() {
+        let values = values(11);
+        let tree = MerkleTree::new_rfc6962(&values, &SHA512);
+        let proof = tree.build_proof_by_index(6).unwrap();
+        let siblings = proof.len() - 1;
+        let bytes = proof.to_compact_bytes();
+        assert_eq!(1 + 1 + 1 + proof.as_bytes().len(), bytes.len());
+        assert_eq!(1, bytes[0]);
+        assert_eq!((siblings << 1 | 1) as u8, bytes[1]);
+        for (i, &position) in proof.positions().unwrap().iter().enumerate() {
+            assert_eq!(position == Position::Left, bytes[2] >> i & 1 == 1);
+        }
+        assert_eq!(proof.as_bytes(), &bytes[3..]);
+        assert_eq!(proof, Proof::from_compact_bytes_with_len(&bytes, 64).unwrap());
+
+        let sorted = MerkleTree::new(&values, &SHA512).build_proof_by_index(6).unwrap();
+        let bytes = sorted.to_compact_bytes();
+        assert_eq!(((sorted.len() - 1) << 1) as u8, bytes[1]);
+        assert_eq!(sorted.as_bytes(), &bytes[2..]);
+    }
+
+    #[test]
+    fn test_long_count() {
+        let mut bytes = vec![0u8; 201 * 32];
+        bytes[0] = 1;
+        let proof = Proof::from_bytes(&bytes, 32).unwrap();
+        let compact = proof.to_compact_bytes();
+        assert_eq!(&[1, 0x90, 0x03], &compact[..3]); // 400 as a varint
+        assert_eq!(proof, Proof::from_compact_bytes(&compact, &SHA256).unwrap());
+    }
+
+    #[test]
+    fn test_malformed() {
+        let tree = MerkleTree::new_rfc6962(&values(5), &SHA256);
+        let bytes = tree.build_proof_by_index(2).unwrap().to_compact_bytes();
+        let decode = |bytes: &[u8]| Proof::from_compact_bytes(bytes, &SHA256);
+
+        assert_eq!(Err(CompactError::Truncated), decode(&[]));
+        assert_eq!(Err(CompactError::Truncated), decode(&[1]));
+        assert_eq!(Err(CompactError::Truncated), decode(&bytes[..bytes.len() - 1]));
+        assert_eq!(Err(CompactError::Truncated), decode(&bytes[..2]));
+        assert_eq!(Err(CompactError::UnsupportedVersion(2)), decode(&[&[2], &bytes[1..]].concat()));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(Err(CompactError::TrailingBytes { len: 1 }), decode(&trailing));
+        assert_eq!(Err(CompactError::TrailingBytes { len: 32 }), decode(&[&bytes[..], &[0; 32]].concat()));
+
+        let mut padding = bytes.clone();
+        padding[2] |= 0x80;
+        assert_eq!(Err(CompactError::PaddingBits), decode(&padding));
+
+        // Overlong and oversized sibling counts
+        assert_eq!(Err(CompactError::BadVarint), decode(&[1, 0x86, 0x00]));
+        assert_eq!(Err(CompactError::BadVarint), decode(&[1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02]));
+        assert_eq!(Err(CompactError::BadVarint), decode(&[&[1], &[0x80; 10][..]].concat()));
+        assert_eq!(Err(CompactError::Truncated), decode(&[1, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]));
+
+        // A digest length other than the one of the proof never takes the bytes exactly
+        assert!(Proof::from_compact_bytes(&bytes, &SHA512).is_err());
+        assert_eq!(Err(CompactError::Format(ProofFormatError::TooShort { hashes: 1 })), decode(&[&[1, 0], &[0; 32][..]].concat()));
+        assert_eq!(Err(CompactError::Format(ProofFormatError::ZeroDigestLen)), Proof::from_compact_bytes_with_len(&[1, 2], 0));
+        assert_eq!("2 bytes follow the compact proof", CompactError::TrailingBytes { len: 2 }.to_string());
+    }
+
+    #[test]
+    fn test_single_leaf() {
+        let tree = MerkleTree::new_rfc6962(&values(1), &SHA256);
+        let proof = tree.build_proof_by_index(0).unwrap();
+        let bytes = proof.to_compact_bytes();
+        assert_eq!(&[1, 1], &bytes[..2]);
+        let decoded = Proof::from_compact_bytes(&bytes, &SHA256).unwrap();
+        assert_eq!(proof, decoded);
+        assert!(tree.validate(&decoded));
+    }
+}
+
 mod test_with_map {
     test_tree!(new_with_map);
 }
@@ -0,0 +1,33 @@
+// Runs in the browser or node with wasm-pack test --node -- --features wasm
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+extern crate js_sys;
+extern crate vmt;
+extern crate wasm_bindgen;
+extern crate wasm_bindgen_test;
+
+use js_sys::Uint8Array;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use vmt::wasm::{verify, WasmMerkleTree};
+
+fn values() -> Vec<Uint8Array> {
+    ["one", "two", "three"].iter().map(|v| Uint8Array::from(v.as_bytes())).collect()
+}
+
+#[wasm_bindgen_test]
+fn test_proof_round_trip() {
+    let tree = WasmMerkleTree::new(values(), "sha256").unwrap();
+    let root = tree.root();
+    assert_eq!(32, root.length());
+    let proof = tree.build_proof(Uint8Array::from(&b"two"[..])).unwrap();
+    assert!(verify(root.clone(), proof.clone(), "sha256").unwrap());
+    assert!(!verify(root, proof, "sha512").unwrap());
+    assert!(tree.build_proof(Uint8Array::from(&b"four"[..])).is_none());
+}
+
+#[wasm_bindgen_test]
+fn test_unknown_algorithm() {
+    assert!(WasmMerkleTree::new(values(), "md5").is_err());
+    assert!(verify(Uint8Array::new_with_length(32), Uint8Array::new_with_length(32), "md5").is_err());
+}